@@ -0,0 +1,64 @@
+// Rc/Weak tree against the arena tree: building a tree and walking it.
+// cargo bench --bench trees
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use rust_book::chapters::chapter_15_4_thru_6::arena::{Arena, NodeId};
+use rust_book::chapters::chapter_15_4_thru_6::tree::Tree;
+
+// every node gets FAN_OUT children until there are `size` nodes, filled a level at a time
+const FAN_OUT: usize = 4;
+const SIZES: [usize; 3] = [1_000, 10_000, 100_000];
+
+fn build_rc(size: usize) -> Tree<u64> {
+    let root = Tree::new(0);
+    let mut parents = vec![root.clone()];
+    for n in 1..size {
+        let child = parents[(n - 1) / FAN_OUT].add_child(n as u64);
+        parents.push(child);
+    }
+    root
+}
+
+fn build_arena(size: usize) -> (Arena<u64>, NodeId) {
+    let mut arena = Arena::with_capacity(size);
+    let root = arena.new_node(0);
+    let mut parents = vec![root];
+    for n in 1..size {
+        let child = arena
+            .add_child(parents[(n - 1) / FAN_OUT], n as u64)
+            .unwrap();
+        parents.push(child);
+    }
+    (arena, root)
+}
+
+fn build(c: &mut Criterion) {
+    let mut group = c.benchmark_group("build");
+    for size in SIZES {
+        group.bench_with_input(BenchmarkId::new("rc", size), &size, |b, &size| {
+            b.iter(|| build_rc(black_box(size)))
+        });
+        group.bench_with_input(BenchmarkId::new("arena", size), &size, |b, &size| {
+            b.iter(|| build_arena(black_box(size)))
+        });
+    }
+    group.finish();
+}
+
+fn traverse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("depth_first");
+    for size in SIZES {
+        let tree = build_rc(size);
+        group.bench_with_input(BenchmarkId::new("rc", size), &tree, |b, tree| {
+            b.iter(|| tree.depth_first().map(|node| *node.value()).sum::<u64>())
+        });
+        let (arena, root) = build_arena(size);
+        group.bench_with_input(BenchmarkId::new("arena", size), &arena, |b, arena| {
+            b.iter(|| arena.depth_first(root).map(|id| arena[id]).sum::<u64>())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, build, traverse);
+criterion_main!(benches);
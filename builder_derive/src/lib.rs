@@ -0,0 +1,305 @@
+//! # builder_derive
+//!
+//! `#[derive(Builder)]` writes the builder that `Dave` in async_rust/chapter_3 does by hand.
+//!
+//! ```ignore
+//! #[derive(Builder)]
+//! #[builder(validate = Dave::check)]
+//! struct Dave {
+//!     built: u8,            // required
+//!     sex: Sex,             // required
+//!     age: Option<u8>,      // optional, None unless set
+//!     #[builder(default = 170)]
+//!     height: u8,           // optional, 170 unless set
+//! }
+//!
+//! let dave = Dave::builder().built(8).sex(Sex::Male).age(40).build()?;
+//! ```
+//!
+//! Every required field has to be set exactly once before `build` exists: the builder carries
+//! one type parameter per required field, `()` until it's set and `(T,)` after, and `build` is
+//! only implemented once they're all `(T,)`. Forgetting one is a compile error, not a panic.
+//!
+//! `build` returns `Result<T, String>`, running the `validate` function if there is one. It
+//! takes `&T` and returns `Result<(), E>` for any `E: ToString`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Expr, Fields, GenericArgument, Ident, Path,
+    PathArguments, Type,
+};
+
+#[proc_macro_derive(Builder, attributes(builder))]
+pub fn derive_builder(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+enum Kind {
+    /// Must be set, tracked in the builder's type.
+    Required,
+    /// An `Option<T>` field, the setter takes the `T`.
+    Optional(Type),
+    /// Falls back to `Default::default()` or the given expression.
+    Default(Option<Expr>),
+}
+
+struct Field {
+    name: Ident,
+    ty: Type,
+    kind: Kind,
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "Builder doesn't support generic structs",
+        ));
+    }
+    let named = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(named) => &named.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "Builder needs a struct with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "Builder only works on structs",
+            ))
+        }
+    };
+
+    let mut fields = vec![];
+    for field in named {
+        let default = field_default(&field.attrs)?;
+        let kind = match (default, option_inner(&field.ty)) {
+            (Some(default), _) => Kind::Default(default),
+            (None, Some(inner)) => Kind::Optional(inner.clone()),
+            (None, None) => Kind::Required,
+        };
+        fields.push(Field {
+            name: field.ident.clone().unwrap(),
+            ty: field.ty.clone(),
+            kind,
+        });
+    }
+    let validate = struct_validate(&input.attrs)?;
+
+    let vis = &input.vis;
+    let name = &input.ident;
+    let builder = format_ident!("{}Builder", name);
+
+    // one state parameter per required field, by position in `fields`
+    let states: Vec<Option<Ident>> = fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| match field.kind {
+            Kind::Required => Some(format_ident!("__S{}", i)),
+            _ => None,
+        })
+        .collect();
+    let all_states: Vec<&Ident> = states.iter().flatten().collect();
+
+    let builder_fields = fields.iter().zip(&states).map(|(field, state)| {
+        let field_name = &field.name;
+        match (&field.kind, state) {
+            (Kind::Required, Some(state)) => quote! { #field_name: #state },
+            (Kind::Optional(inner), _) => quote! { #field_name: ::std::option::Option<#inner> },
+            _ => {
+                let ty = &field.ty;
+                quote! { #field_name: ::std::option::Option<#ty> }
+            }
+        }
+    });
+
+    let empty = fields.iter().map(|field| {
+        let field_name = &field.name;
+        match field.kind {
+            Kind::Required => quote! { #field_name: () },
+            _ => quote! { #field_name: ::std::option::Option::None },
+        }
+    });
+    let unset = all_states.iter().map(|_| quote! { () });
+
+    // a setter per required field, only there while that field is still ()
+    let required_setters = fields.iter().enumerate().filter_map(|(i, field)| {
+        if !matches!(field.kind, Kind::Required) {
+            return None;
+        }
+        let field_name = &field.name;
+        let ty = &field.ty;
+        let this = states[i].as_ref().unwrap();
+        let others = all_states.iter().filter(|state| **state != this);
+        let before = all_states
+            .iter()
+            .map(|state| if *state == this { quote! { () } } else { quote! { #state } });
+        let after = all_states
+            .iter()
+            .map(|state| if *state == this { quote! { (#ty,) } } else { quote! { #state } });
+        let moved = fields.iter().map(|other| {
+            let other_name = &other.name;
+            if other_name == field_name {
+                quote! { #other_name: (value.into(),) }
+            } else {
+                quote! { #other_name: self.#other_name }
+            }
+        });
+        let doc = format!("Sets `{}`, which is required.", field_name);
+        Some(quote! {
+            #[allow(dead_code)]
+            impl<#(#others),*> #builder<#(#before),*> {
+                #[doc = #doc]
+                #vis fn #field_name(self, value: impl ::std::convert::Into<#ty>) -> #builder<#(#after),*> {
+                    #builder { #(#moved),* }
+                }
+            }
+        })
+    });
+
+    let optional_setters = fields.iter().filter_map(|field| {
+        let field_name = &field.name;
+        let (ty, doc) = match &field.kind {
+            Kind::Required => return None,
+            Kind::Optional(inner) => (inner, format!("Sets `{}`, `None` if not set.", field_name)),
+            Kind::Default(_) => (
+                &field.ty,
+                format!("Sets `{}`, which has a default.", field_name),
+            ),
+        };
+        Some(quote! {
+            #[doc = #doc]
+            #vis fn #field_name(mut self, value: impl ::std::convert::Into<#ty>) -> Self {
+                self.#field_name = ::std::option::Option::Some(value.into());
+                self
+            }
+        })
+    });
+
+    let set_types = fields
+        .iter()
+        .filter(|field| matches!(field.kind, Kind::Required))
+        .map(|field| {
+            let ty = &field.ty;
+            quote! { (#ty,) }
+        });
+    let finished = fields.iter().map(|field| {
+        let field_name = &field.name;
+        match &field.kind {
+            Kind::Required => quote! { #field_name: self.#field_name.0 },
+            Kind::Optional(_) => quote! { #field_name: self.#field_name },
+            Kind::Default(None) => {
+                quote! { #field_name: self.#field_name.unwrap_or_default() }
+            }
+            Kind::Default(Some(expr)) => {
+                quote! { #field_name: self.#field_name.unwrap_or_else(|| #expr) }
+            }
+        }
+    });
+    let check = validate.map(|validate| {
+        quote! {
+            #validate(&value).map_err(|e| ::std::string::ToString::to_string(&e))?;
+        }
+    });
+
+    let builder_doc = format!(
+        "Builds a [`{}`]. Made by `{}::builder()`, `build` only exists once every required \
+         field has been set.",
+        name, name
+    );
+
+    Ok(quote! {
+        #[doc = #builder_doc]
+        #vis struct #builder<#(#all_states),*> {
+            #(#builder_fields),*
+        }
+
+        #[allow(dead_code)]
+        impl #name {
+            #vis fn builder() -> #builder<#(#unset),*> {
+                #builder { #(#empty),* }
+            }
+        }
+
+        #(#required_setters)*
+
+        #[allow(dead_code)]
+        impl<#(#all_states),*> #builder<#(#all_states),*> {
+            #(#optional_setters)*
+        }
+
+        #[allow(dead_code)]
+        impl #builder<#(#set_types),*> {
+            /// Fills in the defaults and runs the validation, if there is one.
+            #vis fn build(self) -> ::std::result::Result<#name, ::std::string::String> {
+                let value = #name { #(#finished),* };
+                #check
+                ::std::result::Result::Ok(value)
+            }
+        }
+    })
+}
+
+/// `T` for a field typed `Option<T>`.
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let path = match ty {
+        Type::Path(path) if path.qself.is_none() => &path.path,
+        _ => return None,
+    };
+    let last = path.segments.last()?;
+    if last.ident != "Option" {
+        return None;
+    }
+    match &last.arguments {
+        PathArguments::AngleBracketed(args) if args.args.len() == 1 => match &args.args[0] {
+            GenericArgument::Type(inner) => Some(inner),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// `Some(None)` for `#[builder(default)]`, `Some(Some(expr))` for `#[builder(default = expr)]`.
+fn field_default(attrs: &[Attribute]) -> syn::Result<Option<Option<Expr>>> {
+    let mut default = None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("builder")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("default") {
+                default = Some(if meta.input.peek(syn::Token![=]) {
+                    Some(meta.value()?.parse()?)
+                } else {
+                    None
+                });
+                Ok(())
+            } else {
+                Err(meta.error("expected `default` or `default = ...`"))
+            }
+        })?;
+    }
+    Ok(default)
+}
+
+/// The function from `#[builder(validate = path)]` on the struct.
+fn struct_validate(attrs: &[Attribute]) -> syn::Result<Option<Path>> {
+    let mut validate = None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("builder")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("validate") {
+                validate = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `validate = path::to::function`"))
+            }
+        })?;
+    }
+    Ok(validate)
+}
@@ -0,0 +1,96 @@
+use builder_derive::Builder;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Sex {
+    Male,
+    Female,
+}
+
+#[derive(Debug, PartialEq, Builder)]
+#[builder(validate = Person::check)]
+struct Person {
+    name: String,
+    sex: Sex,
+    age: Option<u8>,
+    #[builder(default = 170)]
+    height: u8,
+    #[builder(default)]
+    tags: Vec<String>,
+}
+
+impl Person {
+    fn check(&self) -> Result<(), &'static str> {
+        if self.name.is_empty() {
+            return Err("name can't be empty");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Builder)]
+struct Settings {
+    verbose: Option<bool>,
+    #[builder(default = String::from("out"))]
+    dir: String,
+}
+
+#[test]
+fn required_fields_in_any_order() {
+    let a = Person::builder()
+        .name("Dave")
+        .sex(Sex::Male)
+        .build()
+        .unwrap();
+    let b = Person::builder()
+        .sex(Sex::Male)
+        .name(String::from("Dave"))
+        .build()
+        .unwrap();
+    assert_eq!(a, b);
+    assert_eq!(None, a.age);
+    assert_eq!(170, a.height);
+    assert!(a.tags.is_empty());
+}
+
+#[test]
+fn optional_fields_can_be_set_whenever() {
+    let person = Person::builder()
+        .age(30)
+        .name("Jo")
+        .height(180)
+        .sex(Sex::Female)
+        .tags(vec![String::from("x")])
+        .age(31)
+        .build()
+        .unwrap();
+    assert_eq!(Some(31), person.age);
+    assert_eq!(180, person.height);
+    assert_eq!(vec!["x"], person.tags);
+}
+
+#[test]
+fn build_validates() {
+    let err = Person::builder().name("").sex(Sex::Male).build();
+    assert_eq!(Err(String::from("name can't be empty")), err);
+}
+
+#[test]
+fn nothing_required() {
+    assert_eq!(
+        Settings {
+            verbose: None,
+            dir: String::from("out")
+        },
+        Settings::builder().build().unwrap()
+    );
+    assert_eq!(
+        Some(true),
+        Settings::builder().verbose(true).build().unwrap().verbose
+    );
+}
+
+#[test]
+fn compile_errors() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,9 @@
+use builder_derive::Builder;
+
+#[derive(Builder)]
+struct Dave {
+    #[builder(defualt)]
+    built: u8,
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use builder_derive::Builder;
+
+#[derive(Builder)]
+struct Dave {
+    built: u8,
+    age: Option<u8>,
+}
+
+fn main() {
+    // built was never set, so there is no build()
+    let _ = Dave::builder().age(3).build();
+}
@@ -0,0 +1,10 @@
+use builder_derive::Builder;
+
+#[derive(Builder)]
+struct Dave {
+    built: u8,
+}
+
+fn main() {
+    let _ = Dave::builder().built(1).built(2);
+}
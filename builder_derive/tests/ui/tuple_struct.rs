@@ -0,0 +1,6 @@
+use builder_derive::Builder;
+
+#[derive(Builder)]
+struct Dave(u8);
+
+fn main() {}
@@ -94,7 +94,12 @@ pub fn async_await() {
         Female
     }
 
-    #[derive(Debug)]
+    // Builder writes DaveBuilder for us, see builder_derive. built and sex have to be set, the
+    // Options start as None, and build() runs check
+    use builder_derive::Builder;
+
+    #[derive(Debug, Builder)]
+    #[builder(validate = Dave::check)]
     struct Dave {
         built: u8,
         sex: Sex,
@@ -118,6 +123,13 @@ pub fn async_await() {
             self
         }
 
+        fn check(&self) -> Result<(), String> {
+            match self.age {
+                Some(age) if age > 150 => Err(format!("{} is too old for a Dave", age)),
+                _ => Ok(()),
+            }
+        }
+
 
     }
 
@@ -126,4 +138,9 @@ pub fn async_await() {
 
     println!("{:?}", x);
 
+    // the same with the derived builder, leaving out built or sex won't compile
+    let y = Dave::builder().built(8).sex(Sex::Female).age(30).build();
+    println!("{:?}", y);
+    assert!(Dave::builder().built(8).sex(Sex::Male).age(200).build().is_err());
+
 }
\ No newline at end of file
@@ -2,11 +2,14 @@ use std::fs;
 use std::process;
 use std::error::Error;
 use std::env;
+use super::chapter_6::ip::{self, Cidr};
 
     pub struct Config {
         pub query: String,
         pub filename: String,
-        pub case_sensitive: bool
+        pub case_sensitive: bool,
+        // only lines with an address in this block, from ADDRESS_RANGE=10.0.0.0/8
+        pub address_range: Option<Cidr>
     }
 
     impl Config {
@@ -38,7 +41,16 @@ use std::env;
 
             let case_sensitive = env::var("CASE_INSENSITIVE").is_err();
 
-            Ok(Config { query, filename, case_sensitive })
+            // same idea as CASE_INSENSITIVE, set it in the environment to narrow the search
+            let address_range = match env::var("ADDRESS_RANGE") {
+                Ok(range) => match range.parse() {
+                    Ok(range) => Some(range),
+                    Err(_) => return Err("ADDRESS_RANGE isn't a CIDR block like 10.0.0.0/8")
+                },
+                Err(_) => None
+            };
+
+            Ok(Config { query, filename, case_sensitive, address_range })
         }
     }
 
@@ -51,12 +63,16 @@ use std::env;
     pub fn run(config: Config) -> Result<(), Box<dyn Error>> {
         let contents = fs::read_to_string(config.filename)?;
 
-        let results = if config.case_sensitive {
+        let mut results = if config.case_sensitive {
             search(&config.query, &contents)
         } else {
             search_case_insensitive(&config.query, &contents)
         };
 
+        if let Some(range) = &config.address_range {
+            results.retain(|line| in_range(range, line));
+        }
+
         for line in results {
             println!("{}", line);
         }
@@ -96,6 +112,18 @@ use std::env;
             .collect()
     }
 
+    // any address written in the line counts, so "10.0.0.1:8080" or "[::1]:80" too
+    fn in_range(range: &Cidr, line: &str) -> bool {
+        ip::find_addresses(line).iter().any(|addr| range.contains(addr))
+    }
+
+    pub fn search_addresses<'a>(range: &Cidr, contents: &'a str) -> Vec<&'a str> {
+        contents
+            .lines()
+            .filter(|line| in_range(range, line))
+            .collect()
+    }
+
     #[cfg(test)]
     mod tests {
         use super::*;
@@ -122,4 +150,19 @@ Trust me.";
 
             assert_eq!(vec!["Rust:", "Trust me."], search_case_insensitive(query, contents))
         }
+
+        #[test]
+        fn by_address_range() {
+            let range = "10.0.0.0/8".parse().unwrap();
+            let contents = "\
+10.1.2.3:443 GET /
+192.168.0.4 GET /admin
+[::1]:80 GET /
+10.200.0.1 POST /login";
+
+            assert_eq!(
+                vec!["10.1.2.3:443 GET /", "10.200.0.1 POST /login"],
+                search_addresses(&range, contents)
+            )
+        }
     }
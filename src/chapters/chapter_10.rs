@@ -1,5 +1,144 @@
+use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 
+// 10.2 follow ups that build on the Summary trait below
+pub mod feed;
+pub mod formats;
+pub mod stats;
+pub mod strategy;
+
+pub trait Summary {
+    fn summarize(&self) -> String;
+
+    fn summarize_author(&self) -> String;
+
+    // this creates a default which can call others within the trait
+    fn summarize_default(&self) -> String {
+        format!("(Read more {}...)", self.summarize_author())
+    }
+
+    /// Seconds since the unix epoch. Items that don't know when they were published sort last.
+    fn timestamp(&self) -> u64 {
+        0
+    }
+
+    /// True when the item only passes on something published elsewhere.
+    fn is_repost(&self) -> bool {
+        false
+    }
+
+    /// What a repost and its original have in common, used to spot duplicates in a feed.
+    fn dedup_key(&self) -> String {
+        self.summarize()
+    }
+
+    /// Which kind of item this is, so a strategy::Summarizer can pick a strategy per type.
+    fn kind(&self) -> &'static str {
+        "item"
+    }
+
+    /// Named fields for `{placeholder}` templates beyond the ones every Summary has.
+    fn field(&self, _name: &str) -> Option<String> {
+        None
+    }
+
+    fn summarize_with(&self, strategy: &strategy::Strategy) -> String
+    where
+        Self: Sized,
+    {
+        strategy.apply(self)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewsArticle {
+    pub headline: String,
+    pub location: String,
+    pub author: String,
+    pub content: String,
+    #[serde(default)]
+    pub timestamp: u64,
+}
+
+impl Summary for NewsArticle {
+    fn summarize(&self) -> String {
+        format!("{} {} {}", self.headline, self.author, self.location)
+    }
+
+    fn summarize_author(&self) -> String {
+        format!("{}", self.author)
+    }
+
+    fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    // syndicated copies of an article keep the headline
+    fn dedup_key(&self) -> String {
+        self.headline.trim().to_lowercase()
+    }
+
+    fn kind(&self) -> &'static str {
+        "article"
+    }
+
+    fn field(&self, name: &str) -> Option<String> {
+        match name {
+            "headline" => Some(self.headline.clone()),
+            "location" => Some(self.location.clone()),
+            "content" => Some(self.content.clone()),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tweet {
+    pub username: String,
+    pub content: String,
+    #[serde(default)]
+    pub reply: bool,
+    #[serde(default)]
+    pub retweet: bool,
+    #[serde(default)]
+    pub timestamp: u64,
+}
+
+impl Summary for Tweet {
+    fn summarize(&self) -> String {
+        format!("{} {}", self.username, self.content)
+    }
+
+    fn summarize_author(&self) -> String {
+        format!("{}", self.username)
+    }
+
+    fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    // a reply says something new so only retweets count as reposts
+    fn is_repost(&self) -> bool {
+        self.retweet && !self.reply
+    }
+
+    fn dedup_key(&self) -> String {
+        self.content.trim().to_lowercase()
+    }
+
+    fn kind(&self) -> &'static str {
+        "tweet"
+    }
+
+    fn field(&self, name: &str) -> Option<String> {
+        match name {
+            "username" => Some(self.username.clone()),
+            "content" => Some(self.content.clone()),
+            _ => None,
+        }
+    }
+}
+
 pub fn using_generic_data_types() {
     /*
     Similar to the way a function takes parameters with unknown values to run the same
@@ -210,57 +349,15 @@ pub fn traits_defining_shared_behavior() {
 
 
     */
-    // you see pub because I'm copying the examples and exist in lib.rs
-    pub trait Summary {
-        fn summarize(&self) -> String;
-
-        fn summarize_author(&self) -> String;
-
-        // this creates a default which can call others within the trait
-        fn summarize_default(&self) -> String {
-            format!("(Read more {}...)", self.summarize_author())
-        }
-    }
-
-    pub struct NewsArticle {
-        pub headline: String,
-        pub location: String,
-        pub author: String,
-        pub content: String,
-    }
-
-    impl Summary for NewsArticle {
-        fn summarize(&self) -> String {
-            format!("{} {} {}", self.headline, self.author, self.location)
-        }
-
-        fn summarize_author(&self) -> String {
-            format!("{}", self.author)
-        }
-    }
-
-    pub struct Tweet {
-        pub username: String,
-        pub content: String,
-        pub reply: bool,
-        pub retweet: bool,
-    }
-
-    impl Summary for Tweet {
-        fn summarize(&self) -> String {
-            format!("{} {}", self.username, self.content)
-        }
-
-        fn summarize_author(&self) -> String {
-            format!("{}", self.username)
-        }
-    }
+    // Summary, NewsArticle and Tweet used to be defined here, they now sit at the top of the
+    // module so the feed aggregator in chapter_10/feed.rs can use them
 
     let tweet = Tweet {
         username: String::from("horse_ebooks"),
         content: String::from("of course, as you probably already know, people"),
         reply: false,
         retweet: false,
+        timestamp: 0,
     };
 
     println!("1 new tweet: {}", tweet.summarize());
@@ -280,6 +377,7 @@ pub fn traits_defining_shared_behavior() {
             "The Pittsburgh Penguins once again are the best \
              hockey team in the NHL.",
         ),
+        timestamp: 0,
     };
 
     println!("New article available! {}", article.summarize_default());
@@ -362,6 +460,7 @@ pub fn traits_defining_shared_behavior() {
             content: String::from("of course, as you probably already know, people"),
             reply: false,
             retweet: false,
+            timestamp: 0,
         }
     }
 
@@ -414,6 +513,7 @@ pub fn traits_defining_shared_behavior() {
     // *** Fixing the largest Function with Trait Bounds
 
     
+    // panics on an empty list, see stats::max for one that returns an Option
     fn largest<T: PartialOrd + Copy>(list: &[T]) -> T {
         let mut largest = list[0];
 
@@ -0,0 +1,326 @@
+// A media aggregator over the Summary trait, the use case 10.2 opens with. A Feed takes any mix
+// of NewsArticle, Tweet or other Summary types, sorts them newest first, folds reposts into the
+// item they repost and renders the result as a plain text or HTML digest.
+
+use super::Summary;
+use std::collections::HashMap;
+use std::iter::FromIterator;
+
+#[derive(Default)]
+pub struct Feed {
+    items: Vec<Box<dyn Summary>>,
+}
+
+impl Feed {
+    pub fn new() -> Feed {
+        Feed { items: vec![] }
+    }
+
+    pub fn push<S: Summary + 'static>(&mut self, item: S) {
+        self.items.push(Box::new(item));
+    }
+
+    pub fn push_boxed(&mut self, item: Box<dyn Summary>) {
+        self.items.push(item);
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Items in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Summary> {
+        self.items.iter().map(|item| item.as_ref())
+    }
+
+    /// Newest first. Items with the same timestamp keep the order they were added in.
+    pub fn sorted(&self) -> Vec<&dyn Summary> {
+        let mut items: Vec<&dyn Summary> = self.iter().collect();
+        items.sort_by_key(|item| std::cmp::Reverse(item.timestamp()));
+        items
+    }
+
+    /// Groups reposts with what they repost. Every original item gets an entry of its own and
+    /// reposts attach to the oldest original with the same `dedup_key`. Reposts of something
+    /// that isn't in the feed collapse into the oldest of them.
+    pub fn digest(&self) -> Digest<'_> {
+        let sorted = self.sorted();
+        // entries paired with their position in `sorted` so they can be put back in that order
+        let mut entries: Vec<(usize, Entry)> = vec![];
+        let mut by_key: HashMap<String, usize> = HashMap::new();
+
+        // walking newest first means the oldest original for a key is the one left in the map
+        for (rank, &item) in sorted.iter().enumerate() {
+            if !item.is_repost() {
+                by_key.insert(item.dedup_key(), entries.len());
+                entries.push((rank, Entry::new(item)));
+            }
+        }
+        for (rank, &item) in sorted.iter().enumerate().rev() {
+            if !item.is_repost() {
+                continue;
+            }
+            match by_key.get(&item.dedup_key()) {
+                Some(&i) => entries[i].1.reposts.push(item),
+                None => {
+                    by_key.insert(item.dedup_key(), entries.len());
+                    entries.push((rank, Entry::new(item)));
+                }
+            }
+        }
+
+        entries.sort_by_key(|&(rank, _)| rank);
+        Digest {
+            entries: entries.into_iter().map(|(_, entry)| entry).collect(),
+        }
+    }
+}
+
+impl Extend<Box<dyn Summary>> for Feed {
+    fn extend<I: IntoIterator<Item = Box<dyn Summary>>>(&mut self, iter: I) {
+        self.items.extend(iter);
+    }
+}
+
+impl FromIterator<Box<dyn Summary>> for Feed {
+    fn from_iter<I: IntoIterator<Item = Box<dyn Summary>>>(iter: I) -> Feed {
+        Feed {
+            items: iter.into_iter().collect(),
+        }
+    }
+}
+
+/// One line of a digest: an item and the reposts folded into it, oldest repost first.
+pub struct Entry<'a> {
+    pub item: &'a dyn Summary,
+    pub reposts: Vec<&'a dyn Summary>,
+}
+
+impl<'a> Entry<'a> {
+    fn new(item: &'a dyn Summary) -> Entry<'a> {
+        Entry {
+            item,
+            reposts: vec![],
+        }
+    }
+
+    fn reposted_by(&self) -> Vec<String> {
+        self.reposts.iter().map(|r| r.summarize_author()).collect()
+    }
+}
+
+pub struct Digest<'a> {
+    pub entries: Vec<Entry<'a>>,
+}
+
+impl<'a> Digest<'a> {
+    pub fn to_text(&self) -> String {
+        let mut out = format!("Digest ({} items)\n", self.entries.len());
+        for (i, entry) in self.entries.iter().enumerate() {
+            out.push_str(&format!(
+                "{}. [{}] {}",
+                i + 1,
+                format_timestamp(entry.item.timestamp()),
+                entry.item.summarize()
+            ));
+            if !entry.reposts.is_empty() {
+                out.push_str(&format!(
+                    " (reposted by {})",
+                    entry.reposted_by().join(", ")
+                ));
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut out = String::from("<ol class=\"digest\">\n");
+        for entry in &self.entries {
+            let timestamp = entry.item.timestamp();
+            out.push_str("  <li>");
+            if timestamp > 0 {
+                let (date, time) = date_time(timestamp);
+                out.push_str(&format!(
+                    "<time datetime=\"{}T{}:00Z\">{} {}</time> ",
+                    date, time, date, time
+                ));
+            }
+            out.push_str(&escape_html(&entry.item.summarize()));
+            if !entry.reposts.is_empty() {
+                out.push_str(&format!(
+                    " <span class=\"reposts\">reposted by {}</span>",
+                    escape_html(&entry.reposted_by().join(", "))
+                ));
+            }
+            out.push_str("</li>\n");
+        }
+        out.push_str("</ol>\n");
+        out
+    }
+}
+
+/// `YYYY-MM-DD HH:MM` in UTC, or `undated` for a zero timestamp.
+pub fn format_timestamp(secs: u64) -> String {
+    if secs == 0 {
+        return String::from("undated");
+    }
+    let (date, time) = date_time(secs);
+    format!("{} {}", date, time)
+}
+
+fn date_time(secs: u64) -> (String, String) {
+    let (year, month, day) = civil_from_days(secs / 86_400);
+    let of_day = secs % 86_400;
+    (
+        format!("{:04}-{:02}-{:02}", year, month, day),
+        format!("{:02}:{:02}", of_day / 3600, of_day % 3600 / 60),
+    )
+}
+
+// days since the epoch to (year, month, day), see
+// http://howardhinnant.github.io/date_algorithms.html
+pub(super) fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chapters::chapter_10::{NewsArticle, Tweet};
+
+    fn tweet(username: &str, content: &str, retweet: bool, timestamp: u64) -> Tweet {
+        Tweet {
+            username: username.to_string(),
+            content: content.to_string(),
+            reply: false,
+            retweet,
+            timestamp,
+        }
+    }
+
+    fn article(headline: &str, author: &str, timestamp: u64) -> NewsArticle {
+        NewsArticle {
+            headline: headline.to_string(),
+            location: String::from("Pittsburgh"),
+            author: author.to_string(),
+            content: String::new(),
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn sorts_newest_first_and_keeps_ties_in_order() {
+        let mut feed = Feed::new();
+        feed.push(tweet("a", "first", false, 100));
+        feed.push(article("Undated", "x", 0));
+        feed.push(tweet("b", "second", false, 300));
+        feed.push(tweet("c", "tie", false, 100));
+
+        let order: Vec<String> = feed.sorted().iter().map(|i| i.summarize_author()).collect();
+        assert_eq!(vec!["b", "a", "c", "x"], order);
+    }
+
+    #[test]
+    fn reposts_fold_into_the_original() {
+        let mut feed = Feed::new();
+        feed.push(tweet("horse", "of course", false, 100));
+        feed.push(tweet("fan1", "Of course ", true, 200));
+        feed.push(tweet("fan2", "of course", true, 150));
+        let mut reply = tweet("critic", "of course", true, 250);
+        reply.reply = true;
+        feed.push(reply);
+        // nobody in the feed wrote this one, the oldest retweet stands in for it
+        feed.push(tweet("late", "orphan", true, 400));
+        feed.push(tweet("early", "orphan", true, 50));
+
+        let digest = feed.digest();
+        let lines: Vec<(String, Vec<String>)> = digest
+            .entries
+            .iter()
+            .map(|e| (e.item.summarize_author(), e.reposted_by()))
+            .collect();
+        assert_eq!(
+            vec![
+                ("critic".to_string(), vec![]),
+                (
+                    "horse".to_string(),
+                    vec!["fan2".to_string(), "fan1".to_string()]
+                ),
+                ("early".to_string(), vec!["late".to_string()]),
+            ],
+            lines
+        );
+    }
+
+    #[test]
+    fn renders_text_and_html_digests() {
+        let feed: Feed = vec![
+            Box::new(article("Penguins <win>", "Iceburgh", 1_622_548_800)) as Box<dyn Summary>,
+            Box::new(article("penguins <WIN>", "Wire", 1_622_552_400)),
+            Box::new(tweet("horse_ebooks", "people & things", false, 0)),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(3, feed.len());
+
+        let mut feed = feed;
+        // two outlets ran the story, the retweet goes with whoever ran it first
+        feed.extend(vec![
+            Box::new(tweet("fan", "penguins <win>", true, 1_622_559_600)) as Box<dyn Summary>,
+        ]);
+        assert_eq!(4, feed.len());
+
+        let digest = feed.digest();
+        assert_eq!(
+            "Digest (3 items)\n\
+             1. [2021-06-01 13:00] penguins <WIN> Wire Pittsburgh\n\
+             2. [2021-06-01 12:00] Penguins <win> Iceburgh Pittsburgh (reposted by fan)\n\
+             3. [undated] horse_ebooks people & things\n",
+            digest.to_text()
+        );
+        assert!(digest.to_html().contains(
+            "<li><time datetime=\"2021-06-01T12:00:00Z\">2021-06-01 12:00</time> \
+             Penguins &lt;win&gt; Iceburgh Pittsburgh <span class=\"reposts\">reposted by fan</span></li>"
+        ));
+        assert!(digest
+            .to_html()
+            .contains("<li>horse_ebooks people &amp; things</li>"));
+    }
+
+    #[test]
+    fn formats_timestamps_as_utc() {
+        assert_eq!("undated", format_timestamp(0));
+        assert_eq!("1970-01-01 00:01", format_timestamp(60));
+        assert_eq!("2000-02-29 23:59", format_timestamp(951_868_799));
+        assert_eq!("2021-06-01 12:00", format_timestamp(1_622_548_800));
+    }
+}
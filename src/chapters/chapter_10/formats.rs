@@ -0,0 +1,712 @@
+// Reading and writing NewsArticle and Tweet in the shapes feeds usually get exported as: RSS 2.0,
+// Atom and a plain JSON list. Everything goes through FeedItem so a single file can hold both
+// kinds and what comes out can be pushed straight into a feed::Feed.
+//
+// RSS and Atom have no notion of a tweet, so tweets are written as entries without a title and
+// with a "tweet" category, plus "retweet" and "reply" when those flags are set. An article's
+// location goes in its first category.
+
+use super::feed::civil_from_days;
+use super::{NewsArticle, Summary, Tweet};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum FeedItem {
+    Article(NewsArticle),
+    Tweet(Tweet),
+}
+
+impl FeedItem {
+    fn inner(&self) -> &dyn Summary {
+        match self {
+            FeedItem::Article(article) => article,
+            FeedItem::Tweet(tweet) => tweet,
+        }
+    }
+}
+
+impl From<NewsArticle> for FeedItem {
+    fn from(article: NewsArticle) -> FeedItem {
+        FeedItem::Article(article)
+    }
+}
+
+impl From<Tweet> for FeedItem {
+    fn from(tweet: Tweet) -> FeedItem {
+        FeedItem::Tweet(tweet)
+    }
+}
+
+impl Summary for FeedItem {
+    fn summarize(&self) -> String {
+        self.inner().summarize()
+    }
+
+    fn summarize_author(&self) -> String {
+        self.inner().summarize_author()
+    }
+
+    fn timestamp(&self) -> u64 {
+        self.inner().timestamp()
+    }
+
+    fn is_repost(&self) -> bool {
+        self.inner().is_repost()
+    }
+
+    fn dedup_key(&self) -> String {
+        self.inner().dedup_key()
+    }
+
+    fn kind(&self) -> &'static str {
+        self.inner().kind()
+    }
+
+    fn field(&self, name: &str) -> Option<String> {
+        self.inner().field(name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Rss,
+    Atom,
+}
+
+impl Format {
+    /// Goes by extension: `.json`, `.rss` or `.xml` for RSS and `.atom`.
+    pub fn from_path(path: &Path) -> Option<Format> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        match ext.as_str() {
+            "json" => Some(Format::Json),
+            "rss" | "xml" => Some(Format::Rss),
+            "atom" => Some(Format::Atom),
+            _ => None,
+        }
+    }
+
+    /// Goes by the content: JSON starts with a bracket, RSS has an `<rss>` root and Atom a
+    /// `<feed>` one.
+    pub fn sniff(text: &str) -> Option<Format> {
+        let text = text.trim_start();
+        if text.starts_with('[') || text.starts_with('{') {
+            Some(Format::Json)
+        } else if text.contains("<rss") {
+            Some(Format::Rss)
+        } else if text.contains("<feed") {
+            Some(Format::Atom)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum FormatError {
+    Json(serde_json::Error),
+    Xml(quick_xml::DeError),
+    Date(String),
+    UnknownFormat,
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FormatError::Json(e) => write!(f, "bad JSON feed: {}", e),
+            FormatError::Xml(e) => write!(f, "bad XML feed: {}", e),
+            FormatError::Date(date) => write!(f, "can't read the date {:?}", date),
+            FormatError::UnknownFormat => write!(f, "not a JSON, RSS or Atom feed"),
+        }
+    }
+}
+
+impl Error for FormatError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FormatError::Json(e) => Some(e),
+            FormatError::Xml(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for FormatError {
+    fn from(e: serde_json::Error) -> FormatError {
+        FormatError::Json(e)
+    }
+}
+
+impl From<quick_xml::DeError> for FormatError {
+    fn from(e: quick_xml::DeError) -> FormatError {
+        FormatError::Xml(e)
+    }
+}
+
+pub fn parse(text: &str, format: Format) -> Result<Vec<FeedItem>, FormatError> {
+    match format {
+        Format::Json => from_json(text),
+        Format::Rss => from_rss(text),
+        Format::Atom => from_atom(text),
+    }
+}
+
+/// `title` names the channel in RSS and the feed in Atom, JSON has nowhere to put it.
+pub fn write(items: &[FeedItem], format: Format, title: &str) -> Result<String, FormatError> {
+    match format {
+        Format::Json => to_json(items),
+        Format::Rss => to_rss(items, title),
+        Format::Atom => to_atom(items, title),
+    }
+}
+
+/// Reads a feed file, working out the format from the extension or failing that the content.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Vec<FeedItem>, Box<dyn Error>> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path)?;
+    let format = Format::from_path(path)
+        .or_else(|| Format::sniff(&text))
+        .ok_or(FormatError::UnknownFormat)?;
+    Ok(parse(&text, format)?)
+}
+
+pub fn save<P: AsRef<Path>>(
+    path: P,
+    items: &[FeedItem],
+    title: &str,
+) -> Result<(), Box<dyn Error>> {
+    let path = path.as_ref();
+    let format = Format::from_path(path).ok_or(FormatError::UnknownFormat)?;
+    fs::write(path, write(items, format, title)?)?;
+    Ok(())
+}
+
+// *** JSON, a list of items tagged with "type"
+
+pub fn from_json(text: &str) -> Result<Vec<FeedItem>, FormatError> {
+    Ok(serde_json::from_str(text)?)
+}
+
+pub fn to_json(items: &[FeedItem]) -> Result<String, FormatError> {
+    let mut json = serde_json::to_string_pretty(items)?;
+    json.push('\n');
+    Ok(json)
+}
+
+// *** What RSS items and Atom entries have in common
+
+const TWEET: &str = "tweet";
+const RETWEET: &str = "retweet";
+const REPLY: &str = "reply";
+
+struct Entry {
+    title: Option<String>,
+    author: Option<String>,
+    content: Option<String>,
+    categories: Vec<String>,
+    timestamp: u64,
+}
+
+impl Entry {
+    fn from_item(item: &FeedItem) -> Entry {
+        match item {
+            FeedItem::Article(article) => Entry {
+                title: Some(article.headline.clone()),
+                author: Some(article.author.clone()),
+                content: Some(article.content.clone()),
+                categories: Some(article.location.clone())
+                    .filter(|l| !l.is_empty())
+                    .into_iter()
+                    .collect(),
+                timestamp: article.timestamp,
+            },
+            FeedItem::Tweet(tweet) => {
+                let mut categories = vec![TWEET.to_string()];
+                if tweet.retweet {
+                    categories.push(RETWEET.to_string());
+                }
+                if tweet.reply {
+                    categories.push(REPLY.to_string());
+                }
+                Entry {
+                    title: None,
+                    author: Some(tweet.username.clone()),
+                    content: Some(tweet.content.clone()),
+                    categories,
+                    timestamp: tweet.timestamp,
+                }
+            }
+        }
+    }
+
+    fn into_item(self) -> FeedItem {
+        let has = |name: &str| self.categories.iter().any(|c| c == name);
+        if has(TWEET) {
+            return FeedItem::Tweet(Tweet {
+                retweet: has(RETWEET),
+                reply: has(REPLY),
+                username: self.author.unwrap_or_default(),
+                content: self.content.unwrap_or_default(),
+                timestamp: self.timestamp,
+            });
+        }
+        FeedItem::Article(NewsArticle {
+            location: self.categories.into_iter().next().unwrap_or_default(),
+            headline: self.title.unwrap_or_default(),
+            author: self.author.unwrap_or_default(),
+            content: self.content.unwrap_or_default(),
+            timestamp: self.timestamp,
+        })
+    }
+}
+
+fn to_xml<T: Serialize>(value: &T) -> Result<String, FormatError> {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let mut serializer = quick_xml::se::Serializer::new(&mut xml);
+    serializer.indent(' ', 2);
+    value.serialize(serializer)?;
+    xml.push('\n');
+    Ok(xml)
+}
+
+// *** RSS 2.0
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "rss")]
+struct Rss {
+    #[serde(rename = "@version")]
+    version: String,
+    channel: Channel,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Channel {
+    title: String,
+    #[serde(default)]
+    description: String,
+    #[serde(rename = "item", default)]
+    items: Vec<RssItem>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RssItem {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    // plain RSS wants an email address here so plenty of feeds use dc:creator instead
+    #[serde(alias = "creator", skip_serializing_if = "Option::is_none")]
+    author: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(rename = "category", default)]
+    categories: Vec<String>,
+    #[serde(rename = "pubDate", skip_serializing_if = "Option::is_none")]
+    pub_date: Option<String>,
+}
+
+pub fn from_rss(text: &str) -> Result<Vec<FeedItem>, FormatError> {
+    let rss: Rss = quick_xml::de::from_str(text)?;
+    rss.channel
+        .items
+        .into_iter()
+        .map(|item| {
+            let timestamp = match item.pub_date {
+                Some(date) => parse_rfc2822(&date)?,
+                None => 0,
+            };
+            Ok(Entry {
+                title: item.title,
+                author: item.author,
+                content: item.description,
+                categories: item.categories,
+                timestamp,
+            }
+            .into_item())
+        })
+        .collect()
+}
+
+pub fn to_rss(items: &[FeedItem], title: &str) -> Result<String, FormatError> {
+    let items = items
+        .iter()
+        .map(|item| {
+            let entry = Entry::from_item(item);
+            RssItem {
+                title: entry.title,
+                author: entry.author,
+                description: entry.content,
+                categories: entry.categories,
+                pub_date: Some(entry.timestamp).filter(|&t| t > 0).map(format_rfc2822),
+            }
+        })
+        .collect();
+    to_xml(&Rss {
+        version: String::from("2.0"),
+        channel: Channel {
+            title: title.to_string(),
+            description: title.to_string(),
+            items,
+        },
+    })
+}
+
+// *** Atom
+
+const ATOM_NS: &str = "http://www.w3.org/2005/Atom";
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "feed")]
+struct AtomFeed {
+    #[serde(rename = "@xmlns", default)]
+    xmlns: String,
+    #[serde(default)]
+    id: String,
+    title: String,
+    #[serde(default)]
+    updated: String,
+    #[serde(rename = "entry", default)]
+    entries: Vec<AtomEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AtomEntry {
+    #[serde(default)]
+    id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author: Option<AtomPerson>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    published: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    updated: Option<String>,
+    #[serde(rename = "category", default)]
+    categories: Vec<AtomCategory>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<AtomContent>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AtomPerson {
+    name: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AtomCategory {
+    #[serde(rename = "@term")]
+    term: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AtomContent {
+    #[serde(rename = "@type", default)]
+    kind: String,
+    #[serde(rename = "$text", default)]
+    text: String,
+}
+
+pub fn from_atom(text: &str) -> Result<Vec<FeedItem>, FormatError> {
+    let feed: AtomFeed = quick_xml::de::from_str(text)?;
+    feed.entries
+        .into_iter()
+        .map(|entry| {
+            let timestamp = match entry.published.or(entry.updated) {
+                Some(date) => parse_rfc3339(&date)?,
+                None => 0,
+            };
+            Ok(Entry {
+                title: entry.title,
+                author: entry.author.map(|a| a.name),
+                content: entry.content.map(|c| c.text).or(entry.summary),
+                categories: entry.categories.into_iter().map(|c| c.term).collect(),
+                timestamp,
+            }
+            .into_item())
+        })
+        .collect()
+}
+
+pub fn to_atom(items: &[FeedItem], title: &str) -> Result<String, FormatError> {
+    let latest = items.iter().map(|i| i.timestamp()).max().unwrap_or(0);
+    let entries = items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let entry = Entry::from_item(item);
+            let date = Some(entry.timestamp).filter(|&t| t > 0).map(format_rfc3339);
+            AtomEntry {
+                id: format!("urn:rust-book:entry:{}", i + 1),
+                title: entry.title,
+                author: entry.author.map(|name| AtomPerson { name }),
+                published: date.clone(),
+                updated: date,
+                categories: entry
+                    .categories
+                    .into_iter()
+                    .map(|term| AtomCategory { term })
+                    .collect(),
+                summary: None,
+                content: entry.content.map(|text| AtomContent {
+                    kind: String::from("text"),
+                    text,
+                }),
+            }
+        })
+        .collect();
+    to_xml(&AtomFeed {
+        xmlns: ATOM_NS.to_string(),
+        id: format!(
+            "urn:rust-book:feed:{}",
+            title.to_lowercase().replace(' ', "-")
+        ),
+        title: title.to_string(),
+        updated: format_rfc3339(latest),
+        entries,
+    })
+}
+
+// *** Dates. RSS uses RFC 2822 ("Tue, 01 Jun 2021 12:00:00 GMT"), Atom RFC 3339
+// ("2021-06-01T12:00:00Z"). Both come back as seconds since the epoch in UTC.
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+pub fn format_rfc2822(secs: u64) -> String {
+    let days = secs / 86_400;
+    let (year, month, day) = civil_from_days(days);
+    // the epoch was a Thursday
+    let weekday = WEEKDAYS[((days + 4) % 7) as usize];
+    format!(
+        "{}, {:02} {} {:04} {} GMT",
+        weekday,
+        day,
+        MONTHS[month as usize - 1],
+        year,
+        clock(secs)
+    )
+}
+
+pub fn format_rfc3339(secs: u64) -> String {
+    let (year, month, day) = civil_from_days(secs / 86_400);
+    format!("{:04}-{:02}-{:02}T{}Z", year, month, day, clock(secs))
+}
+
+fn clock(secs: u64) -> String {
+    let of_day = secs % 86_400;
+    format!(
+        "{:02}:{:02}:{:02}",
+        of_day / 3600,
+        of_day % 3600 / 60,
+        of_day % 60
+    )
+}
+
+pub fn parse_rfc2822(date: &str) -> Result<u64, FormatError> {
+    let error = || FormatError::Date(date.to_string());
+    let mut parts: Vec<&str> = date.split_whitespace().collect();
+    // the day of the week is optional and carries no information
+    if parts.first().is_some_and(|p| p.ends_with(',')) {
+        parts.remove(0);
+    }
+    if parts.len() != 5 {
+        return Err(error());
+    }
+
+    let day: u64 = parts[0].parse().map_err(|_| error())?;
+    let month = MONTHS
+        .iter()
+        .position(|m| m.eq_ignore_ascii_case(parts[1]))
+        .ok_or_else(error)? as u64
+        + 1;
+    let year: u64 = match parts[2].parse().map_err(|_| error())? {
+        // two digit years, RFC 2822 says 00-49 are 2000s
+        y if y < 50 => y + 2000,
+        y if y < 100 => y + 1900,
+        y => y,
+    };
+    let mut clock = parts[3].split(':').map(|n| n.parse::<u64>());
+    let (hour, minute, second) = match (clock.next(), clock.next(), clock.next(), clock.next()) {
+        (Some(Ok(h)), Some(Ok(m)), None, None) => (h, m, 0),
+        (Some(Ok(h)), Some(Ok(m)), Some(Ok(s)), None) => (h, m, s),
+        _ => return Err(error()),
+    };
+    let offset = match parts[4] {
+        "GMT" | "UT" | "UTC" | "Z" => 0,
+        "EDT" => -4 * 3600,
+        "EST" | "CDT" => -5 * 3600,
+        "CST" | "MDT" => -6 * 3600,
+        "MST" | "PDT" => -7 * 3600,
+        "PST" => -8 * 3600,
+        zone => parse_offset(zone).ok_or_else(error)?,
+    };
+
+    to_epoch(year, month, day, (hour, minute, second), offset).ok_or_else(error)
+}
+
+pub fn parse_rfc3339(date: &str) -> Result<u64, FormatError> {
+    let error = || FormatError::Date(date.to_string());
+    let split = date.find(['T', 't', ' ']).ok_or_else(error)?;
+    let (day_part, rest) = (&date[..split], &date[split + 1..]);
+
+    let ymd: Vec<u64> = day_part
+        .split('-')
+        .map(|n| n.parse())
+        .collect::<Result<_, _>>()
+        .map_err(|_| error())?;
+    if ymd.len() != 3 {
+        return Err(error());
+    }
+
+    let zone_at = rest.find(['Z', 'z', '+', '-']).ok_or_else(error)?;
+    let (time, zone) = rest.split_at(zone_at);
+    // fractional seconds are dropped
+    let time = time.split('.').next().unwrap_or("");
+    let hms: Vec<u64> = time
+        .split(':')
+        .map(|n| n.parse())
+        .collect::<Result<_, _>>()
+        .map_err(|_| error())?;
+    if hms.len() != 3 {
+        return Err(error());
+    }
+    let offset = match zone {
+        "Z" | "z" => 0,
+        zone => parse_offset(&zone.replace(':', "")).ok_or_else(error)?,
+    };
+
+    to_epoch(ymd[0], ymd[1], ymd[2], (hms[0], hms[1], hms[2]), offset).ok_or_else(error)
+}
+
+// "+0530" or "-0800" as seconds east of UTC
+fn parse_offset(zone: &str) -> Option<i64> {
+    // all ASCII, so the byte slices below can't land inside a char
+    if zone.len() != 5 || !zone.is_ascii() {
+        return None;
+    }
+    let sign = match &zone[..1] {
+        "+" => 1,
+        "-" => -1,
+        _ => return None,
+    };
+    let hours: i64 = zone[1..3].parse().ok()?;
+    let minutes: i64 = zone[3..].parse().ok()?;
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+// a local date and time plus its UTC offset to seconds since the epoch, None if any part is
+// out of range or the result is before 1970. Everything is range checked before any arithmetic,
+// so a huge number from a feed can't overflow into a wrong but valid looking date.
+fn to_epoch(
+    year: u64,
+    month: u64,
+    day: u64,
+    (hour, minute, second): (u64, u64, u64),
+    offset: i64,
+) -> Option<u64> {
+    // second 60 is a leap second
+    if year > 9999
+        || !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+    let days = days_from_civil(year as i64, month as i64, day as i64);
+    let local = days * 86_400 + (hour * 3600 + minute * 60 + second) as i64;
+    let utc = local - offset;
+    if utc < 0 {
+        None
+    } else {
+        Some(utc as u64)
+    }
+}
+
+// the inverse of civil_from_days
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates_round_trip() {
+        for &secs in &[60, 951_868_799, 1_622_548_800, 4_102_444_800] {
+            assert_eq!(secs, parse_rfc2822(&format_rfc2822(secs)).unwrap());
+            assert_eq!(secs, parse_rfc3339(&format_rfc3339(secs)).unwrap());
+        }
+        assert_eq!(
+            "Tue, 01 Jun 2021 12:00:00 GMT",
+            format_rfc2822(1_622_548_800)
+        );
+        assert_eq!("2021-06-01T12:00:00Z", format_rfc3339(1_622_548_800));
+    }
+
+    #[test]
+    fn dates_honour_zones() {
+        let noon = 1_622_548_800;
+        assert_eq!(noon, parse_rfc2822("01 Jun 2021 08:00 EDT").unwrap());
+        assert_eq!(noon, parse_rfc2822("Tue, 1 jun 21 17:30:00 +0530").unwrap());
+        assert_eq!(
+            noon,
+            parse_rfc3339("2021-06-01T04:00:00.250-08:00").unwrap()
+        );
+
+        assert!(parse_rfc2822("Tue, 01 Foo 2021 12:00:00 GMT").is_err());
+        assert!(parse_rfc2822("01 Jan 1970 00:00:00 +0100").is_err());
+        assert!(parse_rfc3339("2021-13-01T12:00:00Z").is_err());
+        assert!(parse_rfc2822("01 Jun 2021 24:00 GMT").is_err());
+        assert!(parse_rfc3339("2021-06-01T12:60:00Z").is_err());
+        assert!(parse_rfc2822("01 Jun 2021 12:00 +2400").is_err());
+        assert!(parse_rfc3339("2021-06-01T12:00:00+05:60").is_err());
+        assert!(parse_rfc3339("2021-06-01").is_err());
+    }
+
+    #[test]
+    fn hostile_dates_are_errors_not_panics() {
+        // a multi-byte char inside the zone used to split on a byte slice
+        assert!(parse_rfc2822("Mon, 01 Jan 2024 10:00:00 +1é1").is_err());
+        assert!(parse_rfc3339("2024-01-01T10:00:00+1é1").is_err());
+        // numbers big enough to overflow used to panic in debug and wrap in release
+        assert!(parse_rfc2822("01 Jan 2024 99999999999999999:00 GMT").is_err());
+        assert!(parse_rfc3339("99999999999999999-01-01T00:00:00Z").is_err());
+        assert!(parse_rfc3339("2024-01-01T00:00:18446744073709551615Z").is_err());
+        assert!(parse_rfc2822("01 Jan 10000 00:00 GMT").is_err());
+        // the last second of the last year we take is fine, as is a leap second
+        assert!(parse_rfc3339("9999-12-31T23:59:59Z").is_ok());
+        assert!(parse_rfc3339("2016-12-31T23:59:60Z").is_ok());
+    }
+
+    #[test]
+    fn sniffs_formats() {
+        assert_eq!(Some(Format::Json), Format::sniff("  [ ]"));
+        assert_eq!(
+            Some(Format::Rss),
+            Format::sniff("<?xml version=\"1.0\"?><rss>")
+        );
+        assert_eq!(Some(Format::Atom), Format::sniff("<feed xmlns=\"x\">"));
+        assert_eq!(None, Format::sniff("hello"));
+        assert_eq!(Some(Format::Atom), Format::from_path(Path::new("a/b.ATOM")));
+        assert_eq!(None, Format::from_path(Path::new("feed")));
+    }
+}
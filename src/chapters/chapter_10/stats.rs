@@ -0,0 +1,536 @@
+// Statistics over any numeric type. This picks up where largest<T: PartialOrd + Copy> in 10.3
+// leaves off: nothing here panics on empty input, nothing needs Copy just to compare, and NaN is
+// handled on purpose instead of by whichever way partial_cmp happens to fall.
+//
+// NaN rule used throughout: a value that doesn't compare equal to itself (f32/f64 NaN) is
+// skipped by min/max/argmax, sorts after every other value, and is left out of means,
+// percentiles and histograms (but counted, so you know it was there).
+
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Anything that can be turned into an f64 for the arithmetic.
+pub trait Number: Copy + PartialOrd {
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! number {
+    ($($t:ty),*) => {
+        $(impl Number for $t {
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+        })*
+    };
+}
+
+number!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64);
+
+fn is_nan<T: PartialOrd>(x: &T) -> bool {
+    x.partial_cmp(x).is_none()
+}
+
+/// A total order for any PartialOrd type that puts NaN after everything else (NaNs are equal
+/// to each other).
+pub fn nan_last<T: PartialOrd>(a: &T, b: &T) -> Ordering {
+    match a.partial_cmp(b) {
+        Some(ordering) => ordering,
+        None => match (is_nan(a), is_nan(b)) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            // two values that aren't NaN but don't compare, leave them be
+            (false, false) => Ordering::Equal,
+        },
+    }
+}
+
+pub fn sort_nan_last<T: PartialOrd>(items: &mut [T]) {
+    items.sort_by(nan_last);
+}
+
+// index and value of the best item under `better`, NaN never wins
+fn best<I, F>(iter: I, better: F) -> Option<(usize, I::Item)>
+where
+    I: IntoIterator,
+    I::Item: PartialOrd,
+    F: Fn(&I::Item, &I::Item) -> bool,
+{
+    let mut best: Option<(usize, I::Item)> = None;
+    for (i, item) in iter.into_iter().enumerate() {
+        if is_nan(&item) {
+            continue;
+        }
+        best = match best {
+            Some((_, ref current)) if !better(&item, current) => best,
+            _ => Some((i, item)),
+        };
+    }
+    best
+}
+
+/// The largest item, `None` when there are none. Works on `slice.iter()` so nothing is copied.
+/// Ties go to the first.
+pub fn max<I>(iter: I) -> Option<I::Item>
+where
+    I: IntoIterator,
+    I::Item: PartialOrd,
+{
+    best(iter, |a, b| a > b).map(|(_, item)| item)
+}
+
+pub fn min<I>(iter: I) -> Option<I::Item>
+where
+    I: IntoIterator,
+    I::Item: PartialOrd,
+{
+    best(iter, |a, b| a < b).map(|(_, item)| item)
+}
+
+/// Position of the largest item, counting NaNs so the index matches the input.
+pub fn argmax<I>(iter: I) -> Option<usize>
+where
+    I: IntoIterator,
+    I::Item: PartialOrd,
+{
+    best(iter, |a, b| a > b).map(|(i, _)| i)
+}
+
+pub fn argmin<I>(iter: I) -> Option<usize>
+where
+    I: IntoIterator,
+    I::Item: PartialOrd,
+{
+    best(iter, |a, b| a < b).map(|(i, _)| i)
+}
+
+/// Count, mean and variance kept up to date one value at a time with Welford's method, which
+/// doesn't lose precision the way summing squares does. Values can be removed again too.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Running {
+    count: u64,
+    mean: f64,
+    m2: f64,
+    nan: u64,
+}
+
+impl Running {
+    pub fn new() -> Running {
+        Running::default()
+    }
+
+    pub fn push<T: Number>(&mut self, value: T) {
+        let x = value.to_f64();
+        if x.is_nan() {
+            self.nan += 1;
+            return;
+        }
+        self.count += 1;
+        let delta = x - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (x - self.mean);
+    }
+
+    /// Takes back a value that was pushed earlier. Removing something that was never pushed
+    /// leaves the numbers meaningless.
+    pub fn remove<T: Number>(&mut self, value: T) {
+        let x = value.to_f64();
+        if x.is_nan() {
+            self.nan = self.nan.saturating_sub(1);
+            return;
+        }
+        match self.count {
+            0 => {}
+            1 => {
+                *self = Running {
+                    nan: self.nan,
+                    ..Running::default()
+                }
+            }
+            _ => {
+                let delta = x - self.mean;
+                self.count -= 1;
+                self.mean -= delta / self.count as f64;
+                self.m2 -= delta * (x - self.mean);
+                // rounding can leave a tiny negative where there should be zero
+                self.m2 = self.m2.max(0.0);
+            }
+        }
+    }
+
+    /// Combines two sets of values as if they had all been pushed into one.
+    pub fn merge(&mut self, other: &Running) {
+        if other.count == 0 {
+            self.nan += other.nan;
+            return;
+        }
+        let count = self.count + other.count;
+        let delta = other.mean - self.mean;
+        self.mean += delta * other.count as f64 / count as f64;
+        self.m2 += other.m2 + delta * delta * (self.count * other.count) as f64 / count as f64;
+        self.count = count;
+        self.nan += other.nan;
+    }
+
+    /// Values counted, not including NaNs.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn nan_count(&self) -> u64 {
+        self.nan
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        if self.count == 0 {
+            None
+        } else {
+            Some(self.mean)
+        }
+    }
+
+    /// Population variance, dividing by n.
+    pub fn variance(&self) -> Option<f64> {
+        if self.count == 0 {
+            None
+        } else {
+            Some(self.m2 / self.count as f64)
+        }
+    }
+
+    /// Sample variance, dividing by n - 1.
+    pub fn sample_variance(&self) -> Option<f64> {
+        if self.count < 2 {
+            None
+        } else {
+            Some(self.m2 / (self.count - 1) as f64)
+        }
+    }
+
+    pub fn std_dev(&self) -> Option<f64> {
+        self.variance().map(f64::sqrt)
+    }
+}
+
+impl<T: Number> Extend<T> for Running {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+/// The chapter 17 AveragedCollection for any number type, without re-summing the whole list on
+/// every change.
+#[derive(Debug, Clone, Default)]
+pub struct AveragedCollection<T> {
+    list: Vec<T>,
+    stats: Running,
+}
+
+impl<T: Number> AveragedCollection<T> {
+    pub fn new() -> AveragedCollection<T> {
+        AveragedCollection {
+            list: vec![],
+            stats: Running::new(),
+        }
+    }
+
+    pub fn push(&mut self, value: T) {
+        self.list.push(value);
+        self.stats.push(value);
+    }
+
+    pub fn remove(&mut self) -> Option<T> {
+        let value = self.list.pop()?;
+        self.stats.remove(value);
+        Some(value)
+    }
+
+    pub fn average(&self) -> Option<f64> {
+        self.stats.mean()
+    }
+
+    pub fn stats(&self) -> &Running {
+        &self.stats
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.list
+    }
+}
+
+// the non NaN values as f64, sorted
+fn sorted_values<T: Number>(data: &[T]) -> Vec<f64> {
+    let mut values: Vec<f64> = data
+        .iter()
+        .map(|x| x.to_f64())
+        .filter(|x| !x.is_nan())
+        .collect();
+    sort_nan_last(&mut values);
+    values
+}
+
+// `p` in 0..=100 of already sorted values, interpolating between neighbours
+fn percentile_of_sorted(sorted: &[f64], p: f64) -> Option<f64> {
+    if sorted.is_empty() || !(0.0..=100.0).contains(&p) {
+        return None;
+    }
+    let rank = p / 100.0 * (sorted.len() - 1) as f64;
+    let (below, above) = (rank.floor() as usize, rank.ceil() as usize);
+    let fraction = rank - below as f64;
+    Some(sorted[below] + (sorted[above] - sorted[below]) * fraction)
+}
+
+/// The `p`th percentile (0 to 100) using linear interpolation between the closest ranks, the
+/// same as numpy's default. `None` for no data or a `p` outside 0..=100.
+pub fn percentile<T: Number>(data: &[T], p: f64) -> Option<f64> {
+    percentile_of_sorted(&sorted_values(data), p)
+}
+
+/// Several percentiles for the price of one sort.
+pub fn percentiles<T: Number>(data: &[T], ps: &[f64]) -> Option<Vec<f64>> {
+    let sorted = sorted_values(data);
+    ps.iter()
+        .map(|&p| percentile_of_sorted(&sorted, p))
+        .collect()
+}
+
+pub fn median<T: Number>(data: &[T]) -> Option<f64> {
+    percentile(data, 50.0)
+}
+
+/// Counts of values in equal width bins over `min..max`. Values equal to `max` go in the last
+/// bin, anything else outside the range is counted as under or over.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    min: f64,
+    max: f64,
+    counts: Vec<u64>,
+    under: u64,
+    over: u64,
+    nan: u64,
+}
+
+impl Histogram {
+    /// `None` unless `bins` is at least one and `min < max`.
+    pub fn new(min: f64, max: f64, bins: usize) -> Option<Histogram> {
+        if bins == 0 || !min.is_finite() || !max.is_finite() || min >= max {
+            return None;
+        }
+        Some(Histogram {
+            min,
+            max,
+            counts: vec![0; bins],
+            under: 0,
+            over: 0,
+            nan: 0,
+        })
+    }
+
+    /// Bins spanning exactly the data's own range. A single distinct value gets a range of
+    /// one around it.
+    pub fn of<T: Number>(data: &[T], bins: usize) -> Option<Histogram> {
+        let values = sorted_values(data);
+        let (&lo, &hi) = (values.first()?, values.last()?);
+        let (lo, hi) = if lo < hi {
+            (lo, hi)
+        } else {
+            (lo - 0.5, hi + 0.5)
+        };
+        let mut histogram = Histogram::new(lo, hi, bins)?;
+        for &x in data {
+            histogram.add(x);
+        }
+        Some(histogram)
+    }
+
+    pub fn add<T: Number>(&mut self, value: T) {
+        let x = value.to_f64();
+        if x.is_nan() {
+            self.nan += 1;
+        } else if x < self.min {
+            self.under += 1;
+        } else if x > self.max {
+            self.over += 1;
+        } else {
+            let bins = self.counts.len();
+            let bin = ((x - self.min) / self.width()) as usize;
+            self.counts[bin.min(bins - 1)] += 1;
+        }
+    }
+
+    pub fn width(&self) -> f64 {
+        (self.max - self.min) / self.counts.len() as f64
+    }
+
+    /// Each bin as (low edge, high edge, count).
+    pub fn bins(&self) -> impl Iterator<Item = (f64, f64, u64)> + '_ {
+        let width = self.width();
+        self.counts.iter().enumerate().map(move |(i, &count)| {
+            let low = self.min + width * i as f64;
+            (low, low + width, count)
+        })
+    }
+
+    pub fn counts(&self) -> &[u64] {
+        &self.counts
+    }
+
+    /// Values that fell (below, above) the range.
+    pub fn outside(&self) -> (u64, u64) {
+        (self.under, self.over)
+    }
+
+    pub fn nan_count(&self) -> u64 {
+        self.nan
+    }
+}
+
+impl fmt::Display for Histogram {
+    /// One line per bin with a bar scaled so the fullest bin is 40 wide.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let most = self.counts.iter().copied().max().unwrap_or(0).max(1);
+        for (low, high, count) in self.bins() {
+            let bar = "#".repeat((count * 40 / most) as usize);
+            writeln!(f, "[{:>8.2}, {:>8.2}) {:>5} {}", low, high, count, bar)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn min_max_handle_empty_input_and_nan() {
+        let empty: Vec<i32> = vec![];
+        assert_eq!(None, max(&empty));
+        assert_eq!(None, argmin(&empty));
+
+        let numbers = vec![2, 32, 43, 11, 43, 2];
+        assert_eq!(Some(&43), max(&numbers));
+        assert_eq!(Some(2), argmax(&numbers));
+        assert_eq!(Some(0), argmin(&numbers));
+
+        // Strings aren't Copy, which largest<T: PartialOrd + Copy> needed
+        let words = vec![String::from("pear"), String::from("apple")];
+        assert_eq!(Some(&String::from("apple")), min(&words));
+
+        let floats = vec![f64::NAN, 1.5, f64::NAN, -3.0];
+        assert_eq!(Some(1.5), max(floats.iter().copied()));
+        assert_eq!(Some(3), argmin(&floats));
+        assert_eq!(None, max(vec![f64::NAN]));
+    }
+
+    #[test]
+    fn nan_sorts_last() {
+        let mut floats = vec![3.0, f64::NAN, -1.0, f64::INFINITY, f64::NAN, 0.0];
+        sort_nan_last(&mut floats);
+        assert_eq!(&[-1.0, 0.0, 3.0, f64::INFINITY], &floats[..4]);
+        assert!(floats[4].is_nan() && floats[5].is_nan());
+    }
+
+    #[test]
+    fn running_matches_the_two_pass_answer() {
+        let data = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+        let mut running = Running::new();
+        running.extend(data.iter().copied());
+        assert_eq!(Some(5.0), running.mean());
+        assert_eq!(Some(4.0), running.variance());
+        assert_eq!(Some(2.0), running.std_dev());
+        assert!(close(32.0 / 7.0, running.sample_variance().unwrap()));
+
+        // removing takes us back to the stats of what's left
+        running.remove(9.0);
+        running.remove(7.0);
+        let mut expected = Running::new();
+        expected.extend(data[..6].iter().copied());
+        assert!(close(expected.mean().unwrap(), running.mean().unwrap()));
+        assert!(close(
+            expected.variance().unwrap(),
+            running.variance().unwrap()
+        ));
+
+        let (mut left, mut right) = (Running::new(), Running::new());
+        left.extend(vec![2u8, 4, 4, 4]);
+        right.extend(vec![5u8, 5, 7, 9]);
+        right.push(f32::NAN);
+        left.merge(&right);
+        assert_eq!((8, 1), (left.count(), left.nan_count()));
+        assert!(close(4.0, left.variance().unwrap()));
+
+        assert_eq!(None, Running::new().mean());
+        assert_eq!(None, Running::new().sample_variance());
+    }
+
+    #[test]
+    fn averaged_collection_tracks_the_mean() {
+        let mut collection = AveragedCollection::new();
+        assert_eq!(None, collection.average());
+        for &n in &[32, 2, 12, -2] {
+            collection.push(n);
+        }
+        assert_eq!(Some(-2), collection.remove());
+        assert!(close(46.0 / 3.0, collection.average().unwrap()));
+        assert_eq!(&[32, 2, 12], collection.as_slice());
+
+        collection.remove();
+        collection.remove();
+        collection.remove();
+        assert_eq!(None, collection.remove());
+        assert_eq!(None, collection.average());
+    }
+
+    #[test]
+    fn percentiles_interpolate() {
+        let data = [15, 20, 35, 40, 50];
+        assert_eq!(Some(35.0), median(&data));
+        assert_eq!(Some(27.5), median(&[40, 15, 20, 35]));
+        assert_eq!(
+            Some(vec![15.0, 20.0, 29.0, 50.0]),
+            percentiles(&data, &[0.0, 25.0, 40.0, 100.0])
+        );
+        assert_eq!(Some(2.0), median(&[f64::NAN, 1.0, 3.0]));
+
+        let empty: [f32; 0] = [];
+        assert_eq!(None, median(&empty));
+        assert_eq!(None, percentile(&data, 101.0));
+        assert_eq!(None, percentiles(&data, &[50.0, -1.0]));
+    }
+
+    #[test]
+    fn histogram_bins_values() {
+        let data = [1, 2, 2, 3, 3, 3, 4, 4, 4, 4];
+        let histogram = Histogram::of(&data, 3).unwrap();
+        assert_eq!(&[1, 2, 7], histogram.counts());
+        assert_eq!(
+            "[    1.00,     2.00)     1 #####\n\
+             [    2.00,     3.00)     2 ###########\n\
+             [    3.00,     4.00)     7 ########################################\n",
+            histogram.to_string()
+        );
+
+        let mut histogram = Histogram::new(0.0, 1.0, 4).unwrap();
+        for &x in &[-0.5, 0.0, 0.3, 0.99, 1.0, 1.5, f64::NAN] {
+            histogram.add(x);
+        }
+        assert_eq!(&[1, 1, 0, 2], histogram.counts());
+        assert_eq!((1, 1), histogram.outside());
+        assert_eq!(1, histogram.nan_count());
+        assert_eq!(Some((0.25, 0.5, 1)), histogram.bins().nth(1));
+
+        assert_eq!(
+            Some(&[1][..]),
+            Histogram::of(&[7u64], 1).as_ref().map(|h| h.counts())
+        );
+        assert_eq!(None, Histogram::new(1.0, 1.0, 3));
+        assert_eq!(None, Histogram::new(0.0, 1.0, 0));
+        assert_eq!(None, Histogram::of(&[f64::NAN], 2));
+    }
+}
@@ -0,0 +1,482 @@
+// Summaries built at runtime instead of by each type's hard coded summarize. A Strategy is a
+// Template that pulls fields out of any Summary followed by any number of Transforms on the
+// resulting text. A Summarizer holds one Strategy per Summary::kind so articles and tweets can
+// be summarized differently, and can be set up from a small config:
+//
+//     # kind = template | step | step
+//     tweet = @{username}: {content} | truncate 40
+//     article = {headline} ({location}) | highlight penguins, stanley cup
+//     * = {summary} | first-sentence
+
+use super::feed::format_timestamp;
+use super::Summary;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use unicode_segmentation::UnicodeSegmentation;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StrategyError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for StrategyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for StrategyError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Piece {
+    Text(String),
+    Field(String),
+}
+
+/// Text with `{name}` placeholders. `{author}`, `{summary}`, `{default}` and `{date}` work for
+/// every Summary, anything else is looked up with `Summary::field`. `{{` and `}}` are literal
+/// braces and a placeholder the item doesn't know is left as it is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    pieces: Vec<Piece>,
+}
+
+impl Template {
+    pub fn parse(text: &str) -> Result<Template, String> {
+        let mut pieces = vec![];
+        let mut literal = String::new();
+        let mut chars = text.chars().peekable();
+
+        while let Some(ch) = chars.next() {
+            match ch {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) if c.is_alphanumeric() || c == '_' => name.push(c),
+                            _ => return Err(format!("unclosed placeholder in {:?}", text)),
+                        }
+                    }
+                    if name.is_empty() {
+                        return Err(format!("empty placeholder in {:?}", text));
+                    }
+                    if !literal.is_empty() {
+                        pieces.push(Piece::Text(std::mem::take(&mut literal)));
+                    }
+                    pieces.push(Piece::Field(name));
+                }
+                '}' => return Err(format!("unmatched }} in {:?}", text)),
+                _ => literal.push(ch),
+            }
+        }
+        if !literal.is_empty() {
+            pieces.push(Piece::Text(literal));
+        }
+        Ok(Template { pieces })
+    }
+
+    pub fn render(&self, item: &dyn Summary) -> String {
+        let mut out = String::new();
+        for piece in &self.pieces {
+            match piece {
+                Piece::Text(text) => out.push_str(text),
+                Piece::Field(name) => match lookup(item, name) {
+                    Some(value) => out.push_str(&value),
+                    None => {
+                        out.push('{');
+                        out.push_str(name);
+                        out.push('}');
+                    }
+                },
+            }
+        }
+        out
+    }
+}
+
+fn lookup(item: &dyn Summary, name: &str) -> Option<String> {
+    match name {
+        "author" => Some(item.summarize_author()),
+        "summary" => Some(item.summarize()),
+        "default" => Some(item.summarize_default()),
+        "date" => Some(format_timestamp(item.timestamp())),
+        _ => item.field(name),
+    }
+}
+
+/// One step applied to the text a Template produced. Plain closures work too.
+pub trait Transform {
+    fn apply(&self, text: &str) -> String;
+}
+
+impl<F: Fn(&str) -> String> Transform for F {
+    fn apply(&self, text: &str) -> String {
+        self(text)
+    }
+}
+
+/// Cuts the text to at most `max` grapheme clusters, the last being an ellipsis when anything
+/// was cut. Counting graphemes rather than chars keeps accents and emoji in one piece.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Truncate {
+    pub max: usize,
+}
+
+impl Transform for Truncate {
+    fn apply(&self, text: &str) -> String {
+        if text.graphemes(true).nth(self.max).is_none() {
+            return text.to_string();
+        }
+        if self.max == 0 {
+            return String::new();
+        }
+        let kept: String = text.graphemes(true).take(self.max - 1).collect();
+        format!("{}…", kept.trim_end())
+    }
+}
+
+/// Keeps the first sentence, using the Unicode sentence rules so "U.S. teams" doesn't end one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FirstSentence;
+
+impl Transform for FirstSentence {
+    fn apply(&self, text: &str) -> String {
+        text.unicode_sentences()
+            .next()
+            .unwrap_or("")
+            .trim()
+            .to_string()
+    }
+}
+
+/// Wraps whole word, case insensitive matches of any keyword in `before` and `after`. A keyword
+/// can be a phrase like "stanley cup", any run of spaces in it matching any run in the text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Highlight {
+    // each keyword split on word bounds the same way the text is, longest first so "stanley cup"
+    // wins over "stanley"
+    keywords: Vec<Vec<String>>,
+    pub before: String,
+    pub after: String,
+}
+
+impl Highlight {
+    /// Highlights with `**bold**` markers, change `before` and `after` for e.g. `<mark>`.
+    pub fn new<S: AsRef<str>>(keywords: &[S]) -> Highlight {
+        let mut keywords: Vec<Vec<String>> = keywords
+            .iter()
+            .map(|k| k.as_ref().trim())
+            .filter(|k| !k.is_empty())
+            .map(|k| k.split_word_bounds().map(fold).collect())
+            .collect();
+        keywords.sort_by_key(|k| std::cmp::Reverse(k.len()));
+        Highlight {
+            keywords,
+            before: String::from("**"),
+            after: String::from("**"),
+        }
+    }
+}
+
+// what a piece of text is compared as: lowercased, and any whitespace as a single space
+fn fold(piece: &str) -> String {
+    if piece.trim().is_empty() {
+        String::from(" ")
+    } else {
+        piece.to_lowercase()
+    }
+}
+
+impl Transform for Highlight {
+    fn apply(&self, text: &str) -> String {
+        let pieces: Vec<&str> = text.split_word_bounds().collect();
+        let folded: Vec<String> = pieces.iter().map(|piece| fold(piece)).collect();
+        let mut out = String::with_capacity(text.len());
+        let mut i = 0;
+        while i < pieces.len() {
+            match self.keywords.iter().find(|k| folded[i..].starts_with(k)) {
+                Some(keyword) => {
+                    out.push_str(&self.before);
+                    out.push_str(&pieces[i..i + keyword.len()].concat());
+                    out.push_str(&self.after);
+                    i += keyword.len();
+                }
+                None => {
+                    out.push_str(pieces[i]);
+                    i += 1;
+                }
+            }
+        }
+        out
+    }
+}
+
+pub struct Strategy {
+    template: Template,
+    steps: Vec<Box<dyn Transform>>,
+}
+
+impl Strategy {
+    pub fn new(template: Template) -> Strategy {
+        Strategy {
+            template,
+            steps: vec![],
+        }
+    }
+
+    /// Adds a step, run after the ones already added.
+    pub fn then<T: Transform + 'static>(mut self, step: T) -> Strategy {
+        self.steps.push(Box::new(step));
+        self
+    }
+
+    pub fn apply(&self, item: &dyn Summary) -> String {
+        let mut text = self.template.render(item);
+        for step in &self.steps {
+            text = step.apply(&text);
+        }
+        text
+    }
+
+    /// Reads `template | step | step` where a step is `truncate N`, `first-sentence` or
+    /// `highlight word, word`.
+    pub fn parse(text: &str) -> Result<Strategy, String> {
+        let mut parts = text.split('|');
+        let template = Template::parse(parts.next().unwrap_or("").trim())?;
+        let mut strategy = Strategy::new(template);
+
+        for step in parts {
+            let step = step.trim();
+            let (name, arg) = match step.find(char::is_whitespace) {
+                Some(i) => (&step[..i], step[i..].trim()),
+                None => (step, ""),
+            };
+            strategy = match name {
+                "truncate" => {
+                    let max = arg
+                        .parse()
+                        .map_err(|_| format!("truncate needs a length, got {:?}", arg))?;
+                    strategy.then(Truncate { max })
+                }
+                "first-sentence" if arg.is_empty() => strategy.then(FirstSentence),
+                "highlight" if !arg.is_empty() => {
+                    let keywords: Vec<&str> = arg.split(',').collect();
+                    strategy.then(Highlight::new(&keywords))
+                }
+                _ => return Err(format!("unknown step {:?}", step)),
+            };
+        }
+        Ok(strategy)
+    }
+}
+
+impl Default for Strategy {
+    /// Just what the type's own `summarize` says.
+    fn default() -> Strategy {
+        Strategy::new(Template {
+            pieces: vec![Piece::Field(String::from("summary"))],
+        })
+    }
+}
+
+/// Picks a Strategy by `Summary::kind`, falling back to one for every other kind.
+#[derive(Default)]
+pub struct Summarizer {
+    strategies: HashMap<String, Strategy>,
+    fallback: Strategy,
+}
+
+impl Summarizer {
+    pub fn new() -> Summarizer {
+        Summarizer::default()
+    }
+
+    pub fn set(&mut self, kind: &str, strategy: Strategy) {
+        self.strategies.insert(kind.to_string(), strategy);
+    }
+
+    pub fn set_fallback(&mut self, strategy: Strategy) {
+        self.fallback = strategy;
+    }
+
+    pub fn strategy_for(&self, item: &dyn Summary) -> &Strategy {
+        self.strategies.get(item.kind()).unwrap_or(&self.fallback)
+    }
+
+    pub fn summarize(&self, item: &dyn Summary) -> String {
+        self.strategy_for(item).apply(item)
+    }
+
+    /// One `kind = strategy` per line, `*` for the fallback. Blank lines and lines starting
+    /// with `#` are skipped.
+    pub fn parse(config: &str) -> Result<Summarizer, StrategyError> {
+        let mut summarizer = Summarizer::new();
+        for (i, line) in config.lines().enumerate() {
+            let error = |message: String| StrategyError {
+                line: i + 1,
+                message,
+            };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let eq = line
+                .find('=')
+                .ok_or_else(|| error(String::from("expected kind = strategy")))?;
+            let kind = line[..eq].trim();
+            let strategy = Strategy::parse(&line[eq + 1..]).map_err(error)?;
+            match kind {
+                "*" => summarizer.set_fallback(strategy),
+                "" => return Err(error(String::from("missing kind"))),
+                kind => summarizer.set(kind, strategy),
+            }
+        }
+        Ok(summarizer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chapters::chapter_10::{NewsArticle, Tweet};
+
+    fn article() -> NewsArticle {
+        NewsArticle {
+            headline: String::from("Penguins win the Stanley Cup Championship!"),
+            location: String::from("Pittsburgh, PA, USA"),
+            author: String::from("Iceburgh"),
+            content: String::from(
+                "The U.S. side won again. The Pittsburgh Penguins are the best in the NHL.",
+            ),
+            timestamp: 1_622_548_800,
+        }
+    }
+
+    fn tweet() -> Tweet {
+        Tweet {
+            username: String::from("horse_ebooks"),
+            content: String::from("of course, as you probably already know, people"),
+            reply: false,
+            retweet: false,
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn templates_fill_in_fields() {
+        let template = Template::parse("{headline} by {author} on {date} {{{nope}}}").unwrap();
+        assert_eq!(
+            "Penguins win the Stanley Cup Championship! by Iceburgh on 2021-06-01 12:00 {{nope}}",
+            template.render(&article())
+        );
+        assert_eq!(
+            "horse_ebooks: (Read more horse_ebooks...)",
+            Template::parse("{username}: {default}")
+                .unwrap()
+                .render(&tweet())
+        );
+
+        assert!(Template::parse("{author").is_err());
+        assert!(Template::parse("{}").is_err());
+        assert!(Template::parse("a } b").is_err());
+    }
+
+    #[test]
+    fn truncates_on_grapheme_boundaries() {
+        let cut = Truncate { max: 6 };
+        assert_eq!("short", cut.apply("short"));
+        assert_eq!("sixsix", cut.apply("sixsix"));
+        assert_eq!("naïve…", cut.apply("naïve café"));
+        // e + combining acute and a family emoji are one grapheme each
+        assert_eq!("e\u{301}👨‍👩‍👧 ab…", cut.apply("e\u{301}👨‍👩‍👧 abcdef"));
+        assert_eq!("on…", Truncate { max: 4 }.apply("on the ice"));
+        assert_eq!("", Truncate { max: 0 }.apply("anything"));
+    }
+
+    #[test]
+    fn first_sentence_and_highlight() {
+        assert_eq!(
+            "The U.S. side won again.",
+            FirstSentence.apply("The U.S. side won again. The Penguins are the best.")
+        );
+        assert_eq!("no full stop", FirstSentence.apply("  no full stop"));
+
+        let highlight = Highlight::new(&["penguins", " cup "]);
+        assert_eq!(
+            "**Penguins** win the **CUP**, cupcakes for all",
+            highlight.apply("Penguins win the CUP, cupcakes for all")
+        );
+    }
+
+    #[test]
+    fn highlights_phrases() {
+        let highlight = Highlight::new(&["machine learning", "machine", "stanley  cup"]);
+        assert_eq!(
+            "**Machine  Learning** on a **machine**, the **Stanley cup**",
+            highlight.apply("Machine  Learning on a machine, the Stanley cup")
+        );
+        // still whole words only, and the phrase has to be all there
+        assert_eq!(
+            "machine learnings, stanley",
+            Highlight::new(&["machine learning", "stanley cup"])
+                .apply("machine learnings, stanley")
+        );
+
+        let summarizer = Summarizer::parse("* = {headline} | highlight stanley cup, win").unwrap();
+        assert_eq!(
+            "Penguins **win** the **Stanley Cup** Championship!",
+            summarizer.summarize(&article())
+        );
+    }
+
+    #[test]
+    fn summarizer_picks_a_strategy_per_kind() {
+        let summarizer = Summarizer::parse(
+            "# tweets are short already
+             tweet = @{username}: {content} | truncate 20
+             article = {content} | first-sentence | highlight SIDE, nhl
+             * = {author} said something",
+        )
+        .unwrap();
+
+        assert_eq!("@horse_ebooks: of c…", summarizer.summarize(&tweet()));
+        assert_eq!(
+            "The U.S. **side** won again.",
+            summarizer.summarize(&article())
+        );
+
+        let mut summarizer = summarizer;
+        summarizer.set(
+            "tweet",
+            Strategy::default().then(|text: &str| text.to_uppercase()),
+        );
+        assert_eq!(
+            "HORSE_EBOOKS OF COURSE, AS YOU PROBABLY ALREADY KNOW, PEOPLE",
+            tweet().summarize_with(summarizer.strategy_for(&tweet()))
+        );
+    }
+
+    #[test]
+    fn bad_config_reports_the_line() {
+        let error = Summarizer::parse("tweet = {content}\n\narticle = {headline} | shout")
+            .err()
+            .unwrap();
+        assert_eq!(3, error.line);
+        assert_eq!("line 3: unknown step \"shout\"", error.to_string());
+
+        assert_eq!(1, Summarizer::parse("tweet {content}").err().unwrap().line);
+        assert!(Summarizer::parse("tweet = {content} | truncate lots").is_err());
+        assert!(Summarizer::parse(" = {content}").is_err());
+    }
+}
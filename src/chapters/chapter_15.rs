@@ -1,3 +1,8 @@
+// 15.1 follow up: the cons list below as a proper persistent list
+pub mod cons_list;
+// 15.2/15.3 follow up: MyBox and CustomSmartPointer as pointers that can be checked in tests
+pub mod instrumented;
+
 pub fn smart_pointers() {
     /*
     Recall from C++ days a pointer is just that it points to a location in memory
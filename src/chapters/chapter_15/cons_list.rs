@@ -0,0 +1,302 @@
+// The chapter's cons list grown into something usable. Lists are persistent: `cons` never
+// changes a list, it returns a new one whose tail *is* the old list, so any number of lists can
+// share the same tail. List uses Rc for that and ArcList uses Arc so lists can cross threads,
+// otherwise they're the same code.
+//
+// Dropping a long list the obvious way recurses once per node and overflows the stack somewhere
+// around a few hundred thousand nodes, so both types unlink their nodes in a loop instead.
+
+use std::fmt;
+use std::iter::FromIterator;
+use std::rc::Rc;
+use std::sync::Arc;
+
+macro_rules! cons_list {
+    ($(#[$meta:meta])* $list:ident, $node:ident, $iter:ident, $ptr:ident) => {
+        struct $node<T> {
+            head: T,
+            tail: Option<$ptr<$node<T>>>,
+        }
+
+        $(#[$meta])*
+        pub struct $list<T> {
+            first: Option<$ptr<$node<T>>>,
+        }
+
+        impl<T> $list<T> {
+            /// The empty list, Nil.
+            pub fn new() -> $list<T> {
+                $list { first: None }
+            }
+
+            /// A new list with `head` in front of this one. This list is shared, not copied.
+            pub fn cons(&self, head: T) -> $list<T> {
+                $list {
+                    first: Some($ptr::new($node {
+                        head,
+                        tail: self.first.clone(),
+                    })),
+                }
+            }
+
+            pub fn head(&self) -> Option<&T> {
+                self.first.as_ref().map(|node| &node.head)
+            }
+
+            /// Everything after the head, `None` for the empty list. Shares this list's nodes.
+            pub fn tail(&self) -> Option<$list<T>> {
+                self.first.as_ref().map(|node| $list {
+                    first: node.tail.clone(),
+                })
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.first.is_none()
+            }
+
+            /// Walks the list, so O(n).
+            pub fn len(&self) -> usize {
+                self.iter().count()
+            }
+
+            pub fn iter(&self) -> $iter<'_, T> {
+                $iter {
+                    next: self.first.as_deref(),
+                }
+            }
+
+            /// True when both lists start at the very same node, not just equal values.
+            pub fn ptr_eq(&self, other: &$list<T>) -> bool {
+                match (&self.first, &other.first) {
+                    (Some(a), Some(b)) => $ptr::ptr_eq(a, b),
+                    (None, None) => true,
+                    _ => false,
+                }
+            }
+
+            pub fn map<U, F: FnMut(&T) -> U>(&self, f: F) -> $list<U> {
+                self.iter().map(f).collect()
+            }
+
+            pub fn fold<B, F: FnMut(B, &T) -> B>(&self, init: B, f: F) -> B {
+                self.iter().fold(init, f)
+            }
+        }
+
+        impl<T: Clone> $list<T> {
+            /// A reversed copy. Nothing can be shared since every node gets a new tail.
+            pub fn reverse(&self) -> $list<T> {
+                self.fold($list::new(), |reversed, item| reversed.cons(item.clone()))
+            }
+        }
+
+        impl<T> Drop for $list<T> {
+            fn drop(&mut self) {
+                let mut next = self.first.take();
+                // stop as soon as a node is still shared, whoever else holds it will drop the rest.
+                // into_inner rather than try_unwrap: two threads letting go of the last two
+                // handles at once could both fail try_unwrap, leaving the tail to drop recursively,
+                // while into_inner hands the node to exactly one of them
+                while let Some(node) = next {
+                    next = $ptr::into_inner(node).and_then(|mut node| node.tail.take());
+                }
+            }
+        }
+
+        impl<T> Clone for $list<T> {
+            /// Cheap, just another pointer to the same first node.
+            fn clone(&self) -> $list<T> {
+                $list {
+                    first: self.first.clone(),
+                }
+            }
+        }
+
+        impl<T> Default for $list<T> {
+            fn default() -> $list<T> {
+                $list::new()
+            }
+        }
+
+        impl<T> FromIterator<T> for $list<T> {
+            /// The list comes out in the same order as the iterator.
+            fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> $list<T> {
+                let items: Vec<T> = iter.into_iter().collect();
+                let mut list = $list::new();
+                for item in items.into_iter().rev() {
+                    list = list.cons(item);
+                }
+                list
+            }
+        }
+
+        impl<'a, T> IntoIterator for &'a $list<T> {
+            type Item = &'a T;
+            type IntoIter = $iter<'a, T>;
+
+            fn into_iter(self) -> $iter<'a, T> {
+                self.iter()
+            }
+        }
+
+        impl<T: PartialEq> PartialEq for $list<T> {
+            fn eq(&self, other: &$list<T>) -> bool {
+                self.iter().eq(other.iter())
+            }
+        }
+
+        impl<T: Eq> Eq for $list<T> {}
+
+        impl<T: fmt::Debug> fmt::Debug for $list<T> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.debug_list().entries(self.iter()).finish()
+            }
+        }
+
+        impl<T: fmt::Display> fmt::Display for $list<T> {
+            /// Lisp style, `(1 2 3)` and `()` for Nil.
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "(")?;
+                for (i, item) in self.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, ")")
+            }
+        }
+
+        pub struct $iter<'a, T> {
+            next: Option<&'a $node<T>>,
+        }
+
+        impl<'a, T> Iterator for $iter<'a, T> {
+            type Item = &'a T;
+
+            fn next(&mut self) -> Option<&'a T> {
+                self.next.map(|node| {
+                    self.next = node.tail.as_deref();
+                    &node.head
+                })
+            }
+        }
+    };
+}
+
+cons_list!(
+    /// A persistent singly linked list sharing its nodes through Rc.
+    List,
+    Node,
+    Iter,
+    Rc
+);
+
+cons_list!(
+    /// List with Arc instead of Rc, so it is Send and Sync when T is.
+    ArcList,
+    ArcNode,
+    ArcIter,
+    Arc
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Barrier;
+    use std::thread;
+
+    #[test]
+    fn cons_shares_the_tail() {
+        let a: List<i32> = vec![5, 10].into_iter().collect();
+        let b = a.cons(3);
+        let c = a.cons(4);
+
+        assert_eq!("(3 5 10)", b.to_string());
+        assert_eq!("(4 5 10)", c.to_string());
+        assert!(b.tail().unwrap().ptr_eq(&a));
+        assert!(c.tail().unwrap().ptr_eq(&a));
+        assert_eq!(Some(&5), a.head());
+        assert_eq!(2, a.len());
+
+        let nil = List::<i32>::new();
+        assert!(nil.is_empty());
+        assert_eq!(None, nil.head());
+        assert!(nil.tail().is_none());
+        assert_eq!("()", nil.to_string());
+        assert_eq!(List::new(), a.tail().unwrap().tail().unwrap());
+    }
+
+    #[test]
+    fn map_fold_and_reverse() {
+        let list: List<i32> = (1..=4).collect();
+        assert_eq!(vec![&1, &2, &3, &4], list.iter().collect::<Vec<_>>());
+        assert_eq!(10, list.fold(0, |sum, n| sum + n));
+        assert_eq!("(1 4 9 16)", list.map(|n| n * n).to_string());
+        assert_eq!("(4 3 2 1)", list.reverse().to_string());
+        assert_eq!(list, list.reverse().reverse());
+        assert_eq!(
+            "[\"a\", \"b\"]",
+            format!("{:?}", List::new().cons("b").cons("a"))
+        );
+
+        let mut total = 0;
+        for n in &list {
+            total += n;
+        }
+        assert_eq!(10, total);
+    }
+
+    #[test]
+    fn long_lists_drop_without_overflowing() {
+        let list: List<u32> = (0..1_000_000).collect();
+        let shared = list.tail().unwrap();
+        drop(list);
+        // the nodes `shared` still points to survived the first drop
+        assert_eq!(Some(&1), shared.head());
+        drop(shared);
+
+        let list: ArcList<u32> = (0..1_000_000).collect();
+        drop(list);
+    }
+
+    #[test]
+    fn last_two_handles_dropped_at_once() {
+        // both threads let go together, whichever ends up owning the nodes must still drop them
+        // a loop at a time on its small stack
+        for _ in 0..4 {
+            let list: ArcList<u32> = (0..200_000).collect();
+            let barrier = Arc::new(Barrier::new(2));
+            let handles: Vec<_> = vec![list.clone(), list]
+                .into_iter()
+                .map(|list| {
+                    let barrier = Arc::clone(&barrier);
+                    thread::Builder::new()
+                        .stack_size(64 * 1024)
+                        .spawn(move || {
+                            barrier.wait();
+                            drop(list);
+                        })
+                        .unwrap()
+                })
+                .collect();
+            for handle in handles {
+                handle.join().unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn arc_lists_cross_threads() {
+        let list: ArcList<String> = vec!["b".to_string(), "c".to_string()].into_iter().collect();
+        let handles: Vec<_> = (0..3)
+            .map(|i| {
+                let list = list.clone();
+                thread::spawn(move || list.cons(i.to_string()).to_string())
+            })
+            .collect();
+        let results: Vec<String> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(vec!["(0 b c)", "(1 b c)", "(2 b c)"], results);
+        assert_eq!("(b c)", list.to_string());
+    }
+}
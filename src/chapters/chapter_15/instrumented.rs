@@ -0,0 +1,387 @@
+// MyBox and CustomSmartPointer from 15.2/15.3 with something to show for it. MyBox gets DerefMut,
+// TrackedBox reports every allocation to a registry so a test can check nothing it made is still
+// alive, and DropRecorder writes down the order things are dropped in instead of printing it.
+
+use std::any;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread::{self, ThreadId};
+
+/// The chapter's MyBox, on the heap this time and mutable through `*`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MyBox<T>(Box<T>);
+
+impl<T> MyBox<T> {
+    pub fn new(x: T) -> MyBox<T> {
+        MyBox(Box::new(x))
+    }
+
+    pub fn into_inner(self) -> T {
+        *self.0
+    }
+}
+
+impl<T> Deref for MyBox<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for MyBox<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+// every live TrackedBox, by id. Ids only go up so a Scope can tell which ones came after it.
+struct Entry {
+    thread: ThreadId,
+    bytes: usize,
+    type_name: &'static str,
+}
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+static REGISTRY: Mutex<BTreeMap<u64, Entry>> = Mutex::new(BTreeMap::new());
+
+fn registry() -> std::sync::MutexGuard<'static, BTreeMap<u64, Entry>> {
+    // a test panicking while holding the lock shouldn't take every other test down with it
+    REGISTRY
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Stats {
+    pub allocations: usize,
+    pub bytes: usize,
+}
+
+/// Every TrackedBox alive right now, on any thread.
+pub fn live() -> Stats {
+    registry()
+        .values()
+        .fold(Stats::default(), |stats, entry| Stats {
+            allocations: stats.allocations + 1,
+            bytes: stats.bytes + entry.bytes,
+        })
+}
+
+/// A Box that registers itself while it is alive. `bytes` is the size of the heap allocation,
+/// so whatever T owns itself (a String's buffer, say) isn't counted.
+pub struct TrackedBox<T> {
+    // only None once into_inner has taken the value, on the way to being dropped
+    value: Option<Box<T>>,
+    id: u64,
+}
+
+impl<T> TrackedBox<T> {
+    pub fn new(value: T) -> TrackedBox<T> {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        registry().insert(
+            id,
+            Entry {
+                thread: thread::current().id(),
+                bytes: mem::size_of::<T>(),
+                type_name: any::type_name::<T>(),
+            },
+        );
+        TrackedBox {
+            value: Some(Box::new(value)),
+            id,
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn into_inner(mut self) -> T {
+        // can't move out of a type with Drop, so take the box and let Drop unregister the rest
+        let value = self.value.take().expect("TrackedBox already emptied");
+        *value
+    }
+}
+
+impl<T> Drop for TrackedBox<T> {
+    fn drop(&mut self) {
+        registry().remove(&self.id);
+    }
+}
+
+impl<T> Deref for TrackedBox<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value.as_deref().expect("TrackedBox already emptied")
+    }
+}
+
+impl<T> DerefMut for TrackedBox<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.value
+            .as_deref_mut()
+            .expect("TrackedBox already emptied")
+    }
+}
+
+impl<T: Clone> Clone for TrackedBox<T> {
+    /// A new allocation, tracked on its own.
+    fn clone(&self) -> TrackedBox<T> {
+        TrackedBox::new((**self).clone())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for TrackedBox<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("TrackedBox").field(&**self).finish()
+    }
+}
+
+/// Watches the TrackedBoxes the current thread creates from here on. Tests run on threads of
+/// their own, so boxes from other tests running alongside never show up.
+pub struct Scope {
+    first_id: u64,
+    thread: ThreadId,
+}
+
+/// A TrackedBox that is still alive when its Scope checks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Leak {
+    pub id: u64,
+    pub bytes: usize,
+    pub type_name: &'static str,
+}
+
+impl Scope {
+    pub fn new() -> Scope {
+        Scope {
+            first_id: NEXT_ID.load(Ordering::Relaxed),
+            thread: thread::current().id(),
+        }
+    }
+
+    /// Boxes this scope made that haven't been dropped yet, wherever they have moved to since.
+    pub fn leaks(&self) -> Vec<Leak> {
+        registry()
+            .range(self.first_id..)
+            .filter(|(_, entry)| entry.thread == self.thread)
+            .map(|(&id, entry)| Leak {
+                id,
+                bytes: entry.bytes,
+                type_name: entry.type_name,
+            })
+            .collect()
+    }
+
+    pub fn live(&self) -> Stats {
+        let leaks = self.leaks();
+        Stats {
+            allocations: leaks.len(),
+            bytes: leaks.iter().map(|leak| leak.bytes).sum(),
+        }
+    }
+
+    /// Panics, listing them, if anything this scope allocated is still alive.
+    pub fn assert_freed(&self) {
+        let leaks = self.leaks();
+        if !leaks.is_empty() {
+            let list: Vec<String> = leaks
+                .iter()
+                .map(|leak| format!("#{} {} ({} bytes)", leak.id, leak.type_name, leak.bytes))
+                .collect();
+            panic!(
+                "{} tracked allocation(s) still alive: {}",
+                leaks.len(),
+                list.join(", ")
+            );
+        }
+    }
+}
+
+impl Default for Scope {
+    fn default() -> Scope {
+        Scope::new()
+    }
+}
+
+/// CustomSmartPointer's println, kept in a list you can look at afterwards.
+#[derive(Debug, Clone, Default)]
+pub struct DropRecorder {
+    dropped: Rc<RefCell<Vec<String>>>,
+}
+
+impl DropRecorder {
+    pub fn new() -> DropRecorder {
+        DropRecorder::default()
+    }
+
+    /// Wraps `value` so that dropping it records `label`.
+    pub fn track<T>(&self, label: &str, value: T) -> Recorded<T> {
+        Recorded {
+            value,
+            label: label.to_string(),
+            dropped: Rc::clone(&self.dropped),
+        }
+    }
+
+    /// Labels in the order their values were dropped.
+    pub fn order(&self) -> Vec<String> {
+        self.dropped.borrow().clone()
+    }
+
+    pub fn clear(&self) {
+        self.dropped.borrow_mut().clear();
+    }
+}
+
+pub struct Recorded<T> {
+    value: T,
+    label: String,
+    dropped: Rc<RefCell<Vec<String>>>,
+}
+
+impl<T> Recorded<T> {
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+}
+
+impl<T> Drop for Recorded<T> {
+    fn drop(&mut self) {
+        self.dropped.borrow_mut().push(mem::take(&mut self.label));
+    }
+}
+
+impl<T> Deref for Recorded<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for Recorded<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Recorded<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Recorded")
+            .field("label", &self.label)
+            .field("value", &self.value)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn my_box_derefs_both_ways() {
+        let mut b = MyBox::new(String::from("Rust"));
+        b.push_str("acean");
+        *b = b.to_uppercase();
+        assert_eq!("RUSTACEAN", &*b);
+        assert_eq!(9, b.len());
+        assert_eq!("RUSTACEAN", b.into_inner());
+    }
+
+    #[test]
+    fn scope_sees_what_it_allocated() {
+        let scope = Scope::new();
+        {
+            let a = TrackedBox::new(5u64);
+            let mut b = TrackedBox::new([0u8; 100]);
+            b[0] = *a as u8;
+            assert_eq!(
+                Stats {
+                    allocations: 2,
+                    bytes: 108
+                },
+                scope.live()
+            );
+            let c = b.clone();
+            assert_eq!(5, c[0]);
+            assert_eq!(3, scope.live().allocations);
+            assert_eq!(5, a.into_inner());
+            assert_eq!(2, scope.live().allocations);
+            // the value comes out whole, with its own heap data still there
+            let s = TrackedBox::new(String::from("kept"));
+            assert_eq!("TrackedBox(\"kept\")", format!("{:?}", s));
+            assert_eq!("kept", s.into_inner());
+            assert_eq!(2, scope.live().allocations);
+        }
+        scope.assert_freed();
+
+        // dropped on another thread still counts as freed
+        let boxed = TrackedBox::new(String::from("moving"));
+        thread::spawn(move || assert_eq!("moving", *boxed))
+            .join()
+            .unwrap();
+        scope.assert_freed();
+    }
+
+    #[test]
+    fn leaks_are_reported() {
+        let scope = Scope::new();
+        let kept = TrackedBox::new(1u32);
+        mem::forget(TrackedBox::new(2u16));
+
+        let leaks = scope.leaks();
+        assert_eq!(2, leaks.len());
+        assert_eq!(kept.id(), leaks[0].id);
+        assert_eq!("u16", leaks[1].type_name);
+        assert_eq!(2, leaks[1].bytes);
+
+        drop(kept);
+        let message = std::panic::catch_unwind(|| scope.assert_freed())
+            .unwrap_err()
+            .downcast::<String>()
+            .unwrap();
+        assert!(message.starts_with("1 tracked allocation(s) still alive: #"));
+        assert!(message.ends_with("u16 (2 bytes)"));
+    }
+
+    #[test]
+    fn records_drop_order() {
+        struct Pair {
+            _first: Recorded<i32>,
+            _second: Recorded<i32>,
+        }
+
+        let recorder = DropRecorder::new();
+        {
+            let _c = recorder.track("c", 1);
+            let _d = recorder.track("d", 2);
+            let early = recorder.track("early", 3);
+            let _pair = Pair {
+                _first: recorder.track("field 1", 4),
+                _second: recorder.track("field 2", 5),
+            };
+            drop(early);
+        }
+        // locals go in reverse, fields in the order they're declared
+        assert_eq!(
+            vec!["early", "field 1", "field 2", "d", "c"],
+            recorder.order()
+        );
+
+        recorder.clear();
+        let mut v = recorder.track("vec", vec![1]);
+        v.push(2);
+        assert_eq!("vec", v.label());
+        drop(v);
+        assert_eq!(vec!["vec"], recorder.order());
+    }
+}
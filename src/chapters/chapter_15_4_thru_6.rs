@@ -1,6 +1,12 @@
 use std::cell::{RefCell, RefMut};
 use std::rc::{Rc, Weak};
 
+// 15.6 follow ups: finding the cycles reference_cycles_can_leak warns about, and its Node tree
+// made reusable, both with Rc/Weak and as an index based arena
+pub mod arena;
+pub mod rc_graph;
+pub mod tree;
+
 
 pub fn reference_count_smart_pointer() {
     /*
@@ -223,6 +229,31 @@ impl<'a, T> LimitTracker<'a, T>
 mod tests {
     use super::*;
 
+    #[test]
+    fn rc_graph_finds_the_list_cycle() {
+        use super::rc_graph::RcGraph;
+
+        // the cycle from reference_cycles_can_leak
+        let a = Rc::new(List_Cycle::Cons(5, RefCell::new(Rc::new(List_Cycle::Nil))));
+        let b = Rc::new(List_Cycle::Cons(10, RefCell::new(Rc::clone(&a))));
+        if let Some(link) = a.tail() {
+            *link.borrow_mut() = Rc::clone(&b);
+        }
+
+        let graph = RcGraph::walk("a", &a);
+        assert_eq!(
+            "2 objects, 1 strong cycles\n  #0 Cons(5) strong=2 weak=0\n  #1 Cons(10) strong=2 weak=0\n\
+             cycle Cons(5) <-> Cons(10) leaks once 2 outside references are dropped\n",
+            graph.to_string()
+        );
+
+        // break it again so the test doesn't leak
+        if let Some(link) = a.tail() {
+            *link.borrow_mut() = Rc::new(List_Cycle::Nil);
+        }
+        assert!(RcGraph::walk("a", &a).cycles().is_empty());
+    }
+
     struct MockMessenger {
         // changed this
         sent_messages: RefCell<Vec<String>>,
@@ -301,6 +332,22 @@ impl List_Cycle {
     }
 }
 
+// lets rc_graph walk a List_Cycle without following the cycle forever like Debug does
+impl rc_graph::Visit for List_Cycle {
+    fn label(&self) -> String {
+        match self {
+            List_Cycle::Cons(value, _) => format!("Cons({})", value),
+            List_Cycle::Nil => String::from("Nil"),
+        }
+    }
+
+    fn visit(&self, edges: &mut rc_graph::Edges) {
+        if let Some(link) = self.tail() {
+            edges.strong("next", &link.borrow());
+        }
+    }
+}
+
 
 pub fn reference_cycles_can_leak() {
     /*
@@ -378,6 +425,7 @@ pub fn reference_cycles_can_leak() {
 
     */
 
+    // tree::Tree is this Node made generic, with moving, removing and iterating added
     #[derive(Debug)]
     struct Node {
         value: i32,
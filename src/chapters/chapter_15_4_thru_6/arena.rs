@@ -0,0 +1,597 @@
+// The same tree as tree::Tree, but with every node stored in one Vec and linked by index instead
+// of Rc/Weak. No reference counts, no RefCell, and the whole thing serializes as plain data.
+//
+// The catch with indices is that a removed node's slot gets reused, so an old index would
+// quietly point at whatever moved in. Each slot keeps a generation that goes up every time it
+// is freed, and a NodeId only works while its generation still matches.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct NodeId {
+    index: u32,
+    generation: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArenaError {
+    /// The node was removed, this id is stale.
+    Removed(NodeId),
+    /// The node would end up below itself.
+    WouldCycle,
+    /// Deserialized data that doesn't hold together, saying what was wrong.
+    Corrupt(&'static str),
+}
+
+impl fmt::Display for ArenaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArenaError::Removed(id) => write!(
+                f,
+                "node {} (generation {}) has been removed",
+                id.index, id.generation
+            ),
+            ArenaError::WouldCycle => write!(f, "a node can't be moved under itself"),
+            ArenaError::Corrupt(problem) => write!(f, "corrupt arena: {}", problem),
+        }
+    }
+}
+
+impl Error for ArenaError {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Node<T> {
+    value: T,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Slot<T> {
+    generation: u32,
+    node: Option<Node<T>>,
+}
+
+// Loaded data goes through RawArena first so a bad free list or dangling link is an error
+// rather than a panic, or a live node silently overwritten, later on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "RawArena<T>")]
+pub struct Arena<T> {
+    slots: Vec<Slot<T>>,
+    // indexes of empty slots, reused before the Vec grows
+    free: Vec<u32>,
+    len: usize,
+}
+
+// Arena's fields as they come off the wire, not yet checked
+#[derive(Deserialize)]
+struct RawArena<T> {
+    slots: Vec<Slot<T>>,
+    free: Vec<u32>,
+    len: usize,
+}
+
+impl<T> TryFrom<RawArena<T>> for Arena<T> {
+    type Error = ArenaError;
+
+    fn try_from(raw: RawArena<T>) -> Result<Arena<T>, ArenaError> {
+        let arena = Arena {
+            slots: raw.slots,
+            free: raw.free,
+            len: raw.len,
+        };
+        let live = arena
+            .slots
+            .iter()
+            .filter(|slot| slot.node.is_some())
+            .count();
+        if arena.len != live {
+            return Err(ArenaError::Corrupt("len doesn't match the live nodes"));
+        }
+        let mut free = HashSet::new();
+        for &index in &arena.free {
+            match arena.slots.get(index as usize) {
+                None => return Err(ArenaError::Corrupt("free slot out of range")),
+                Some(slot) if slot.node.is_some() => {
+                    return Err(ArenaError::Corrupt("free slot holds a live node"))
+                }
+                Some(_) if !free.insert(index) => {
+                    return Err(ArenaError::Corrupt("free slot listed twice"))
+                }
+                Some(_) => {}
+            }
+        }
+        if free.len() + live != arena.slots.len() {
+            return Err(ArenaError::Corrupt("empty slot missing from the free list"));
+        }
+
+        // every link points at a live node and is matched from the other end
+        let mut roots = vec![];
+        for (index, slot) in arena.slots.iter().enumerate() {
+            let node = match &slot.node {
+                Some(node) => node,
+                None => continue,
+            };
+            let id = NodeId {
+                index: index as u32,
+                generation: slot.generation,
+            };
+            match node.parent {
+                None => roots.push(id),
+                Some(parent) => match arena.node(parent) {
+                    Some(parent) if parent.children.contains(&id) => {}
+                    _ => return Err(ArenaError::Corrupt("parent link not matched")),
+                },
+            }
+            for &child in &node.children {
+                match arena.node(child) {
+                    Some(child) if child.parent == Some(id) => {}
+                    _ => return Err(ArenaError::Corrupt("child link not matched")),
+                }
+            }
+        }
+        // and every node is reached from a root exactly once, so there are no loops or nodes
+        // listed twice under one parent
+        let mut reached = 0;
+        let mut queue: VecDeque<NodeId> = roots.into();
+        while let Some(id) = queue.pop_front() {
+            reached += 1;
+            if reached > live {
+                break;
+            }
+            queue.extend(arena.children(id).iter().copied());
+        }
+        if reached != live {
+            return Err(ArenaError::Corrupt("nodes form a loop or are listed twice"));
+        }
+        Ok(arena)
+    }
+}
+
+impl<T> Arena<T> {
+    pub fn new() -> Arena<T> {
+        Arena::with_capacity(0)
+    }
+
+    /// Room for `capacity` nodes before the arena has to grow.
+    pub fn with_capacity(capacity: usize) -> Arena<T> {
+        Arena {
+            slots: Vec::with_capacity(capacity),
+            free: vec![],
+            len: 0,
+        }
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        self.slots
+            .reserve(additional.saturating_sub(self.free.len()));
+    }
+
+    /// Live nodes, across every tree in the arena.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// False once the node has been removed, even if its slot has been reused since.
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.node(id).is_some()
+    }
+
+    fn node(&self, id: NodeId) -> Option<&Node<T>> {
+        self.slots
+            .get(id.index as usize)
+            .filter(|slot| slot.generation == id.generation)
+            .and_then(|slot| slot.node.as_ref())
+    }
+
+    fn node_mut(&mut self, id: NodeId) -> Option<&mut Node<T>> {
+        self.slots
+            .get_mut(id.index as usize)
+            .filter(|slot| slot.generation == id.generation)
+            .and_then(|slot| slot.node.as_mut())
+    }
+
+    fn check(&self, id: NodeId) -> Result<&Node<T>, ArenaError> {
+        self.node(id).ok_or(ArenaError::Removed(id))
+    }
+
+    fn alloc(&mut self, value: T, parent: Option<NodeId>) -> NodeId {
+        let node = Node {
+            value,
+            parent,
+            children: vec![],
+        };
+        self.len += 1;
+        match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index as usize];
+                slot.node = Some(node);
+                NodeId {
+                    index,
+                    generation: slot.generation,
+                }
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    node: Some(node),
+                });
+                NodeId {
+                    index: self.slots.len() as u32 - 1,
+                    generation: 0,
+                }
+            }
+        }
+    }
+
+    /// A new root with no children.
+    pub fn new_node(&mut self, value: T) -> NodeId {
+        self.alloc(value, None)
+    }
+
+    pub fn get(&self, id: NodeId) -> Option<&T> {
+        self.node(id).map(|node| &node.value)
+    }
+
+    pub fn get_mut(&mut self, id: NodeId) -> Option<&mut T> {
+        self.node_mut(id).map(|node| &mut node.value)
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.node(id).and_then(|node| node.parent)
+    }
+
+    pub fn is_root(&self, id: NodeId) -> bool {
+        self.parent(id).is_none()
+    }
+
+    /// Empty for a removed node as well as a leaf.
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        self.node(id).map_or(&[], |node| &node.children)
+    }
+
+    /// Adds a new node holding `value` as the last child of `parent`.
+    pub fn add_child(&mut self, parent: NodeId, value: T) -> Result<NodeId, ArenaError> {
+        self.check(parent)?;
+        let child = self.alloc(value, Some(parent));
+        self.node_mut(parent).unwrap().children.push(child);
+        Ok(child)
+    }
+
+    /// Adds a child for every value, in order, reserving room for them all up front.
+    pub fn add_children<I>(&mut self, parent: NodeId, values: I) -> Result<Vec<NodeId>, ArenaError>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        self.check(parent)?;
+        let values = values.into_iter();
+        self.reserve(values.size_hint().0);
+        let children: Vec<NodeId> = values
+            .map(|value| self.alloc(value, Some(parent)))
+            .collect();
+        self.node_mut(parent)
+            .unwrap()
+            .children
+            .extend_from_slice(&children);
+        Ok(children)
+    }
+
+    /// Moves `child` and its subtree to be the last child of `parent`, detaching it first.
+    pub fn append(&mut self, parent: NodeId, child: NodeId) -> Result<(), ArenaError> {
+        self.check(parent)?;
+        self.check(child)?;
+        if self.path_to_root(parent).contains(&child) {
+            return Err(ArenaError::WouldCycle);
+        }
+        self.detach(child)?;
+        self.node_mut(child).unwrap().parent = Some(parent);
+        self.node_mut(parent).unwrap().children.push(child);
+        Ok(())
+    }
+
+    /// Moves `id` and its subtree under `new_parent`.
+    pub fn reparent(&mut self, id: NodeId, new_parent: NodeId) -> Result<(), ArenaError> {
+        self.append(new_parent, id)
+    }
+
+    /// Takes the `index`th child of `parent` out of the tree and makes it a root. The node is
+    /// still in the arena; use `remove` to get rid of it.
+    pub fn remove_child(&mut self, parent: NodeId, index: usize) -> Option<NodeId> {
+        let children = &mut self.node_mut(parent)?.children;
+        if index >= children.len() {
+            return None;
+        }
+        let child = children.remove(index);
+        self.node_mut(child).unwrap().parent = None;
+        Some(child)
+    }
+
+    /// Cuts `id` and its subtree loose from its parent, making it a root.
+    pub fn detach(&mut self, id: NodeId) -> Result<(), ArenaError> {
+        if let Some(parent) = self.check(id)?.parent {
+            self.node_mut(parent)
+                .unwrap()
+                .children
+                .retain(|child| *child != id);
+            self.node_mut(id).unwrap().parent = None;
+        }
+        Ok(())
+    }
+
+    /// Removes `id` and everything below it from the arena, handing back its value. Every id
+    /// into the subtree goes stale.
+    pub fn remove(&mut self, id: NodeId) -> Result<T, ArenaError> {
+        self.detach(id)?;
+        let mut pending = vec![id];
+        let mut value = None;
+        while let Some(next) = pending.pop() {
+            let slot = &mut self.slots[next.index as usize];
+            let node = slot.node.take().unwrap();
+            slot.generation = slot.generation.wrapping_add(1);
+            self.free.push(next.index);
+            self.len -= 1;
+            pending.extend(node.children);
+            if next == id {
+                value = Some(node.value);
+            }
+        }
+        Ok(value.unwrap())
+    }
+
+    /// `id`, its parent, and so on up to the root. Empty if `id` was removed.
+    pub fn path_to_root(&self, id: NodeId) -> Vec<NodeId> {
+        let mut path = vec![];
+        let mut next = self.node(id).map(|_| id);
+        while let Some(id) = next {
+            path.push(id);
+            next = self.parent(id);
+        }
+        path
+    }
+
+    /// How many ancestors `id` has, 0 for a root.
+    pub fn depth(&self, id: NodeId) -> usize {
+        self.path_to_root(id).len().saturating_sub(1)
+    }
+
+    /// `id` and every node below it, parents before children.
+    pub fn depth_first(&self, id: NodeId) -> DepthFirst<'_, T> {
+        DepthFirst {
+            arena: self,
+            stack: self.node(id).map(|_| id).into_iter().collect(),
+        }
+    }
+
+    /// `id` and every node below it, a level at a time.
+    pub fn breadth_first(&self, id: NodeId) -> BreadthFirst<'_, T> {
+        BreadthFirst {
+            arena: self,
+            queue: self.node(id).map(|_| id).into_iter().collect(),
+        }
+    }
+}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Arena<T> {
+        Arena::new()
+    }
+}
+
+impl<T> Index<NodeId> for Arena<T> {
+    type Output = T;
+
+    /// Panics on a stale id, use `get` when that can happen.
+    fn index(&self, id: NodeId) -> &T {
+        match self.get(id) {
+            Some(value) => value,
+            None => panic!("{}", ArenaError::Removed(id)),
+        }
+    }
+}
+
+impl<T> IndexMut<NodeId> for Arena<T> {
+    fn index_mut(&mut self, id: NodeId) -> &mut T {
+        match self.get_mut(id) {
+            Some(value) => value,
+            None => panic!("{}", ArenaError::Removed(id)),
+        }
+    }
+}
+
+pub struct DepthFirst<'a, T> {
+    arena: &'a Arena<T>,
+    stack: Vec<NodeId>,
+}
+
+impl<'a, T> Iterator for DepthFirst<'a, T> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let id = self.stack.pop()?;
+        // reversed so the first child comes off the stack first
+        self.stack
+            .extend(self.arena.children(id).iter().rev().copied());
+        Some(id)
+    }
+}
+
+pub struct BreadthFirst<'a, T> {
+    arena: &'a Arena<T>,
+    queue: VecDeque<NodeId>,
+}
+
+impl<'a, T> Iterator for BreadthFirst<'a, T> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let id = self.queue.pop_front()?;
+        self.queue.extend(self.arena.children(id).iter().copied());
+        Some(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //      root
+    //     /    \
+    //    a      b
+    //   / \     |
+    //  c   d    e
+    fn sample() -> (Arena<&'static str>, Vec<NodeId>) {
+        let mut arena = Arena::new();
+        let root = arena.new_node("root");
+        let ab = arena.add_children(root, vec!["a", "b"]).unwrap();
+        let cd = arena.add_children(ab[0], vec!["c", "d"]).unwrap();
+        let e = arena.add_child(ab[1], "e").unwrap();
+        (arena, vec![root, ab[0], ab[1], cd[0], cd[1], e])
+    }
+
+    fn names(arena: &Arena<&'static str>, ids: impl Iterator<Item = NodeId>) -> Vec<&'static str> {
+        ids.map(|id| arena[id]).collect()
+    }
+
+    #[test]
+    fn walks_depth_and_breadth_first() {
+        let (arena, ids) = sample();
+        assert_eq!(
+            vec!["root", "a", "c", "d", "b", "e"],
+            names(&arena, arena.depth_first(ids[0]))
+        );
+        assert_eq!(
+            vec!["root", "a", "b", "c", "d", "e"],
+            names(&arena, arena.breadth_first(ids[0]))
+        );
+        assert_eq!(
+            vec!["d", "a", "root"],
+            names(&arena, arena.path_to_root(ids[4]).into_iter())
+        );
+        assert_eq!(2, arena.depth(ids[5]));
+        assert_eq!(6, arena.len());
+    }
+
+    #[test]
+    fn moves_and_removes_subtrees() {
+        let (mut arena, ids) = sample();
+        let (root, a, b, c, e) = (ids[0], ids[1], ids[2], ids[3], ids[5]);
+
+        arena.reparent(a, e).unwrap();
+        assert_eq!(
+            vec!["root", "b", "e", "a", "c", "d"],
+            names(&arena, arena.depth_first(root))
+        );
+        assert_eq!(Err(ArenaError::WouldCycle), arena.reparent(b, c));
+        assert_eq!(Err(ArenaError::WouldCycle), arena.append(a, a));
+
+        assert_eq!(Some(a), arena.remove_child(e, 0));
+        assert!(arena.is_root(a));
+        assert_eq!(None, arena.remove_child(e, 0));
+        assert_eq!(6, arena.len());
+
+        assert_eq!(Ok("a"), arena.remove(a));
+        assert_eq!(3, arena.len());
+        arena[b] = "b2";
+        assert_eq!(
+            vec!["root", "b2", "e"],
+            names(&arena, arena.depth_first(root))
+        );
+    }
+
+    #[test]
+    fn stale_ids_are_caught() {
+        let (mut arena, ids) = sample();
+        let (root, b, e) = (ids[0], ids[2], ids[5]);
+
+        arena.remove(b).unwrap();
+        assert!(!arena.contains(e));
+        assert_eq!(None, arena.get(e));
+        assert_eq!(Err(ArenaError::Removed(e)), arena.add_child(e, "x"));
+        assert_eq!(Err(ArenaError::Removed(b)), arena.remove(b));
+        assert_eq!(0, arena.depth_first(e).count());
+
+        // the freed slots get reused, the old ids still don't reach the new nodes
+        let x = arena.add_child(root, "x").unwrap();
+        let y = arena.add_child(root, "y").unwrap();
+        assert!([b.index, e.index].contains(&x.index));
+        assert!([b.index, e.index].contains(&y.index));
+        assert_eq!(None, arena.get(b));
+        assert_eq!(None, arena.get(e));
+        assert_eq!(Some(&"x"), arena.get(x));
+        assert_eq!(6, arena.len());
+    }
+
+    #[test]
+    fn round_trips_through_json() {
+        let (mut arena, ids) = sample();
+        arena.remove(ids[2]).unwrap();
+        let json = serde_json::to_string(&arena).unwrap();
+        let back: Arena<String> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(
+            vec!["root", "a", "c", "d"],
+            back.depth_first(ids[0])
+                .map(|id| back[id].as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(None, back.get(ids[2]));
+        assert_eq!(4, back.len());
+    }
+
+    #[test]
+    fn corrupt_json_is_an_error() {
+        let (arena, _) = sample();
+        let json = serde_json::to_value(&arena).unwrap();
+        type Edit = fn(&mut serde_json::Value);
+        let load = |edit: Edit| {
+            let mut json = json.clone();
+            edit(&mut json);
+            serde_json::from_value::<Arena<String>>(json)
+        };
+        assert!(load(|_| {}).is_ok());
+
+        let cases: Vec<(Edit, &str)> = vec![
+            (
+                |json| json["free"] = serde_json::json!([99]),
+                "out of range",
+            ),
+            (|json| json["free"] = serde_json::json!([0]), "live node"),
+            (|json| json["len"] = serde_json::json!(2), "len"),
+            (
+                |json| json["slots"][0]["node"]["children"] = serde_json::json!([]),
+                "parent link",
+            ),
+            (
+                |json| json["slots"][0]["node"]["children"][0]["generation"] = 7.into(),
+                "child link",
+            ),
+        ];
+        for (edit, problem) in cases {
+            let error = load(edit).unwrap_err().to_string();
+            assert!(error.contains(problem), "{}", error);
+        }
+
+        // two nodes as each other's parent, with no root between them
+        let mut two = Arena::new();
+        let a = two.new_node(String::from("a"));
+        two.add_child(a, String::from("b")).unwrap();
+        let mut json = serde_json::to_value(&two).unwrap();
+        json["slots"][0]["node"]["parent"] = serde_json::to_value(NodeId {
+            index: 1,
+            generation: 0,
+        })
+        .unwrap();
+        json["slots"][1]["node"]["children"] = serde_json::to_value(vec![a]).unwrap();
+        let error = serde_json::from_value::<Arena<String>>(json).unwrap_err();
+        assert!(error.to_string().contains("loop"), "{}", error);
+    }
+}
@@ -0,0 +1,472 @@
+// A leak finder for Rc/RefCell data. reference_cycles_can_leak builds a cycle that is never
+// freed and even Debug can't show it because printing follows the cycle forever. Here each type
+// describes its own outgoing pointers through the Visit trait and RcGraph walks them once per
+// object, recording strong and weak counts as it goes. Strong cycles come out of that as
+// strongly connected components and the whole thing can be dumped as Graphviz DOT:
+//
+//     let graph = RcGraph::walk("a", &a);
+//     println!("{}", graph);              // counts and cycles
+//     fs::write("a.dot", graph.to_dot())  // dot -Tsvg a.dot > a.svg
+//
+// The walk holds a clone of each object it has found while it runs. Counts are read before
+// that clone is taken so they show what your program holds, not what the walk holds.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::{Rc, Weak};
+
+/// Implemented by anything that holds Rc or Weak pointers worth following.
+pub trait Visit {
+    /// How the object is shown in reports, e.g. `Cons(5)`.
+    fn label(&self) -> String;
+
+    /// Hands every outgoing pointer to `edges`. Borrow any RefCells just for the call.
+    fn visit(&self, edges: &mut Edges);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeKind {
+    Strong,
+    Weak,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edge {
+    pub from: usize,
+    /// `None` for a Weak whose target has already been dropped.
+    pub to: Option<usize>,
+    pub name: String,
+    pub kind: EdgeKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Object {
+    pub label: String,
+    pub strong: usize,
+    pub weak: usize,
+}
+
+/// A set of objects kept alive by each other's strong pointers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle {
+    /// Object indexes, in the order they were found.
+    pub objects: Vec<usize>,
+    /// Strong references to the cycle from outside the walked graph, like local variables.
+    /// Once this reaches zero the cycle has leaked.
+    pub external: usize,
+}
+
+/// Collects the pointers of one object during a walk.
+pub struct Edges<'w> {
+    from: usize,
+    walk: &'w mut Walk,
+}
+
+impl<'w> Edges<'w> {
+    pub fn strong<T: Visit + 'static>(&mut self, name: &str, rc: &Rc<T>) {
+        let to = self.walk.find(rc, Rc::strong_count(rc), Rc::weak_count(rc));
+        self.walk.edge(self.from, Some(to), name, EdgeKind::Strong);
+    }
+
+    pub fn weak<T: Visit + 'static>(&mut self, name: &str, weak: &Weak<T>) {
+        let to = weak.upgrade().map(|rc| {
+            // the upgrade itself is one of the strong references, leave it out
+            let strong = Rc::strong_count(&rc) - 1;
+            self.walk.find(&rc, strong, Rc::weak_count(&rc))
+        });
+        self.walk.edge(self.from, to, name, EdgeKind::Weak);
+    }
+}
+
+// state while walking, kept apart from RcGraph so the clones never outlive the walk
+#[derive(Default)]
+struct Walk {
+    objects: Vec<Object>,
+    edges: Vec<Edge>,
+    by_address: HashMap<usize, usize>,
+    pending: Vec<(usize, Rc<dyn Visit>)>,
+}
+
+impl Walk {
+    // index of the object behind `rc`, queueing it up the first time it is seen
+    fn find<T: Visit + 'static>(&mut self, rc: &Rc<T>, strong: usize, weak: usize) -> usize {
+        let address = Rc::as_ptr(rc) as *const () as usize;
+        if let Some(&index) = self.by_address.get(&address) {
+            return index;
+        }
+        let index = self.objects.len();
+        self.objects.push(Object {
+            label: rc.label(),
+            strong,
+            weak,
+        });
+        self.by_address.insert(address, index);
+        self.pending.push((index, Rc::clone(rc) as Rc<dyn Visit>));
+        index
+    }
+
+    fn edge(&mut self, from: usize, to: Option<usize>, name: &str, kind: EdgeKind) {
+        self.edges.push(Edge {
+            from,
+            to,
+            name: name.to_string(),
+            kind,
+        });
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RcGraph {
+    pub objects: Vec<Object>,
+    pub edges: Vec<Edge>,
+    /// Names given to the starting points and the objects they are.
+    pub roots: Vec<(String, usize)>,
+    by_address: HashMap<usize, usize>,
+}
+
+impl RcGraph {
+    /// Everything reachable from one Rc.
+    pub fn walk<T: Visit + 'static>(name: &str, root: &Rc<T>) -> RcGraph {
+        let mut graph = RcGraph::default();
+        graph.walk_from(name, root);
+        graph
+    }
+
+    /// Adds whatever is reachable from another root. Objects already seen, going by address,
+    /// aren't repeated, so don't drop anything between walks.
+    pub fn walk_from<T: Visit + 'static>(&mut self, name: &str, root: &Rc<T>) {
+        let mut walk = Walk {
+            objects: std::mem::take(&mut self.objects),
+            edges: std::mem::take(&mut self.edges),
+            by_address: std::mem::take(&mut self.by_address),
+            pending: vec![],
+        };
+        let index = walk.find(root, Rc::strong_count(root), Rc::weak_count(root));
+        self.roots.push((name.to_string(), index));
+
+        while let Some((from, object)) = walk.pending.pop() {
+            object.visit(&mut Edges {
+                from,
+                walk: &mut walk,
+            });
+        }
+        self.objects = walk.objects;
+        self.edges = walk.edges;
+        self.by_address = walk.by_address;
+    }
+
+    /// Every group of objects that keep each other alive through strong pointers, including
+    /// an object pointing at itself.
+    pub fn cycles(&self) -> Vec<Cycle> {
+        let n = self.objects.len();
+        let mut adjacent = vec![vec![]; n];
+        let mut strong_in = vec![0; n];
+        for edge in &self.edges {
+            if let (EdgeKind::Strong, Some(to)) = (edge.kind, edge.to) {
+                adjacent[edge.from].push(to);
+                strong_in[to] += 1;
+            }
+        }
+
+        strongly_connected(&adjacent)
+            .into_iter()
+            .filter(|component| {
+                component.len() > 1 || adjacent[component[0]].contains(&component[0])
+            })
+            .map(|mut objects| {
+                objects.sort_unstable();
+                let external = objects
+                    .iter()
+                    .map(|&i| self.objects[i].strong.saturating_sub(strong_in[i]))
+                    .sum();
+                Cycle { objects, external }
+            })
+            .collect()
+    }
+
+    /// The graph in Graphviz DOT. Strong pointers are solid, weak ones dashed, objects in a
+    /// cycle are red and a weak pointer to something already dropped points at a grey dot.
+    pub fn to_dot(&self) -> String {
+        let in_cycle: Vec<usize> = self.cycles().into_iter().flat_map(|c| c.objects).collect();
+        let mut dot = String::from("digraph rc {\n  node [shape=box];\n");
+
+        for (i, object) in self.objects.iter().enumerate() {
+            let color = if in_cycle.contains(&i) {
+                ", color=red"
+            } else {
+                ""
+            };
+            dot.push_str(&format!(
+                "  n{} [label=\"{}\\nstrong={} weak={}\"{}];\n",
+                i,
+                escape(&object.label),
+                object.strong,
+                object.weak,
+                color
+            ));
+        }
+        for (i, (name, index)) in self.roots.iter().enumerate() {
+            dot.push_str(&format!(
+                "  root{} [label=\"{}\", shape=plaintext];\n  root{} -> n{};\n",
+                i,
+                escape(name),
+                i,
+                index
+            ));
+        }
+        for (i, edge) in self.edges.iter().enumerate() {
+            let style = match edge.kind {
+                EdgeKind::Strong => "",
+                EdgeKind::Weak => ", style=dashed",
+            };
+            let to = match edge.to {
+                Some(to) => format!("n{}", to),
+                None => {
+                    dot.push_str(&format!(
+                        "  dropped{} [label=\"\", shape=point, color=grey];\n",
+                        i
+                    ));
+                    format!("dropped{}", i)
+                }
+            };
+            dot.push_str(&format!(
+                "  n{} -> {} [label=\"{}\"{}];\n",
+                edge.from,
+                to,
+                escape(&edge.name),
+                style
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+impl fmt::Display for RcGraph {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cycles = self.cycles();
+        writeln!(
+            f,
+            "{} objects, {} strong cycles",
+            self.objects.len(),
+            cycles.len()
+        )?;
+        for (i, object) in self.objects.iter().enumerate() {
+            writeln!(
+                f,
+                "  #{} {} strong={} weak={}",
+                i, object.label, object.strong, object.weak
+            )?;
+        }
+        for cycle in &cycles {
+            let labels: Vec<&str> = cycle
+                .objects
+                .iter()
+                .map(|&i| self.objects[i].label.as_str())
+                .collect();
+            if cycle.external == 0 {
+                writeln!(f, "cycle {} has leaked", labels.join(" <-> "))?;
+            } else {
+                writeln!(
+                    f,
+                    "cycle {} leaks once {} outside references are dropped",
+                    labels.join(" <-> "),
+                    cycle.external
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// Tarjan's algorithm with an explicit stack so long chains don't overflow the real one
+fn strongly_connected(adjacent: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let n = adjacent.len();
+    let mut index: Vec<Option<usize>> = vec![None; n];
+    let mut low = vec![0; n];
+    let mut on_stack = vec![false; n];
+    let mut stack = vec![];
+    let mut next = 0;
+    let mut components = vec![];
+
+    for start in 0..n {
+        if index[start].is_some() {
+            continue;
+        }
+        // (object, how many of its edges have been looked at)
+        let mut work = vec![(start, 0)];
+        index[start] = Some(next);
+        low[start] = next;
+        next += 1;
+        stack.push(start);
+        on_stack[start] = true;
+
+        while let Some((v, i)) = work.pop() {
+            if i < adjacent[v].len() {
+                work.push((v, i + 1));
+                let w = adjacent[v][i];
+                match index[w] {
+                    None => {
+                        index[w] = Some(next);
+                        low[w] = next;
+                        next += 1;
+                        stack.push(w);
+                        on_stack[w] = true;
+                        work.push((w, 0));
+                    }
+                    Some(w_index) if on_stack[w] => low[v] = low[v].min(w_index),
+                    Some(_) => {}
+                }
+                continue;
+            }
+
+            if let Some(&(parent, _)) = work.last() {
+                low[parent] = low[parent].min(low[v]);
+            }
+            if Some(low[v]) == index[v] {
+                let mut component = vec![];
+                while let Some(w) = stack.pop() {
+                    on_stack[w] = false;
+                    component.push(w);
+                    if w == v {
+                        break;
+                    }
+                }
+                components.push(component);
+            }
+        }
+    }
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    struct Node {
+        name: &'static str,
+        parent: RefCell<Weak<Node>>,
+        children: RefCell<Vec<Rc<Node>>>,
+    }
+
+    impl Node {
+        fn new(name: &'static str) -> Rc<Node> {
+            Rc::new(Node {
+                name,
+                parent: RefCell::new(Weak::new()),
+                children: RefCell::new(vec![]),
+            })
+        }
+
+        fn adopt(parent: &Rc<Node>, child: &Rc<Node>) {
+            parent.children.borrow_mut().push(Rc::clone(child));
+            *child.parent.borrow_mut() = Rc::downgrade(parent);
+        }
+    }
+
+    impl Visit for Node {
+        fn label(&self) -> String {
+            self.name.to_string()
+        }
+
+        fn visit(&self, edges: &mut Edges) {
+            edges.weak("parent", &self.parent.borrow());
+            for (i, child) in self.children.borrow().iter().enumerate() {
+                edges.strong(&format!("child {}", i), child);
+            }
+        }
+    }
+
+    #[test]
+    fn weak_parents_make_no_cycle() {
+        let branch = Node::new("branch");
+        let leaf = Node::new("leaf");
+        Node::adopt(&branch, &leaf);
+
+        let graph = RcGraph::walk("branch", &branch);
+        assert_eq!(
+            vec![
+                Object {
+                    label: String::from("branch"),
+                    strong: 1,
+                    weak: 1
+                },
+                Object {
+                    label: String::from("leaf"),
+                    strong: 2,
+                    weak: 0
+                },
+            ],
+            graph.objects
+        );
+        assert!(graph.cycles().is_empty());
+        // the walk let go of its clones
+        assert_eq!(1, Rc::strong_count(&branch));
+        assert_eq!(2, Rc::strong_count(&leaf));
+    }
+
+    #[test]
+    fn finds_cycles_and_counts_outside_references() {
+        let a = Node::new("a");
+        let b = Node::new("b");
+        let c = Node::new("c");
+        Node::adopt(&a, &b);
+        Node::adopt(&b, &c);
+        // c holding a strongly closes the loop
+        c.children.borrow_mut().push(Rc::clone(&a));
+        let lonely = Node::new("self");
+        lonely.children.borrow_mut().push(Rc::clone(&lonely));
+        a.children.borrow_mut().push(Rc::clone(&lonely));
+
+        let graph = RcGraph::walk("a", &a);
+        let cycles = graph.cycles();
+        assert_eq!(2, cycles.len());
+        let abc = cycles.iter().find(|c| c.objects.len() == 3).unwrap();
+        // a, b and c are each still held by a local variable
+        assert_eq!(3, abc.external);
+        let own = cycles.iter().find(|c| c.objects.len() == 1).unwrap();
+        assert_eq!(1, own.external);
+
+        let report = graph.to_string();
+        assert!(report.starts_with("4 objects, 2 strong cycles\n"));
+        assert!(report.contains("cycle a <-> b <-> c leaks once 3 outside references are dropped"));
+
+        // break the cycles so the test doesn't leak
+        c.children.borrow_mut().clear();
+        lonely.children.borrow_mut().clear();
+        assert!(RcGraph::walk("a", &a).cycles().is_empty());
+    }
+
+    #[test]
+    fn renders_dot() {
+        let root = Node::new("root \"1\"");
+        let child = Node::new("child");
+        Node::adopt(&root, &child);
+        {
+            let gone = Node::new("gone");
+            *root.parent.borrow_mut() = Rc::downgrade(&gone);
+        }
+
+        let mut graph = RcGraph::walk("root", &root);
+        graph.walk_from("child", &child);
+        assert_eq!(2, graph.objects.len());
+        assert_eq!(
+            "digraph rc {\n  node [shape=box];\n\
+             \x20 n0 [label=\"root \\\"1\\\"\\nstrong=1 weak=1\"];\n\
+             \x20 n1 [label=\"child\\nstrong=2 weak=0\"];\n\
+             \x20 root0 [label=\"root\", shape=plaintext];\n  root0 -> n0;\n\
+             \x20 root1 [label=\"child\", shape=plaintext];\n  root1 -> n1;\n\
+             \x20 dropped0 [label=\"\", shape=point, color=grey];\n\
+             \x20 n0 -> dropped0 [label=\"parent\", style=dashed];\n\
+             \x20 n0 -> n1 [label=\"child 0\"];\n\
+             \x20 n1 -> n0 [label=\"parent\", style=dashed];\n\
+             }\n",
+            graph.to_dot()
+        );
+    }
+}
@@ -0,0 +1,380 @@
+// The Node from reference_cycles_can_leak as a reusable tree. Same layout as the book: a node
+// owns its children through Rc and points at its parent through Weak, so a parent going away
+// takes its children with it and nothing keeps a parent alive by accident.
+//
+// A Tree<T> is a handle to one node and, through it, the subtree below it. Handles are cheap to
+// clone. Values live in a RefCell so they can be changed through any handle.
+
+use std::cell::{Ref, RefCell, RefMut};
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::rc::{Rc, Weak};
+
+struct Node<T> {
+    value: RefCell<T>,
+    parent: RefCell<Weak<Node<T>>>,
+    children: RefCell<Vec<Rc<Node<T>>>>,
+}
+
+impl<T> Drop for Node<T> {
+    // dropping the children from here would recurse once per level, a deep enough tree blows
+    // the stack, so children we are the last owner of are emptied into a list and dropped flat
+    fn drop(&mut self) {
+        let mut pending = std::mem::take(self.children.get_mut());
+        while let Some(child) = pending.pop() {
+            if let Ok(mut child) = Rc::try_unwrap(child) {
+                pending.append(child.children.get_mut());
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeError {
+    /// The node would end up below itself.
+    WouldCycle,
+}
+
+impl fmt::Display for TreeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TreeError::WouldCycle => write!(f, "a node can't be moved under itself"),
+        }
+    }
+}
+
+impl Error for TreeError {}
+
+pub struct Tree<T> {
+    node: Rc<Node<T>>,
+}
+
+impl<T> Tree<T> {
+    /// A new root with no children.
+    pub fn new(value: T) -> Tree<T> {
+        Tree {
+            node: Rc::new(Node {
+                value: RefCell::new(value),
+                parent: RefCell::new(Weak::new()),
+                children: RefCell::new(vec![]),
+            }),
+        }
+    }
+
+    fn from_node(node: Rc<Node<T>>) -> Tree<T> {
+        Tree { node }
+    }
+
+    pub fn value(&self) -> Ref<'_, T> {
+        self.node.value.borrow()
+    }
+
+    pub fn value_mut(&self) -> RefMut<'_, T> {
+        self.node.value.borrow_mut()
+    }
+
+    /// True when both handles are the same node.
+    pub fn ptr_eq(&self, other: &Tree<T>) -> bool {
+        Rc::ptr_eq(&self.node, &other.node)
+    }
+
+    pub fn parent(&self) -> Option<Tree<T>> {
+        self.node.parent.borrow().upgrade().map(Tree::from_node)
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.parent().is_none()
+    }
+
+    pub fn children(&self) -> Vec<Tree<T>> {
+        self.node
+            .children
+            .borrow()
+            .iter()
+            .cloned()
+            .map(Tree::from_node)
+            .collect()
+    }
+
+    pub fn child_count(&self) -> usize {
+        self.node.children.borrow().len()
+    }
+
+    /// Adds a new node holding `value` as the last child and returns it.
+    pub fn add_child(&self, value: T) -> Tree<T> {
+        let child = Tree::new(value);
+        *child.node.parent.borrow_mut() = Rc::downgrade(&self.node);
+        self.node.children.borrow_mut().push(Rc::clone(&child.node));
+        child
+    }
+
+    /// Moves `child`, with everything under it, to be the last child of this node. It is
+    /// detached from wherever it was first.
+    pub fn append(&self, child: &Tree<T>) -> Result<(), TreeError> {
+        if self.path_to_root().iter().any(|node| node.ptr_eq(child)) {
+            return Err(TreeError::WouldCycle);
+        }
+        child.detach();
+        *child.node.parent.borrow_mut() = Rc::downgrade(&self.node);
+        self.node.children.borrow_mut().push(Rc::clone(&child.node));
+        Ok(())
+    }
+
+    /// Moves this node and its subtree under `new_parent`.
+    pub fn reparent(&self, new_parent: &Tree<T>) -> Result<(), TreeError> {
+        new_parent.append(self)
+    }
+
+    /// Takes the `index`th child and its subtree out of the tree, `None` if there isn't one.
+    pub fn remove_child(&self, index: usize) -> Option<Tree<T>> {
+        let mut children = self.node.children.borrow_mut();
+        if index >= children.len() {
+            return None;
+        }
+        let child = children.remove(index);
+        *child.parent.borrow_mut() = Weak::new();
+        Some(Tree::from_node(child))
+    }
+
+    /// Cuts this node and its subtree loose from its parent, making it a root. The parent
+    /// no longer keeps it alive, so hold on to this handle if you still want it.
+    pub fn detach(&self) {
+        if let Some(parent) = self.parent() {
+            parent
+                .node
+                .children
+                .borrow_mut()
+                .retain(|child| !Rc::ptr_eq(child, &self.node));
+            *self.node.parent.borrow_mut() = Weak::new();
+        }
+    }
+
+    /// This node, its parent, and so on up to the root.
+    pub fn path_to_root(&self) -> Vec<Tree<T>> {
+        let mut path = vec![self.clone()];
+        while let Some(parent) = path[path.len() - 1].parent() {
+            path.push(parent);
+        }
+        path
+    }
+
+    /// How many ancestors this node has, 0 for a root.
+    pub fn depth(&self) -> usize {
+        self.path_to_root().len() - 1
+    }
+
+    /// This node and every node below it, parents before children.
+    pub fn depth_first(&self) -> DepthFirst<T> {
+        DepthFirst {
+            stack: vec![Rc::clone(&self.node)],
+        }
+    }
+
+    /// This node and every node below it, a level at a time.
+    pub fn breadth_first(&self) -> BreadthFirst<T> {
+        BreadthFirst {
+            queue: vec![Rc::clone(&self.node)].into(),
+        }
+    }
+}
+
+impl<T> Clone for Tree<T> {
+    /// Another handle to the same node, not a copy of the subtree.
+    fn clone(&self) -> Tree<T> {
+        Tree::from_node(Rc::clone(&self.node))
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Tree<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Tree")
+            .field("value", &*self.value())
+            .field("children", &self.children())
+            .finish()
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Tree<T> {
+    /// One node per line, indented two spaces per level below this one.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // DepthFirst's stack with each node's level alongside, rather than asking every node for
+        // its depth and walking back up to the root each time
+        let mut stack = vec![(Rc::clone(&self.node), 0)];
+        while let Some((node, level)) = stack.pop() {
+            writeln!(f, "{}{}", "  ".repeat(level), node.value.borrow())?;
+            stack.extend(
+                node.children
+                    .borrow()
+                    .iter()
+                    .rev()
+                    .map(|child| (Rc::clone(child), level + 1)),
+            );
+        }
+        Ok(())
+    }
+}
+
+pub struct DepthFirst<T> {
+    stack: Vec<Rc<Node<T>>>,
+}
+
+impl<T> Iterator for DepthFirst<T> {
+    type Item = Tree<T>;
+
+    fn next(&mut self) -> Option<Tree<T>> {
+        let node = self.stack.pop()?;
+        // reversed so the first child comes off the stack first
+        self.stack
+            .extend(node.children.borrow().iter().rev().cloned());
+        Some(Tree::from_node(node))
+    }
+}
+
+pub struct BreadthFirst<T> {
+    queue: VecDeque<Rc<Node<T>>>,
+}
+
+impl<T> Iterator for BreadthFirst<T> {
+    type Item = Tree<T>;
+
+    fn next(&mut self) -> Option<Tree<T>> {
+        let node = self.queue.pop_front()?;
+        self.queue.extend(node.children.borrow().iter().cloned());
+        Some(Tree::from_node(node))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    // counts how many values have been dropped so the tests can tell nothing leaked
+    struct Counted {
+        name: &'static str,
+        drops: Rc<Cell<usize>>,
+    }
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            self.drops.set(self.drops.get() + 1);
+        }
+    }
+
+    fn names(nodes: impl Iterator<Item = Tree<&'static str>>) -> Vec<&'static str> {
+        nodes.map(|node| *node.value()).collect()
+    }
+
+    //      root
+    //     /    \
+    //    a      b
+    //   / \     |
+    //  c   d    e
+    fn sample() -> (Tree<&'static str>, Vec<Tree<&'static str>>) {
+        let root = Tree::new("root");
+        let a = root.add_child("a");
+        let b = root.add_child("b");
+        let c = a.add_child("c");
+        let d = a.add_child("d");
+        let e = b.add_child("e");
+        (root, vec![a, b, c, d, e])
+    }
+
+    #[test]
+    fn walks_depth_and_breadth_first() {
+        let (root, nodes) = sample();
+        assert_eq!(
+            vec!["root", "a", "c", "d", "b", "e"],
+            names(root.depth_first())
+        );
+        assert_eq!(
+            vec!["root", "a", "b", "c", "d", "e"],
+            names(root.breadth_first())
+        );
+        assert_eq!(vec!["a", "c", "d"], names(nodes[0].depth_first()));
+        assert_eq!(
+            vec!["d", "a", "root"],
+            names(nodes[3].path_to_root().into_iter())
+        );
+        assert_eq!(2, nodes[4].depth());
+        assert_eq!("root\n  a\n    c\n    d\n  b\n    e\n", root.to_string());
+        assert_eq!("a\n  c\n  d\n", nodes[0].to_string());
+    }
+
+    #[test]
+    fn moves_and_removes_subtrees() {
+        let (root, nodes) = sample();
+        let (a, b, e) = (&nodes[0], &nodes[1], &nodes[4]);
+
+        a.reparent(e).unwrap();
+        assert_eq!(
+            vec!["root", "b", "e", "a", "c", "d"],
+            names(root.depth_first())
+        );
+        assert!(a.parent().unwrap().ptr_eq(e));
+        assert_eq!(1, root.child_count());
+
+        // b is above a now, so it can't go under it
+        assert_eq!(Err(TreeError::WouldCycle), b.reparent(&nodes[2]));
+        assert_eq!(Err(TreeError::WouldCycle), a.append(a));
+
+        let removed = e.remove_child(0).unwrap();
+        assert!(removed.ptr_eq(a));
+        assert!(a.is_root());
+        assert!(e.remove_child(0).is_none());
+
+        b.detach();
+        assert_eq!(vec!["root"], names(root.depth_first()));
+        *b.value_mut() = "b2";
+        assert_eq!("b2\n  e\n", b.to_string());
+    }
+
+    #[test]
+    fn nothing_leaks() {
+        let drops = Rc::new(Cell::new(0));
+        let counted = |name| Counted {
+            name,
+            drops: Rc::clone(&drops),
+        };
+
+        let root = Tree::new(counted("root"));
+        let a = root.add_child(counted("a"));
+        a.add_child(counted("c"));
+        let b = root.add_child(counted("b"));
+        b.add_child(counted("d"));
+
+        // a subtree cut loose lives on as long as someone holds it
+        let detached = root.remove_child(1).unwrap();
+        drop(b);
+        drop(root);
+        // the parent link is weak, so a child doesn't keep its parent alive
+        assert_eq!(1, drops.get());
+        assert!(a.is_root());
+        drop(a);
+        assert_eq!(3, drops.get());
+
+        assert_eq!("b", detached.value().name);
+        drop(detached);
+        assert_eq!(5, drops.get());
+    }
+
+    #[test]
+    fn deep_trees_drop_without_overflowing() {
+        let drops = Rc::new(Cell::new(0));
+        let root = Tree::new(Counted {
+            name: "root",
+            drops: Rc::clone(&drops),
+        });
+        let mut last = root.clone();
+        for _ in 0..200_000 {
+            last = last.add_child(Counted {
+                name: "child",
+                drops: Rc::clone(&drops),
+            });
+        }
+        drop(last);
+        drop(root);
+        assert_eq!(200_001, drops.get());
+    }
+}
@@ -1,3 +1,8 @@
+// 17.3 follow ups that build on the Post types at the bottom of this file
+pub mod gui;
+pub mod post_store;
+pub mod state_post;
+
 pub fn characteristics_of_oop() {
     // How OOP relates to Rust 
     // Gang of four authors OOP desing patterns
@@ -21,6 +26,7 @@ pub fn characteristics_of_oop() {
 
     */
 
+    // chapter_10::stats::AveragedCollection does this for any number type without re-summing
     pub struct AveragedCollection {
         list: Vec<i32>,
         average: f64
@@ -112,22 +118,10 @@ pub fn characteristics_of_oop() {
             Creating shared behavior
     */
 
-    pub trait Draw {
-        fn draw(&self);
-    }
-
+    // Draw and Screen used to be defined right here. They now live in gui.rs as real public
+    // types where Draw renders into a character framebuffer.
     // we dont know what draw might be other than it implements draw
-    pub struct Screen {
-        pub components: Vec<Box<dyn Draw>>
-    }
-
-    impl Screen {
-        pub fn run(&self) {
-            for component in self.components.iter() {
-                component.draw();
-            }
-        }
-    }
+    use gui::{Button, Screen, SelectBox};
 
     // *************** IMPORTANT DIFFERENCE
     // USING GENERICS
@@ -150,54 +144,25 @@ pub fn characteristics_of_oop() {
     // }
 
     // Implementing the Trait
-
-    pub struct Button {
-        pub width: u32,
-        pub height: u32,
-        pub label: String, 
-    }
-
-    pub struct SelectBox {
-        pub width: u32,
-        pub height: u32,
-        pub options: Vec<String>
-    }
-
-    impl Draw for Button {
-        fn draw(&self) {
-            // code to draw type Button
-        }
-    }
-
-    impl Draw for SelectBox {
-        fn draw(&self) {
-            // code to draw type Button
-        }
-    }
+    // Button and SelectBox implement Draw over in gui/widgets.rs
 
     // What it looks like when we build it
 
 
-    let screen = Screen {
-        components: vec![
-            Box::new(Button {
-                width: 50,
-                height: 10,
-                label: String::from("OK"),
-            }),
-            Box::new(SelectBox {
-                width: 75,
-                height: 10,
-                options: vec![
-                    String::from("yes"),
-                    String::from("maybe"),
-                    String::from("no"),
-                ]
-            }),
-        ],
-    };
-
-    screen.run();
+    let screen = Screen::new(vec![
+        Box::new(Button::new(50, 10, "OK")),
+        Box::new(SelectBox::new(
+            75,
+            10,
+            vec![
+                String::from("yes"),
+                String::from("maybe"),
+                String::from("no"),
+            ],
+        )),
+    ]);
+
+    println!("{}", screen.run());
 
     /*
     Trait objects perform dynamic dispatch 
@@ -339,16 +304,20 @@ pub fn characteristics_of_oop() {
 // scattered throughout Post
 
 pub fn implementing_an_oo_design_pattern() {
-    // let mut post = Post::new();
+    // the commented out State objects above now live in state_post as DynPost where the
+    // transitions come from a Workflow table instead of each State impl
+    use state_post::{DynPost, Workflow};
 
-    // post.add_text("I ate a salad for lunch today");
-    // assert_eq!("", post.content());
+    let mut post = DynPost::new(Workflow::standard());
 
-    // post.request_review();
-    // assert_eq!("",post.content());
+    post.add_text("I ate a salad for lunch today");
+    assert_eq!("", post.content());
 
-    // post.approve();
-    // assert_eq!("I ate salad for lunch today", post.content());
+    post.request_review();
+    assert_eq!("",post.content());
+
+    post.approve();
+    assert_eq!("I ate a salad for lunch today", post.content());
     
 
     /*
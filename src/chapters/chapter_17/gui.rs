@@ -0,0 +1,589 @@
+// The Screen/Draw example from 17.2 pulled out of characteristics_of_oop so it can be used.
+//
+// Components draw into a Framebuffer of characters rather than a real window, so the output of
+// Screen::run is just a string. That makes it easy to print and easy to assert on in tests.
+//
+// Containers (Row/Column) are components too. They hand their children to the Screen through
+// children()/layout_children() so focus and mouse events can reach components at any depth.
+
+pub mod event;
+pub mod framebuffer;
+pub mod layout;
+pub mod widgets;
+
+pub use self::event::{Event, EventSource, Key, MouseKind, ScriptedEvents};
+pub use self::framebuffer::{Canvas, Framebuffer, Rect};
+pub use self::layout::{Column, Length, Row};
+pub use self::widgets::{Button, Checkbox, Label, List, ProgressBar, SelectBox, TextInput};
+
+pub trait Draw {
+    /// The (width, height) in character cells the component wants.
+    fn size(&self) -> (u32, u32);
+
+    /// Draws into `canvas`, which is already positioned and sized for this component.
+    fn draw(&self, canvas: &mut Canvas);
+
+    // the rest have defaults so purely decorative components only need the two above
+
+    /// Whether tab can move focus onto this component.
+    fn focusable(&self) -> bool {
+        false
+    }
+
+    fn set_focus(&mut self, _focused: bool) {}
+
+    /// Reacts to an event, returning true if it was used. Mouse coordinates are relative to
+    /// the component's top left corner.
+    fn handle_event(&mut self, _event: &Event) -> bool {
+        false
+    }
+
+    /// Components nested inside this one, for containers.
+    fn children(&self) -> &[Box<dyn Draw>] {
+        &[]
+    }
+
+    fn children_mut(&mut self) -> &mut [Box<dyn Draw>] {
+        &mut []
+    }
+
+    /// Where each child goes when this component is drawn at `area`, in the same coordinate
+    /// space as `area`.
+    fn layout_children(&self, _area: Rect) -> Vec<Rect> {
+        vec![]
+    }
+}
+
+// One entry per component in the tree, parents before their children. `path` is the index at
+// each level starting from Screen.components, `clip` is the part of `rect` actually visible.
+struct Node {
+    path: Vec<usize>,
+    rect: Rect,
+    clip: Rect,
+    focusable: bool,
+}
+
+fn walk(component: &dyn Draw, path: Vec<usize>, rect: Rect, clip: Rect, nodes: &mut Vec<Node>) {
+    let clip = clip.intersect(&rect);
+    let children = component.children();
+    let rects = component.layout_children(rect);
+    nodes.push(Node {
+        path: path.clone(),
+        rect,
+        clip,
+        focusable: component.focusable(),
+    });
+    for (index, (child, child_rect)) in children.iter().zip(rects).enumerate() {
+        let mut child_path = path.clone();
+        child_path.push(index);
+        walk(child.as_ref(), child_path, child_rect, clip, nodes);
+    }
+}
+
+// we dont know what draw might be other than it implements draw
+pub struct Screen {
+    pub components: Vec<Box<dyn Draw>>,
+    focus: Option<Vec<usize>>,
+}
+
+impl Screen {
+    pub fn new(components: Vec<Box<dyn Draw>>) -> Screen {
+        Screen {
+            components,
+            focus: None,
+        }
+    }
+
+    /// Where each top level component goes: stacked top to bottom in vector order at its
+    /// own size. Containers lay out their own children inside these.
+    pub fn layout(&self) -> Vec<Rect> {
+        let mut y = 0;
+        self.components
+            .iter()
+            .map(|component| {
+                let (w, h) = component.size();
+                let rect = Rect::new(0, y, w, h);
+                y += h;
+                rect
+            })
+            .collect()
+    }
+
+    /// Draws every component into a framebuffer just big enough to hold them all.
+    pub fn render(&self) -> Framebuffer {
+        let rects = self.layout();
+        let (width, height) = Self::extent(&rects);
+
+        let mut fb = Framebuffer::new(width, height);
+        let mut canvas = fb.canvas();
+        for (component, rect) in self.components.iter().zip(rects) {
+            component.draw(&mut canvas.sub(rect));
+        }
+        fb
+    }
+
+    /// Renders the screen and returns the snapshot text.
+    pub fn run(&self) -> String {
+        self.render().snapshot()
+    }
+
+    /// Feeds every event from `source` through `dispatch` then returns the final snapshot.
+    pub fn run_events<S: EventSource>(&mut self, source: &mut S) -> String {
+        while let Some(event) = source.next_event() {
+            self.dispatch(event);
+        }
+        self.run()
+    }
+
+    /// Index of the top level component holding focus.
+    pub fn focused(&self) -> Option<usize> {
+        self.focus.as_ref().map(|path| path[0])
+    }
+
+    /// Full path to the focused component, one index per level of nesting.
+    pub fn focused_path(&self) -> Option<&[usize]> {
+        self.focus.as_deref()
+    }
+
+    /// Sends one event where it belongs. Tab and shift-tab move focus, escape clears it,
+    /// other keys go to the focused component and mouse events go to whatever is under the
+    /// pointer (focusing it on click). Events a component ignores bubble up to its
+    /// containers. Returns true if anything used the event.
+    pub fn dispatch(&mut self, event: Event) -> bool {
+        match event {
+            Event::Key(Key::Tab) => self.move_focus(true),
+            Event::Key(Key::BackTab) => self.move_focus(false),
+            Event::Key(Key::Escape) => {
+                let had_focus = self.focus.is_some();
+                self.set_focus(None);
+                had_focus
+            }
+            Event::Key(_) => match self.focus.clone() {
+                Some(path) => self.bubble(&path, |_| event),
+                None => false,
+            },
+            Event::Mouse { kind, x, y } => {
+                let nodes = self.nodes();
+                // deepest visible component under the pointer, children come after parents
+                let hit = match nodes.iter().rev().find(|n| n.clip.contains(x, y)) {
+                    Some(node) => node,
+                    None => return false,
+                };
+                if kind == MouseKind::Click && hit.focusable {
+                    self.set_focus(Some(hit.path.clone()));
+                }
+                let path = hit.path.clone();
+                self.bubble(&path, |depth| {
+                    let rect = nodes
+                        .iter()
+                        .find(|n| n.path[..] == path[..depth])
+                        .map(|n| n.rect)
+                        .unwrap_or_default();
+                    Event::Mouse {
+                        kind,
+                        x: x - rect.x,
+                        y: y - rect.y,
+                    }
+                })
+            }
+        }
+    }
+
+    // offers the event to the component at `path` then each of its containers in turn,
+    // `event_at` builds the event for a given depth so mouse coordinates can be made local
+    fn bubble<F: Fn(usize) -> Event>(&mut self, path: &[usize], event_at: F) -> bool {
+        (1..=path.len())
+            .rev()
+            .any(|depth| self.component_mut(&path[..depth]).handle_event(&event_at(depth)))
+    }
+
+    fn nodes(&self) -> Vec<Node> {
+        let rects = self.layout();
+        let (width, height) = Self::extent(&rects);
+        let screen = Rect::new(0, 0, width, height);
+
+        let mut nodes = vec![];
+        for (index, (component, rect)) in self.components.iter().zip(rects).enumerate() {
+            walk(component.as_ref(), vec![index], rect, screen, &mut nodes);
+        }
+        nodes
+    }
+
+    fn component_mut(&mut self, path: &[usize]) -> &mut dyn Draw {
+        let mut component = self.components[path[0]].as_mut();
+        for &index in &path[1..] {
+            component = component.children_mut()[index].as_mut();
+        }
+        component
+    }
+
+    fn extent(rects: &[Rect]) -> (u32, u32) {
+        let width = rects.iter().map(|r| r.right()).max().unwrap_or(0);
+        let height = rects.iter().map(|r| r.bottom()).max().unwrap_or(0);
+        (width, height)
+    }
+
+    // walks forward or back from the current focus to the next focusable component, wrapping
+    fn move_focus(&mut self, forward: bool) -> bool {
+        let focusable: Vec<Vec<usize>> = self
+            .nodes()
+            .into_iter()
+            .filter(|n| n.focusable)
+            .map(|n| n.path)
+            .collect();
+        if focusable.is_empty() {
+            return false;
+        }
+
+        let len = focusable.len();
+        let current = self
+            .focus
+            .as_ref()
+            .and_then(|path| focusable.iter().position(|p| p == path));
+        let next = match (current, forward) {
+            (Some(i), true) => (i + 1) % len,
+            (Some(i), false) => (i + len - 1) % len,
+            (None, true) => 0,
+            (None, false) => len - 1,
+        };
+        self.set_focus(Some(focusable[next].clone()));
+        true
+    }
+
+    fn set_focus(&mut self, focus: Option<Vec<usize>>) {
+        if let Some(old) = self.focus.take() {
+            self.component_mut(&old).set_focus(false);
+        }
+        if let Some(new) = &focus {
+            self.component_mut(new).set_focus(true);
+        }
+        self.focus = focus;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn options(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    // only implements the required methods so it can never take focus
+    struct Banner(&'static str);
+
+    impl Draw for Banner {
+        fn size(&self) -> (u32, u32) {
+            (self.0.len() as u32, 1)
+        }
+
+        fn draw(&self, canvas: &mut Canvas) {
+            canvas.text(0, 0, self.0);
+        }
+    }
+
+    #[test]
+    fn renders_button_and_select_box() {
+        let screen = Screen::new(vec![
+            Box::new(Button::new(10, 3, "OK")),
+            Box::new(SelectBox::new(12, 5, options(&["yes", "maybe", "no"]))),
+        ]);
+
+        let expected = "\
++--------+
+|   OK   |
++--------+
++----------+
+|> yes     |
+|  maybe   |
+|  no      |
++----------+
+";
+        assert_eq!(expected, screen.run());
+    }
+
+    #[test]
+    fn clips_content_to_the_component() {
+        let screen = Screen::new(vec![
+            Box::new(SelectBox::new(
+                7,
+                4,
+                options(&["much too long", "b", "hidden"]),
+            )),
+            Box::new(Button::new(7, 1, "Go")),
+        ]);
+
+        let expected = "\
++-----+
+|> muc|
+|  b  |
++-----+
+ [Go]
+";
+        assert_eq!(expected, screen.run());
+    }
+
+    #[test]
+    fn canvas_clips_to_parent() {
+        let mut fb = Framebuffer::new(4, 2);
+        let mut canvas = fb.canvas();
+        // starts inside but runs well past the right and bottom edges
+        let mut sub = canvas.sub(Rect::new(2, 1, 10, 10));
+        sub.fill('#');
+
+        assert_eq!("\n  ##\n", fb.snapshot());
+        assert_eq!(Some('#'), fb.get(3, 1));
+        assert_eq!(None, fb.get(4, 1));
+    }
+
+    #[test]
+    fn huge_sizes_clip_instead_of_overflowing() {
+        let far = Rect::new(u32::MAX - 1, 3, 10, u32::MAX);
+        assert_eq!(u32::MAX, far.right());
+        assert_eq!(u32::MAX, far.bottom());
+        assert!(far.contains(u32::MAX - 1, u32::MAX - 1));
+
+        let mut fb = Framebuffer::new(u32::MAX, u32::MAX);
+        assert_eq!(framebuffer::MAX_CELLS, fb.width());
+        assert_eq!(1, fb.height());
+        let tall = Framebuffer::new(4096, u32::MAX);
+        assert_eq!(4096, tall.height());
+
+        let mut canvas = fb.canvas();
+        let mut sub = canvas.sub(Rect::new(u32::MAX - 1, 0, 5, 5));
+        sub.text(u32::MAX, 0, "no room");
+        sub.fill('#');
+        assert_eq!(None, fb.get(u32::MAX - 1, 0));
+        assert_eq!(Some(' '), fb.get(0, 0));
+    }
+
+    #[test]
+    fn empty_screen_renders_nothing() {
+        let screen = Screen::new(vec![]);
+        assert_eq!("", screen.run());
+    }
+
+    #[test]
+    fn tab_moves_focus_and_skips_unfocusable() {
+        let mut screen = Screen::new(vec![
+            Box::new(Banner("title")),
+            Box::new(Button::new(6, 1, "A")),
+            Box::new(Banner("--")),
+            Box::new(Button::new(6, 1, "B")),
+        ]);
+
+        assert_eq!(None, screen.focused());
+        screen.dispatch(Event::Key(Key::Tab));
+        assert_eq!(Some(1), screen.focused());
+        screen.dispatch(Event::Key(Key::Tab));
+        assert_eq!(Some(3), screen.focused());
+        screen.dispatch(Event::Key(Key::Tab));
+        assert_eq!(Some(1), screen.focused());
+        screen.dispatch(Event::Key(Key::BackTab));
+        assert_eq!(Some(3), screen.focused());
+
+        assert_eq!("title\n [A]\n--\n >B<\n", screen.run());
+
+        assert!(screen.dispatch(Event::Key(Key::Escape)));
+        assert_eq!(None, screen.focused());
+        assert!(!Screen::new(vec![Box::new(Banner("x"))]).dispatch(Event::Key(Key::Tab)));
+    }
+
+    #[test]
+    fn scripted_flow_fires_callbacks() {
+        let clicks = Rc::new(RefCell::new(0));
+        let changes = Rc::new(RefCell::new(vec![]));
+
+        let clicks_in_button = Rc::clone(&clicks);
+        let changes_in_select = Rc::clone(&changes);
+        let mut screen = Screen::new(vec![
+            Box::new(Button::new(8, 3, "OK").on_click(move || {
+                *clicks_in_button.borrow_mut() += 1;
+            })),
+            Box::new(
+                SelectBox::new(9, 5, options(&["yes", "maybe", "no"])).on_change(
+                    move |index, option| {
+                        changes_in_select
+                            .borrow_mut()
+                            .push(format!("{}:{}", index, option));
+                    },
+                ),
+            ),
+        ]);
+
+        let mut script = ScriptedEvents::parse(
+            "tab enter space   # keyboard clicks
+             tab down down down up
+             click:3,1        # mouse click on the button focuses it
+             click:2,6        # third row of the select box
+             scrollup:2,5",
+        )
+        .unwrap();
+        let snapshot = screen.run_events(&mut script);
+
+        assert_eq!(0, script.remaining());
+        assert_eq!(3, *clicks.borrow());
+        assert_eq!(
+            vec!["1:maybe", "2:no", "1:maybe", "2:no", "1:maybe"],
+            *changes.borrow()
+        );
+        assert_eq!(Some(1), screen.focused());
+
+        let expected = "\
++------+
+|  OK  |
++------+
++=======+
+|  yes  |
+|> maybe|
+|  no   |
++=======+
+";
+        assert_eq!(expected, snapshot);
+    }
+
+    #[test]
+    fn select_box_scrolls_to_keep_selection_visible() {
+        let mut screen = Screen::new(vec![Box::new(SelectBox::new(
+            7,
+            2,
+            options(&["a", "b", "c", "d"]),
+        ))]);
+        let mut script = ScriptedEvents::new(vec![
+            Event::Key(Key::Tab),
+            Event::Key(Key::Down),
+            Event::Key(Key::Down),
+        ]);
+
+        assert_eq!("  b\n> c\n", screen.run_events(&mut script));
+
+        // clicking the top row picks whatever is scrolled into it
+        screen.dispatch(Event::click(0, 0));
+        assert_eq!("> b\n  c\n", screen.run());
+    }
+
+    #[test]
+    fn bad_scripts_are_rejected() {
+        assert_eq!(
+            "nope",
+            ScriptedEvents::parse("tab nope").unwrap_err().token
+        );
+        assert!(ScriptedEvents::parse("click:1").is_err());
+        assert!(ScriptedEvents::parse("click:1,2,3").is_err());
+        assert_eq!(3, ScriptedEvents::parse("type:abc").unwrap().remaining());
+    }
+
+    #[test]
+    fn composes_a_form_from_rows_and_columns() {
+        let progress = ProgressBar::new(20).with_progress(0.5);
+        let form = Column::new()
+            .with_size(22, 8)
+            .push(Label::new("Sign up"))
+            .push(
+                Row::new()
+                    .gap(1)
+                    .push_fixed(5, Label::new("Name"))
+                    .push_flex(1, TextInput::new(0).placeholder("your name")),
+            )
+            .push(Checkbox::new("Send me news", true))
+            .push(progress)
+            .push_flex(1, List::new(0, 0, options(&["one", "two", "three", "four"])))
+            .push(Row::new().push_flex(1, Label::new("")).push(Button::new(6, 1, "Save")));
+        let screen = Screen::new(vec![Box::new(form)]);
+
+        let expected = "\
+Sign up
+Name  [your name     ]
+ [x] Send me news
+[########-------]  50%
+  one
+  two
+  three
+                [Save]
+";
+        assert_eq!(expected, screen.run());
+    }
+
+    #[test]
+    fn focus_and_clicks_reach_nested_components() {
+        let typed = Rc::new(RefCell::new(String::new()));
+        let picked = Rc::new(RefCell::new(vec![]));
+        let toggles = Rc::new(RefCell::new(vec![]));
+
+        let typed_in = Rc::clone(&typed);
+        let picked_in = Rc::clone(&picked);
+        let toggles_in = Rc::clone(&toggles);
+        let progress = ProgressBar::new(17);
+        let bump = progress.handle();
+        let form = Column::new()
+            .push(
+                Row::new()
+                    .gap(1)
+                    .push(Label::new("Name"))
+                    .push(TextInput::new(10).on_submit(move |value| {
+                        *typed_in.borrow_mut() = value.to_string();
+                    })),
+            )
+            .push(Checkbox::new("news", false).on_toggle(move |on| toggles_in.borrow_mut().push(on)))
+            .push(List::new(8, 2, options(&["a", "b", "c"])).on_select(
+                move |index, item| picked_in.borrow_mut().push(format!("{}:{}", index, item)),
+            ))
+            .push(progress)
+            .push(Button::new(8, 1, "Go").on_click(move || bump.set(bump.get() + 0.25)));
+        let mut screen = Screen::new(vec![Box::new(form)]);
+
+        let mut script = ScriptedEvents::parse(
+            "tab type:héllo left backspace enter   # fix the typo
+             tab space
+             tab down down enter
+             click:2,0 click:6,0           # the label ignores it, the input takes focus
+             click:5,2                     # list is scrolled so its top row is b
+             click:1,5 backtab",
+        )
+        .unwrap();
+        let snapshot = screen.run_events(&mut script);
+
+        assert_eq!("hélo", *typed.borrow());
+        assert_eq!(vec![true], *toggles.borrow());
+        assert_eq!(vec!["2:c", "1:b"], *picked.borrow());
+        assert_eq!(Some(&[0, 2][..]), screen.focused_path());
+
+        let expected = "\
+Name [hélo    ]
+ [x] news
+> b
+  c
+[###-------]  25%
+      [Go]
+";
+        assert_eq!(expected, snapshot);
+    }
+
+    #[test]
+    fn row_clips_children_that_do_not_fit() {
+        let row = Row::new()
+            .with_size(6, 1)
+            .push(Label::new("abcd"))
+            .push(Label::new("efgh"));
+        let screen = Screen::new(vec![Box::new(row), Box::new(Label::new("next"))]);
+
+        assert_eq!("abcdef\nnext\n", screen.run());
+    }
+
+    #[test]
+    fn text_input_scrolls_and_handles_multibyte() {
+        let mut input = TextInput::new(6).with_value("日本語です");
+        input.set_focus(true);
+        let mut fb = Framebuffer::new(6, 1);
+        input.draw(&mut fb.canvas());
+        // 4 cells inside the brackets, the cursor at the end pushes the start off the left
+        assert_eq!("[語です|]\n", fb.snapshot());
+
+        input.handle_event(&Event::Key(Key::Backspace));
+        input.handle_event(&Event::click(1, 0));
+        input.handle_event(&Event::Key(Key::Char('¡')));
+        assert_eq!("¡日本語で", input.value());
+    }
+}
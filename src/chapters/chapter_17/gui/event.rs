@@ -0,0 +1,147 @@
+// Input for the Screen. Real terminals would feed these from stdin, tests feed them from a
+// ScriptedEvents so a whole UI flow can run headless and be checked with a snapshot.
+
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Enter,
+    Space,
+    Tab,
+    BackTab,
+    Up,
+    Down,
+    Left,
+    Right,
+    Backspace,
+    Escape,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseKind {
+    Click,
+    ScrollUp,
+    ScrollDown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Key(Key),
+    // x and y are screen coordinates when they reach the Screen and are translated to the
+    // component's own coordinates before the component sees them
+    Mouse { kind: MouseKind, x: u32, y: u32 },
+}
+
+impl Event {
+    pub fn click(x: u32, y: u32) -> Event {
+        Event::Mouse {
+            kind: MouseKind::Click,
+            x,
+            y,
+        }
+    }
+}
+
+/// Anything that can hand the Screen events one at a time. `None` means there are no more.
+pub trait EventSource {
+    fn next_event(&mut self) -> Option<Event>;
+}
+
+/// A canned list of events, either built directly or parsed from a small script.
+#[derive(Debug, Clone, Default)]
+pub struct ScriptedEvents {
+    events: VecDeque<Event>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptError {
+    pub token: String,
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "can't understand event {:?}", self.token)
+    }
+}
+
+impl Error for ScriptError {}
+
+impl ScriptedEvents {
+    pub fn new(events: Vec<Event>) -> ScriptedEvents {
+        ScriptedEvents {
+            events: events.into(),
+        }
+    }
+
+    /// Parses whitespace separated events:
+    ///
+    /// `tab backtab enter space up down left right backspace esc` are keys,
+    /// `type:abc` types each character, `click:x,y` `scrollup:x,y` `scrolldown:x,y` are mouse
+    /// events and anything after `#` on a line is a comment.
+    pub fn parse(script: &str) -> Result<ScriptedEvents, ScriptError> {
+        let mut events = vec![];
+
+        for line in script.lines() {
+            let line = line.split('#').next().unwrap_or("");
+            for token in line.split_whitespace() {
+                let error = || ScriptError {
+                    token: token.to_string(),
+                };
+                let key = match token {
+                    "tab" => Some(Key::Tab),
+                    "backtab" => Some(Key::BackTab),
+                    "enter" => Some(Key::Enter),
+                    "space" => Some(Key::Space),
+                    "up" => Some(Key::Up),
+                    "down" => Some(Key::Down),
+                    "left" => Some(Key::Left),
+                    "right" => Some(Key::Right),
+                    "backspace" => Some(Key::Backspace),
+                    "esc" => Some(Key::Escape),
+                    _ => None,
+                };
+                if let Some(key) = key {
+                    events.push(Event::Key(key));
+                    continue;
+                }
+
+                let (name, arg) = match token.find(':') {
+                    Some(i) => (&token[..i], &token[i + 1..]),
+                    None => return Err(error()),
+                };
+                match name {
+                    "type" => events.extend(arg.chars().map(|c| Event::Key(Key::Char(c)))),
+                    "click" | "scrollup" | "scrolldown" => {
+                        let mut coords = arg.split(',').map(|n| n.parse::<u32>());
+                        let (x, y) = match (coords.next(), coords.next(), coords.next()) {
+                            (Some(Ok(x)), Some(Ok(y)), None) => (x, y),
+                            _ => return Err(error()),
+                        };
+                        let kind = match name {
+                            "click" => MouseKind::Click,
+                            "scrollup" => MouseKind::ScrollUp,
+                            _ => MouseKind::ScrollDown,
+                        };
+                        events.push(Event::Mouse { kind, x, y });
+                    }
+                    _ => return Err(error()),
+                }
+            }
+        }
+
+        Ok(ScriptedEvents::new(events))
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.events.len()
+    }
+}
+
+impl EventSource for ScriptedEvents {
+    fn next_event(&mut self) -> Option<Event> {
+        self.events.pop_front()
+    }
+}
@@ -0,0 +1,224 @@
+// A grid of characters standing in for the screen. Components never touch it directly, they
+// get a Canvas which is a window into part of it. Anything drawn outside the window is dropped
+// so a component can't scribble over its neighbours.
+
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// One past the last column, stopping at u32::MAX rather than overflowing.
+    pub fn right(&self) -> u32 {
+        self.x.saturating_add(self.width)
+    }
+
+    pub fn bottom(&self) -> u32 {
+        self.y.saturating_add(self.height)
+    }
+
+    pub fn contains(&self, x: u32, y: u32) -> bool {
+        x >= self.x && x < self.right() && y >= self.y && y < self.bottom()
+    }
+
+    /// The overlapping part of two rects, empty (zero sized) if they don't overlap.
+    pub fn intersect(&self, other: &Rect) -> Rect {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        Rect::new(x, y, right.saturating_sub(x), bottom.saturating_sub(y))
+    }
+}
+
+/// The most cells a framebuffer will hold, 16 million, a little over 4096 x 4096.
+pub const MAX_CELLS: u32 = 1 << 24;
+
+pub struct Framebuffer {
+    width: u32,
+    height: u32,
+    cells: Vec<char>,
+}
+
+impl Framebuffer {
+    /// A blank buffer. Sizes past `MAX_CELLS` are clipped, the width first and then the height,
+    /// and anything drawn beyond that is dropped like any other out of bounds drawing.
+    pub fn new(width: u32, height: u32) -> Framebuffer {
+        let width = width.min(MAX_CELLS);
+        let height = height.min(MAX_CELLS / width.max(1));
+        let cells = width.checked_mul(height).expect("clipped to MAX_CELLS");
+        Framebuffer {
+            width,
+            height,
+            cells: vec![' '; cells as usize],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> Option<char> {
+        if x < self.width && y < self.height {
+            Some(self.cells[(y * self.width + x) as usize])
+        } else {
+            None
+        }
+    }
+
+    /// A canvas covering the whole buffer.
+    pub fn canvas(&mut self) -> Canvas<'_> {
+        let area = Rect::new(0, 0, self.width, self.height);
+        Canvas {
+            fb: self,
+            area,
+            clip: area,
+        }
+    }
+
+    /// The buffer as text, one line per row. Trailing spaces are trimmed so snapshots in
+    /// tests don't depend on invisible whitespace.
+    pub fn snapshot(&self) -> String {
+        let mut out = String::new();
+        for row in self.cells.chunks(self.width.max(1) as usize) {
+            let line: String = row.iter().collect();
+            out.push_str(line.trim_end());
+            out.push('\n');
+        }
+        out
+    }
+
+    fn set(&mut self, x: u32, y: u32, ch: char) {
+        if x < self.width && y < self.height {
+            self.cells[(y * self.width + x) as usize] = ch;
+        }
+    }
+}
+
+impl fmt::Display for Framebuffer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.snapshot())
+    }
+}
+
+/// A clipped window into a framebuffer. Coordinates are relative to the window's top left.
+pub struct Canvas<'a> {
+    fb: &'a mut Framebuffer,
+    area: Rect,
+    clip: Rect,
+}
+
+impl<'a> Canvas<'a> {
+    pub fn width(&self) -> u32 {
+        self.area.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.area.height
+    }
+
+    /// Where this canvas sits on the framebuffer.
+    pub fn area(&self) -> Rect {
+        self.area
+    }
+
+    pub fn put(&mut self, x: u32, y: u32, ch: char) {
+        let (abs_x, abs_y) = (self.area.x.saturating_add(x), self.area.y.saturating_add(y));
+        if x < self.area.width && y < self.area.height && self.clip.contains(abs_x, abs_y) {
+            self.fb.set(abs_x, abs_y, ch);
+        }
+    }
+
+    /// Writes `text` starting at (x, y). Whatever doesn't fit is clipped.
+    pub fn text(&mut self, x: u32, y: u32, text: &str) {
+        for (i, ch) in text.chars().enumerate() {
+            self.put(x.saturating_add(i as u32), y, ch);
+        }
+    }
+
+    /// Writes `text` centered on row `y`.
+    pub fn text_centered(&mut self, y: u32, text: &str) {
+        let len = text.chars().count() as u32;
+        self.text(self.area.width.saturating_sub(len) / 2, y, text);
+    }
+
+    pub fn fill(&mut self, ch: char) {
+        for y in 0..self.area.height {
+            for x in 0..self.area.width {
+                self.put(x, y, ch);
+            }
+        }
+    }
+
+    /// Draws a `+--+` style border around the edge of the canvas.
+    pub fn border(&mut self) {
+        self.frame('-');
+    }
+
+    /// Same as `border` but with `+==+` top and bottom so the focused component stands out.
+    pub fn focused_border(&mut self) {
+        self.frame('=');
+    }
+
+    fn frame(&mut self, horizontal: char) {
+        let (w, h) = (self.area.width, self.area.height);
+        if w == 0 || h == 0 {
+            return;
+        }
+        for x in 1..w.saturating_sub(1) {
+            self.put(x, 0, horizontal);
+            self.put(x, h - 1, horizontal);
+        }
+        for y in 1..h.saturating_sub(1) {
+            self.put(0, y, '|');
+            self.put(w - 1, y, '|');
+        }
+        for &(x, y) in &[(0, 0), (w - 1, 0), (0, h - 1), (w - 1, h - 1)] {
+            self.put(x, y, '+');
+        }
+    }
+
+    /// A canvas for part of this one. It is clipped to this canvas as well as its own area.
+    pub fn sub(&mut self, area: Rect) -> Canvas<'_> {
+        let abs = Rect::new(
+            self.area.x.saturating_add(area.x),
+            self.area.y.saturating_add(area.y),
+            area.width,
+            area.height,
+        );
+        Canvas {
+            clip: self.clip.intersect(&abs),
+            area: abs,
+            fb: self.fb,
+        }
+    }
+
+    /// The canvas inside a one cell border.
+    pub fn inner(&mut self) -> Canvas<'_> {
+        let area = Rect::new(
+            1,
+            1,
+            self.area.width.saturating_sub(2),
+            self.area.height.saturating_sub(2),
+        );
+        self.sub(area)
+    }
+}
@@ -0,0 +1,304 @@
+// Row and Column containers. Each child slot has a Length saying how much of the main axis it
+// gets; on the cross axis every child is stretched to fill the container. Children that don't
+// fit are clipped by the container's canvas rather than drawn over whatever comes next.
+
+use super::framebuffer::{Canvas, Rect};
+use super::Draw;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Length {
+    /// Whatever the child's own `size()` asks for.
+    Auto,
+    Fixed(u32),
+    /// A share of the space left over after Auto and Fixed children, by weight.
+    Flex(u32),
+}
+
+/// Splits `available` cells between slots. `lengths` pairs each slot's Length with the child's
+/// preferred size along the axis. A gap of `gap` cells goes between neighbouring slots.
+pub fn distribute(available: u32, gap: u32, lengths: &[(Length, u32)]) -> Vec<u32> {
+    // totals are added up in u64 so big sizes, gaps or weights can't overflow on the way
+    let gaps = u64::from(gap) * (lengths.len() as u64).saturating_sub(1);
+    let mut sizes: Vec<u32> = lengths
+        .iter()
+        .map(|&(length, preferred)| match length {
+            Length::Auto => preferred,
+            Length::Fixed(n) => n,
+            Length::Flex(_) => 0,
+        })
+        .collect();
+
+    let used: u64 = sizes.iter().map(|&size| u64::from(size)).sum::<u64>() + gaps;
+    let mut left = u64::from(available).saturating_sub(used) as u32;
+    let total_weight: u64 = lengths
+        .iter()
+        .map(|&(length, _)| match length {
+            Length::Flex(weight) => u64::from(weight),
+            _ => 0,
+        })
+        .sum();
+    if total_weight == 0 {
+        return sizes;
+    }
+
+    let spare = left;
+    for (size, &(length, _)) in sizes.iter_mut().zip(lengths) {
+        if let Length::Flex(weight) = length {
+            // never more than spare, so it fits back in a u32
+            let share = (u64::from(spare) * u64::from(weight) / total_weight) as u32;
+            *size = share;
+            left -= share;
+        }
+    }
+    // rounding leftovers go one cell at a time to the flex slots from the front
+    for (size, &(length, _)) in sizes.iter_mut().zip(lengths) {
+        if left == 0 {
+            break;
+        }
+        if let Length::Flex(weight) = length {
+            if weight > 0 {
+                *size += 1;
+                left -= 1;
+            }
+        }
+    }
+    sizes
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Axis {
+    Horizontal,
+    Vertical,
+}
+
+// everything Row and Column have in common, they only differ by axis
+struct Stack {
+    axis: Axis,
+    children: Vec<Box<dyn Draw>>,
+    lengths: Vec<Length>,
+    gap: u32,
+    size: Option<(u32, u32)>,
+}
+
+impl Stack {
+    fn new(axis: Axis) -> Stack {
+        Stack {
+            axis,
+            children: vec![],
+            lengths: vec![],
+            gap: 0,
+            size: None,
+        }
+    }
+
+    // (main, cross) for a (width, height) pair
+    fn split(&self, (w, h): (u32, u32)) -> (u32, u32) {
+        match self.axis {
+            Axis::Horizontal => (w, h),
+            Axis::Vertical => (h, w),
+        }
+    }
+
+    fn size(&self) -> (u32, u32) {
+        if let Some(size) = self.size {
+            return size;
+        }
+        let mut main = self.gap * (self.children.len() as u32).saturating_sub(1);
+        let mut cross = 0;
+        for (child, length) in self.children.iter().zip(&self.lengths) {
+            let (child_main, child_cross) = self.split(child.size());
+            main += match length {
+                Length::Fixed(n) => *n,
+                _ => child_main,
+            };
+            cross = cross.max(child_cross);
+        }
+        match self.axis {
+            Axis::Horizontal => (main, cross),
+            Axis::Vertical => (cross, main),
+        }
+    }
+
+    fn layout_children(&self, area: Rect) -> Vec<Rect> {
+        let (main, cross) = self.split((area.width, area.height));
+        let lengths: Vec<(Length, u32)> = self
+            .children
+            .iter()
+            .zip(&self.lengths)
+            .map(|(child, &length)| (length, self.split(child.size()).0))
+            .collect();
+
+        let mut offset = 0;
+        distribute(main, self.gap, &lengths)
+            .into_iter()
+            .map(|len| {
+                let rect = match self.axis {
+                    Axis::Horizontal => Rect::new(area.x + offset, area.y, len, cross),
+                    Axis::Vertical => Rect::new(area.x, area.y + offset, cross, len),
+                };
+                offset += len + self.gap;
+                rect
+            })
+            .collect()
+    }
+
+    fn draw(&self, canvas: &mut Canvas) {
+        let area = Rect::new(0, 0, canvas.width(), canvas.height());
+        for (child, rect) in self.children.iter().zip(self.layout_children(area)) {
+            child.draw(&mut canvas.sub(rect));
+        }
+    }
+}
+
+// Row and Column get the same builder methods and Draw impl from here
+macro_rules! stack_container {
+    ($name:ident, $axis:expr) => {
+        impl $name {
+            pub fn new() -> $name {
+                $name {
+                    stack: Stack::new($axis),
+                }
+            }
+
+            /// Adds a child at its own preferred size.
+            pub fn push<C: Draw + 'static>(self, child: C) -> $name {
+                self.push_boxed(Length::Auto, Box::new(child))
+            }
+
+            pub fn push_fixed<C: Draw + 'static>(self, length: u32, child: C) -> $name {
+                self.push_boxed(Length::Fixed(length), Box::new(child))
+            }
+
+            pub fn push_flex<C: Draw + 'static>(self, weight: u32, child: C) -> $name {
+                self.push_boxed(Length::Flex(weight), Box::new(child))
+            }
+
+            pub fn push_boxed(mut self, length: Length, child: Box<dyn Draw>) -> $name {
+                self.stack.children.push(child);
+                self.stack.lengths.push(length);
+                self
+            }
+
+            /// Empty cells between neighbouring children.
+            pub fn gap(mut self, gap: u32) -> $name {
+                self.stack.gap = gap;
+                self
+            }
+
+            /// Fixes the container's own size instead of sizing it to fit its children.
+            pub fn with_size(mut self, width: u32, height: u32) -> $name {
+                self.stack.size = Some((width, height));
+                self
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> $name {
+                $name::new()
+            }
+        }
+
+        impl Draw for $name {
+            fn size(&self) -> (u32, u32) {
+                self.stack.size()
+            }
+
+            fn draw(&self, canvas: &mut Canvas) {
+                self.stack.draw(canvas)
+            }
+
+            fn children(&self) -> &[Box<dyn Draw>] {
+                &self.stack.children
+            }
+
+            fn children_mut(&mut self) -> &mut [Box<dyn Draw>] {
+                &mut self.stack.children
+            }
+
+            fn layout_children(&self, area: Rect) -> Vec<Rect> {
+                self.stack.layout_children(area)
+            }
+        }
+    };
+}
+
+/// Lays children out left to right.
+pub struct Row {
+    stack: Stack,
+}
+
+/// Lays children out top to bottom.
+pub struct Column {
+    stack: Stack,
+}
+
+stack_container!(Row, Axis::Horizontal);
+stack_container!(Column, Axis::Vertical);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chapters::chapter_17::gui::Label;
+
+    #[test]
+    fn flex_shares_leftover_space_by_weight() {
+        let lengths = [
+            (Length::Fixed(4), 0),
+            (Length::Flex(1), 0),
+            (Length::Auto, 3),
+            (Length::Flex(2), 0),
+        ];
+        // 20 - 4 - 3 - 3 gaps = 10 left, split 1:2 with the odd cell going to the first
+        assert_eq!(vec![4, 4, 3, 6], distribute(20, 1, &lengths));
+    }
+
+    #[test]
+    fn huge_space_and_weights_dont_overflow() {
+        let lengths = [(Length::Flex(u32::MAX), 0), (Length::Flex(u32::MAX), 0)];
+        assert_eq!(
+            vec![u32::MAX / 2 + 1, u32::MAX / 2],
+            distribute(u32::MAX, 0, &lengths)
+        );
+
+        let lengths = [
+            (Length::Fixed(u32::MAX), 0),
+            (Length::Auto, u32::MAX),
+            (Length::Flex(1), 0),
+        ];
+        assert_eq!(
+            vec![u32::MAX, u32::MAX, 0],
+            distribute(u32::MAX, u32::MAX, &lengths)
+        );
+    }
+
+    #[test]
+    fn nothing_left_for_flex_when_fixed_overflows() {
+        let lengths = [(Length::Fixed(8), 0), (Length::Flex(1), 0)];
+        assert_eq!(vec![8, 0], distribute(5, 0, &lengths));
+        assert_eq!(Vec::<u32>::new(), distribute(5, 2, &[]));
+    }
+
+    #[test]
+    fn containers_size_to_fit_children() {
+        let row = Row::new().gap(1).push(Label::new("ab")).push(Label::new("cde\nf"));
+        assert_eq!((6, 2), row.size());
+
+        let column = Column::new()
+            .push(Label::new("ab"))
+            .push_fixed(3, Label::new("cde"));
+        assert_eq!((3, 4), column.size());
+        assert_eq!((9, 9), column.with_size(9, 9).size());
+    }
+
+    #[test]
+    fn children_are_stretched_across_and_offset_along() {
+        let row = Row::new()
+            .gap(2)
+            .push_fixed(3, Label::new("a"))
+            .push_flex(1, Label::new("b"));
+        assert_eq!(
+            vec![Rect::new(10, 5, 3, 4), Rect::new(15, 5, 5, 4)],
+            row.layout_children(Rect::new(10, 5, 10, 4))
+        );
+    }
+}
@@ -0,0 +1,652 @@
+// Button and SelectBox are the two components from the book. Both draw a border when they are
+// at least 3 rows tall and fall back to bare text when they are squeezed smaller than that.
+// The rest are the usual form pieces built the same way.
+//
+// Callbacks are boxed closures so a screen full of Box<dyn Draw> can still hold components that
+// each react differently. Share state with the rest of the program through Rc<RefCell<_>>.
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use super::event::{Event, Key, MouseKind};
+use super::framebuffer::{Canvas, Rect};
+use super::Draw;
+
+type ChangeCallback = Box<dyn FnMut(usize, &str)>;
+type TextCallback = Box<dyn FnMut(&str)>;
+
+// first row shown for a scrolling list, only moving when `selected` would be out of view
+fn scroll_to(offset: &Cell<usize>, selected: usize, rows: u32) -> usize {
+    let rows = rows.max(1) as usize;
+    let mut first = offset.get();
+    if selected < first {
+        first = selected;
+    } else if selected >= first + rows {
+        first = selected + 1 - rows;
+    }
+    offset.set(first);
+    first
+}
+
+pub struct Button {
+    pub width: u32,
+    pub height: u32,
+    pub label: String,
+    focused: bool,
+    on_click: Option<Box<dyn FnMut()>>,
+}
+
+impl Button {
+    pub fn new(width: u32, height: u32, label: &str) -> Button {
+        Button {
+            width,
+            height,
+            label: label.to_string(),
+            focused: false,
+            on_click: None,
+        }
+    }
+
+    pub fn on_click<F: FnMut() + 'static>(mut self, callback: F) -> Button {
+        self.on_click = Some(Box::new(callback));
+        self
+    }
+
+    pub fn click(&mut self) {
+        if let Some(callback) = self.on_click.as_mut() {
+            callback();
+        }
+    }
+}
+
+impl Draw for Button {
+    fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn draw(&self, canvas: &mut Canvas) {
+        if canvas.height() >= 3 {
+            if self.focused {
+                canvas.focused_border();
+            } else {
+                canvas.border();
+            }
+            let mut inner = canvas.inner();
+            let row = inner.height().saturating_sub(1) / 2;
+            inner.text_centered(row, &self.label);
+        } else if self.focused {
+            canvas.text_centered(0, &format!(">{}<", self.label));
+        } else {
+            canvas.text_centered(0, &format!("[{}]", self.label));
+        }
+    }
+
+    fn focusable(&self) -> bool {
+        true
+    }
+
+    fn set_focus(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    fn handle_event(&mut self, event: &Event) -> bool {
+        match event {
+            Event::Key(Key::Enter) | Event::Key(Key::Space) => {
+                self.click();
+                true
+            }
+            Event::Mouse {
+                kind: MouseKind::Click,
+                ..
+            } => {
+                self.click();
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+pub struct SelectBox {
+    pub width: u32,
+    pub height: u32,
+    pub options: Vec<String>,
+    selected: usize,
+    focused: bool,
+    // height we were last drawn at and the first option shown, used to map clicks back to
+    // options. Cells because draw only gets &self
+    drawn_height: Cell<u32>,
+    offset: Cell<usize>,
+    on_change: Option<ChangeCallback>,
+}
+
+impl SelectBox {
+    pub fn new(width: u32, height: u32, options: Vec<String>) -> SelectBox {
+        SelectBox {
+            width,
+            height,
+            options,
+            selected: 0,
+            focused: false,
+            drawn_height: Cell::new(height),
+            offset: Cell::new(0),
+            on_change: None,
+        }
+    }
+
+    /// Called with the new index and option whenever the selection changes.
+    pub fn on_change<F: FnMut(usize, &str) + 'static>(mut self, callback: F) -> SelectBox {
+        self.on_change = Some(Box::new(callback));
+        self
+    }
+
+    pub fn selected_index(&self) -> usize {
+        self.selected
+    }
+
+    pub fn selected(&self) -> Option<&str> {
+        self.options.get(self.selected).map(|s| s.as_str())
+    }
+
+    /// Moves the selection to `index`. Returns false if it was out of range or already there.
+    pub fn select(&mut self, index: usize) -> bool {
+        if index >= self.options.len() || index == self.selected {
+            return false;
+        }
+        self.selected = index;
+        if let Some(callback) = self.on_change.as_mut() {
+            callback(index, &self.options[index]);
+        }
+        true
+    }
+
+    fn bordered(&self) -> bool {
+        self.drawn_height.get() >= 3
+    }
+
+    fn rows(&self) -> u32 {
+        if self.bordered() {
+            self.drawn_height.get() - 2
+        } else {
+            self.drawn_height.get()
+        }
+    }
+
+    fn scroll_offset(&self) -> usize {
+        scroll_to(&self.offset, self.selected, self.rows())
+    }
+}
+
+impl Draw for SelectBox {
+    fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn draw(&self, canvas: &mut Canvas) {
+        self.drawn_height.set(canvas.height());
+
+        let mut area = if self.bordered() {
+            if self.focused {
+                canvas.focused_border();
+            } else {
+                canvas.border();
+            }
+            canvas.inner()
+        } else {
+            let (width, height) = (canvas.width(), canvas.height());
+            canvas.sub(Rect::new(0, 0, width, height))
+        };
+
+        let offset = self.scroll_offset();
+        for (row, (index, option)) in self.options.iter().enumerate().skip(offset).enumerate() {
+            let marker = if index == self.selected { '>' } else { ' ' };
+            area.text(0, row as u32, &format!("{} {}", marker, option));
+        }
+    }
+
+    fn focusable(&self) -> bool {
+        true
+    }
+
+    fn set_focus(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    fn handle_event(&mut self, event: &Event) -> bool {
+        match *event {
+            Event::Key(Key::Up)
+            | Event::Mouse {
+                kind: MouseKind::ScrollUp,
+                ..
+            } => self.selected > 0 && self.select(self.selected - 1),
+            Event::Key(Key::Down)
+            | Event::Mouse {
+                kind: MouseKind::ScrollDown,
+                ..
+            } => self.select(self.selected + 1),
+            Event::Mouse {
+                kind: MouseKind::Click,
+                y,
+                ..
+            } => {
+                let top = if self.bordered() { 1 } else { 0 };
+                if y < top || y - top >= self.rows() {
+                    return false;
+                }
+                self.select(self.scroll_offset() + (y - top) as usize)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Static text. Each line of `text` is drawn on its own row.
+pub struct Label {
+    pub text: String,
+}
+
+impl Label {
+    pub fn new(text: &str) -> Label {
+        Label {
+            text: text.to_string(),
+        }
+    }
+}
+
+impl Draw for Label {
+    fn size(&self) -> (u32, u32) {
+        let width = self.text.lines().map(|l| l.chars().count()).max().unwrap_or(0);
+        (width as u32, self.text.lines().count() as u32)
+    }
+
+    fn draw(&self, canvas: &mut Canvas) {
+        for (row, line) in self.text.lines().enumerate() {
+            canvas.text(0, row as u32, line);
+        }
+    }
+}
+
+/// A one line text field drawn as `[text   ]`. The cursor shows as `|` while focused.
+pub struct TextInput {
+    pub width: u32,
+    pub placeholder: String,
+    value: String,
+    // counted in chars, not bytes
+    cursor: usize,
+    focused: bool,
+    on_change: Option<TextCallback>,
+    on_submit: Option<TextCallback>,
+}
+
+impl TextInput {
+    pub fn new(width: u32) -> TextInput {
+        TextInput {
+            width,
+            placeholder: String::new(),
+            value: String::new(),
+            cursor: 0,
+            focused: false,
+            on_change: None,
+            on_submit: None,
+        }
+    }
+
+    /// Shown in place of an empty value while the input isn't focused.
+    pub fn placeholder(mut self, placeholder: &str) -> TextInput {
+        self.placeholder = placeholder.to_string();
+        self
+    }
+
+    pub fn with_value(mut self, value: &str) -> TextInput {
+        self.value = value.to_string();
+        self.cursor = value.chars().count();
+        self
+    }
+
+    pub fn on_change<F: FnMut(&str) + 'static>(mut self, callback: F) -> TextInput {
+        self.on_change = Some(Box::new(callback));
+        self
+    }
+
+    /// Called with the value when enter is pressed.
+    pub fn on_submit<F: FnMut(&str) + 'static>(mut self, callback: F) -> TextInput {
+        self.on_submit = Some(Box::new(callback));
+        self
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    fn byte_index(&self, cursor: usize) -> usize {
+        self.value
+            .char_indices()
+            .nth(cursor)
+            .map_or(self.value.len(), |(i, _)| i)
+    }
+
+    fn changed(&mut self) {
+        if let Some(callback) = self.on_change.as_mut() {
+            callback(&self.value);
+        }
+    }
+}
+
+impl Draw for TextInput {
+    fn size(&self) -> (u32, u32) {
+        (self.width, 1)
+    }
+
+    fn draw(&self, canvas: &mut Canvas) {
+        let inner = canvas.width().saturating_sub(2) as usize;
+        canvas.put(0, 0, '[');
+        canvas.put(canvas.width().saturating_sub(1), 0, ']');
+
+        let mut shown: Vec<char> = if self.value.is_empty() && !self.focused {
+            self.placeholder.chars().collect()
+        } else {
+            self.value.chars().collect()
+        };
+        let mut start = 0;
+        if self.focused {
+            shown.insert(self.cursor, '|');
+            // scroll so the cursor stays inside the brackets
+            start = (self.cursor + 1).saturating_sub(inner);
+        }
+        let visible: String = shown.iter().skip(start).take(inner).collect();
+        canvas.text(1, 0, &visible);
+    }
+
+    fn focusable(&self) -> bool {
+        true
+    }
+
+    fn set_focus(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    fn handle_event(&mut self, event: &Event) -> bool {
+        let len = self.value.chars().count();
+        match *event {
+            Event::Key(Key::Char(c)) => {
+                let at = self.byte_index(self.cursor);
+                self.value.insert(at, c);
+                self.cursor += 1;
+                self.changed();
+                true
+            }
+            Event::Key(Key::Space) => self.handle_event(&Event::Key(Key::Char(' '))),
+            Event::Key(Key::Backspace) => {
+                if self.cursor == 0 {
+                    return false;
+                }
+                self.cursor -= 1;
+                let at = self.byte_index(self.cursor);
+                self.value.remove(at);
+                self.changed();
+                true
+            }
+            Event::Key(Key::Left) if self.cursor > 0 => {
+                self.cursor -= 1;
+                true
+            }
+            Event::Key(Key::Right) if self.cursor < len => {
+                self.cursor += 1;
+                true
+            }
+            Event::Key(Key::Enter) => {
+                if let Some(callback) = self.on_submit.as_mut() {
+                    callback(&self.value);
+                }
+                true
+            }
+            Event::Mouse {
+                kind: MouseKind::Click,
+                x,
+                ..
+            } => {
+                self.cursor = (x.saturating_sub(1) as usize).min(len);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// `[x] label`, toggled with space, enter or a click.
+pub struct Checkbox {
+    pub label: String,
+    checked: bool,
+    focused: bool,
+    on_toggle: Option<Box<dyn FnMut(bool)>>,
+}
+
+impl Checkbox {
+    pub fn new(label: &str, checked: bool) -> Checkbox {
+        Checkbox {
+            label: label.to_string(),
+            checked,
+            focused: false,
+            on_toggle: None,
+        }
+    }
+
+    /// Called with the new checked state after every toggle.
+    pub fn on_toggle<F: FnMut(bool) + 'static>(mut self, callback: F) -> Checkbox {
+        self.on_toggle = Some(Box::new(callback));
+        self
+    }
+
+    pub fn is_checked(&self) -> bool {
+        self.checked
+    }
+
+    pub fn toggle(&mut self) {
+        self.checked = !self.checked;
+        if let Some(callback) = self.on_toggle.as_mut() {
+            callback(self.checked);
+        }
+    }
+}
+
+impl Draw for Checkbox {
+    fn size(&self) -> (u32, u32) {
+        (self.label.chars().count() as u32 + 5, 1)
+    }
+
+    fn draw(&self, canvas: &mut Canvas) {
+        let focus = if self.focused { '>' } else { ' ' };
+        let mark = if self.checked { 'x' } else { ' ' };
+        canvas.text(0, 0, &format!("{}[{}] {}", focus, mark, self.label));
+    }
+
+    fn focusable(&self) -> bool {
+        true
+    }
+
+    fn set_focus(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    fn handle_event(&mut self, event: &Event) -> bool {
+        match event {
+            Event::Key(Key::Space)
+            | Event::Key(Key::Enter)
+            | Event::Mouse {
+                kind: MouseKind::Click,
+                ..
+            } => {
+                self.toggle();
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// `[####----]  50%`. The progress is shared through an `Rc<Cell<f64>>` so callbacks on other
+/// components can move it after the bar has been boxed up in a Screen.
+pub struct ProgressBar {
+    pub width: u32,
+    progress: Rc<Cell<f64>>,
+}
+
+impl ProgressBar {
+    pub fn new(width: u32) -> ProgressBar {
+        ProgressBar {
+            width,
+            progress: Rc::new(Cell::new(0.0)),
+        }
+    }
+
+    pub fn with_progress(self, progress: f64) -> ProgressBar {
+        self.progress.set(progress);
+        self
+    }
+
+    /// The shared progress value, from 0.0 to 1.0.
+    pub fn handle(&self) -> Rc<Cell<f64>> {
+        Rc::clone(&self.progress)
+    }
+
+    /// Clamped to 0.0..=1.0 with NaN treated as no progress.
+    pub fn progress(&self) -> f64 {
+        let p = self.progress.get();
+        if p.is_nan() {
+            0.0
+        } else {
+            p.clamp(0.0, 1.0)
+        }
+    }
+}
+
+impl Draw for ProgressBar {
+    fn size(&self) -> (u32, u32) {
+        (self.width, 1)
+    }
+
+    fn draw(&self, canvas: &mut Canvas) {
+        let percent = format!("{:>4}", format!("{}%", (self.progress() * 100.0).round()));
+        // brackets plus a space before the percentage
+        let bar = canvas.width().saturating_sub(7);
+        let filled = (self.progress() * bar as f64).round() as u32;
+
+        let mut line = String::from("[");
+        for i in 0..bar {
+            line.push(if i < filled { '#' } else { '-' });
+        }
+        line.push_str("] ");
+        line.push_str(&percent);
+        canvas.text(0, 0, &line);
+    }
+}
+
+/// A scrolling list of items without a border. Unlike SelectBox the cursor only shows while
+/// focused and `on_select` fires when an item is picked with enter, space or a click.
+pub struct List {
+    pub width: u32,
+    pub height: u32,
+    pub items: Vec<String>,
+    cursor: usize,
+    focused: bool,
+    drawn_height: Cell<u32>,
+    offset: Cell<usize>,
+    on_select: Option<ChangeCallback>,
+}
+
+impl List {
+    pub fn new(width: u32, height: u32, items: Vec<String>) -> List {
+        List {
+            width,
+            height,
+            items,
+            cursor: 0,
+            focused: false,
+            drawn_height: Cell::new(height),
+            offset: Cell::new(0),
+            on_select: None,
+        }
+    }
+
+    pub fn on_select<F: FnMut(usize, &str) + 'static>(mut self, callback: F) -> List {
+        self.on_select = Some(Box::new(callback));
+        self
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    fn pick(&mut self) -> bool {
+        match (self.items.get(self.cursor), self.on_select.as_mut()) {
+            (Some(item), Some(callback)) => {
+                callback(self.cursor, item);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn scroll_offset(&self) -> usize {
+        scroll_to(&self.offset, self.cursor, self.drawn_height.get())
+    }
+}
+
+impl Draw for List {
+    fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn draw(&self, canvas: &mut Canvas) {
+        self.drawn_height.set(canvas.height());
+        let offset = self.scroll_offset();
+        for (row, (index, item)) in self.items.iter().enumerate().skip(offset).enumerate() {
+            let marker = if self.focused && index == self.cursor { '>' } else { ' ' };
+            canvas.text(0, row as u32, &format!("{} {}", marker, item));
+        }
+    }
+
+    fn focusable(&self) -> bool {
+        true
+    }
+
+    fn set_focus(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    fn handle_event(&mut self, event: &Event) -> bool {
+        match *event {
+            Event::Key(Key::Up)
+            | Event::Mouse {
+                kind: MouseKind::ScrollUp,
+                ..
+            } if self.cursor > 0 => {
+                self.cursor -= 1;
+                true
+            }
+            Event::Key(Key::Down)
+            | Event::Mouse {
+                kind: MouseKind::ScrollDown,
+                ..
+            } if self.cursor + 1 < self.items.len() => {
+                self.cursor += 1;
+                true
+            }
+            Event::Key(Key::Enter) | Event::Key(Key::Space) => self.pick(),
+            Event::Mouse {
+                kind: MouseKind::Click,
+                y,
+                ..
+            } => {
+                let index = self.scroll_offset() + y as usize;
+                if index >= self.items.len() {
+                    return false;
+                }
+                self.cursor = index;
+                self.pick();
+                true
+            }
+            _ => false,
+        }
+    }
+}
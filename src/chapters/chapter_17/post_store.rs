@@ -0,0 +1,555 @@
+// Persisting the type-state posts from 17.3
+//
+// Every save appends a revision to the post's file so nothing is ever overwritten, and the
+// workflow state travels with each revision. That way loading a post can hand back the same
+// typed value we saved (a DraftPost stays a DraftPost) instead of a loosely typed blob.
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::{DraftPost, PendingReviewPost, Post};
+
+/// Which step of the draft -> review -> published workflow a revision was saved in. Ordered
+/// along the workflow, so `Draft < Published`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum PostState {
+    Draft,
+    PendingReview,
+    Published,
+}
+
+impl fmt::Display for PostState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            PostState::Draft => "draft",
+            PostState::PendingReview => "pending review",
+            PostState::Published => "published",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// One saved snapshot of a post. Revision numbers start at 1.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Revision {
+    pub number: u32,
+    pub state: PostState,
+    pub content: String,
+}
+
+// what actually lands on disk, one file per post
+#[derive(Serialize, Deserialize)]
+struct PostRecord {
+    id: String,
+    revisions: Vec<Revision>,
+}
+
+// Rebuilding a typed post from bare content lives in a trait nobody outside the crate can name,
+// so the only way to get a Post from a String is through the store (or request_review and
+// approve). It is also what keeps anyone else from implementing StoredState.
+pub(crate) mod sealed {
+    pub trait Restore: Sized {
+        fn from_stored(content: String) -> Self;
+    }
+}
+
+use sealed::Restore;
+
+/// Implemented by each typed post so the store knows what state it is saving and how to
+/// rebuild the value on the way back out.
+pub trait StoredState: Restore {
+    const STATE: PostState;
+
+    fn stored_content(&self) -> &str;
+}
+
+impl StoredState for DraftPost {
+    const STATE: PostState = PostState::Draft;
+
+    fn stored_content(&self) -> &str {
+        &self.content
+    }
+}
+
+impl Restore for DraftPost {
+    fn from_stored(content: String) -> Self {
+        DraftPost { content }
+    }
+}
+
+impl StoredState for PendingReviewPost {
+    const STATE: PostState = PostState::PendingReview;
+
+    fn stored_content(&self) -> &str {
+        &self.content
+    }
+}
+
+impl Restore for PendingReviewPost {
+    fn from_stored(content: String) -> Self {
+        PendingReviewPost { content }
+    }
+}
+
+impl StoredState for Post {
+    const STATE: PostState = PostState::Published;
+
+    fn stored_content(&self) -> &str {
+        &self.content
+    }
+}
+
+impl Restore for Post {
+    fn from_stored(content: String) -> Self {
+        Post { content }
+    }
+}
+
+/// A post loaded without knowing its state up front. Match on it to get the typed value.
+pub enum LoadedPost {
+    Draft(DraftPost),
+    PendingReview(PendingReviewPost),
+    Published(Post),
+}
+
+impl LoadedPost {
+    pub fn state(&self) -> PostState {
+        match self {
+            LoadedPost::Draft(_) => PostState::Draft,
+            LoadedPost::PendingReview(_) => PostState::PendingReview,
+            LoadedPost::Published(_) => PostState::Published,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum StoreError {
+    Io(io::Error),
+    Format(serde_json::Error),
+    InvalidId(String),
+    NotFound(String),
+    NoSuchRevision {
+        id: String,
+        number: u32,
+    },
+    WrongState {
+        expected: PostState,
+        found: PostState,
+    },
+    /// Saving would move the post back along the workflow, e.g. a draft over a published post.
+    MovedBack {
+        latest: PostState,
+        saving: PostState,
+    },
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StoreError::Io(e) => write!(f, "post store io error: {}", e),
+            StoreError::Format(e) => write!(f, "post store file is corrupt: {}", e),
+            StoreError::InvalidId(id) => write!(f, "invalid post id {:?}", id),
+            StoreError::NotFound(id) => write!(f, "no post stored under {:?}", id),
+            StoreError::NoSuchRevision { id, number } => {
+                write!(f, "post {:?} has no revision {}", id, number)
+            }
+            StoreError::WrongState { expected, found } => {
+                write!(f, "expected a {} post but found a {} post", expected, found)
+            }
+            StoreError::MovedBack { latest, saving } => {
+                write!(f, "can't save a {} post over a {} one", saving, latest)
+            }
+        }
+    }
+}
+
+impl Error for StoreError {}
+
+impl From<io::Error> for StoreError {
+    fn from(e: io::Error) -> Self {
+        StoreError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for StoreError {
+    fn from(e: serde_json::Error) -> Self {
+        StoreError::Format(e)
+    }
+}
+
+/// Directory backed store. Each post lives in `<dir>/<id>.json` with its full history.
+pub struct PostStore {
+    dir: PathBuf,
+}
+
+impl PostStore {
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<PostStore, StoreError> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        Ok(PostStore { dir })
+    }
+
+    /// Appends a new revision for `id` and returns its revision number. A post only moves
+    /// forward, so saving a state earlier than the latest revision's is refused.
+    pub fn save<P: StoredState>(&self, id: &str, post: &P) -> Result<u32, StoreError> {
+        let mut record = match self.read_record(id) {
+            Ok(record) => record,
+            Err(StoreError::NotFound(_)) => PostRecord {
+                id: id.to_string(),
+                revisions: vec![],
+            },
+            Err(e) => return Err(e),
+        };
+        if let Some(latest) = record.revisions.last() {
+            if P::STATE < latest.state {
+                return Err(StoreError::MovedBack {
+                    latest: latest.state,
+                    saving: P::STATE,
+                });
+            }
+        }
+
+        let number = record.revisions.len() as u32 + 1;
+        record.revisions.push(Revision {
+            number,
+            state: P::STATE,
+            content: post.stored_content().to_string(),
+        });
+        self.write_record(&record)?;
+
+        Ok(number)
+    }
+
+    /// Loads the latest revision in whatever state it was saved in.
+    pub fn load(&self, id: &str) -> Result<LoadedPost, StoreError> {
+        let latest = self.latest(id)?;
+        Ok(match latest.state {
+            PostState::Draft => LoadedPost::Draft(DraftPost::from_stored(latest.content)),
+            PostState::PendingReview => {
+                LoadedPost::PendingReview(PendingReviewPost::from_stored(latest.content))
+            }
+            PostState::Published => LoadedPost::Published(Post::from_stored(latest.content)),
+        })
+    }
+
+    /// Loads the latest revision as `P`, failing if the post has moved on to another state.
+    /// `store.load_as::<DraftPost>("salad")` gives back a draft ready for more `add_text`.
+    pub fn load_as<P: StoredState>(&self, id: &str) -> Result<P, StoreError> {
+        let latest = self.latest(id)?;
+        if latest.state != P::STATE {
+            return Err(StoreError::WrongState {
+                expected: P::STATE,
+                found: latest.state,
+            });
+        }
+        Ok(P::from_stored(latest.content))
+    }
+
+    pub fn history(&self, id: &str) -> Result<Vec<Revision>, StoreError> {
+        Ok(self.read_record(id)?.revisions)
+    }
+
+    pub fn revision(&self, id: &str, number: u32) -> Result<Revision, StoreError> {
+        self.read_record(id)?
+            .revisions
+            .into_iter()
+            .find(|r| r.number == number)
+            .ok_or_else(|| StoreError::NoSuchRevision {
+                id: id.to_string(),
+                number,
+            })
+    }
+
+    /// Line diff of the content between two revisions of the same post.
+    pub fn diff(&self, id: &str, from: u32, to: u32) -> Result<Vec<DiffLine>, StoreError> {
+        let old = self.revision(id, from)?;
+        let new = self.revision(id, to)?;
+        Ok(diff_lines(&old.content, &new.content))
+    }
+
+    /// Every stored post id, sorted.
+    pub fn ids(&self) -> Result<Vec<String>, StoreError> {
+        let mut ids = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                    ids.push(stem.to_string());
+                }
+            }
+        }
+        ids.sort();
+        Ok(ids)
+    }
+
+    pub fn delete(&self, id: &str) -> Result<(), StoreError> {
+        let path = self.path_for(id)?;
+        match fs::remove_file(path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Err(StoreError::NotFound(id.into())),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn latest(&self, id: &str) -> Result<Revision, StoreError> {
+        self.read_record(id)?
+            .revisions
+            .pop()
+            .ok_or_else(|| StoreError::NotFound(id.to_string()))
+    }
+
+    // ids become file names so keep them to a safe set of characters
+    fn path_for(&self, id: &str) -> Result<PathBuf, StoreError> {
+        let valid = !id.is_empty()
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(StoreError::InvalidId(id.to_string()));
+        }
+        Ok(self.dir.join(format!("{}.json", id)))
+    }
+
+    fn read_record(&self, id: &str) -> Result<PostRecord, StoreError> {
+        let path = self.path_for(id)?;
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Err(StoreError::NotFound(id.to_string()))
+            }
+            Err(e) => return Err(e.into()),
+        };
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    // write to a temp file then rename so a crash mid write can't truncate the history
+    fn write_record(&self, record: &PostRecord) -> Result<(), StoreError> {
+        let path = self.path_for(&record.id)?;
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(record)?)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+}
+
+/// One line of a revision diff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffLine {
+    Same(String),
+    Added(String),
+    Removed(String),
+}
+
+impl fmt::Display for DiffLine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DiffLine::Same(line) => write!(f, "  {}", line),
+            DiffLine::Added(line) => write!(f, "+ {}", line),
+            DiffLine::Removed(line) => write!(f, "- {}", line),
+        }
+    }
+}
+
+/// Longest common subsequence diff over lines. Posts are small so the O(n*m) table is fine.
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // lcs[i][j] = length of the lcs of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut result = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            result.push(DiffLine::Same(old[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            result.push(DiffLine::Removed(old[i].to_string()));
+            i += 1;
+        } else {
+            result.push(DiffLine::Added(new[j].to_string()));
+            j += 1;
+        }
+    }
+    result.extend(old[i..].iter().map(|l| DiffLine::Removed(l.to_string())));
+    result.extend(new[j..].iter().map(|l| DiffLine::Added(l.to_string())));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process;
+
+    // each test gets its own directory so they can run in parallel
+    fn temp_store(name: &str) -> (PostStore, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("post_store_{}_{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        (PostStore::open(&dir).unwrap(), dir)
+    }
+
+    #[test]
+    fn saves_every_state_and_keeps_history() {
+        let (store, dir) = temp_store("history");
+
+        let mut post = Post::new();
+        post.add_text("I ate a salad");
+        assert_eq!(1, store.save("salad", &post).unwrap());
+
+        post.add_text(" for lunch today");
+        assert_eq!(2, store.save("salad", &post).unwrap());
+
+        let post = post.request_review();
+        assert_eq!(3, store.save("salad", &post).unwrap());
+
+        let post = post.approve();
+        assert_eq!(4, store.save("salad", &post).unwrap());
+
+        let states: Vec<PostState> = store
+            .history("salad")
+            .unwrap()
+            .iter()
+            .map(|r| r.state)
+            .collect();
+        assert_eq!(
+            vec![
+                PostState::Draft,
+                PostState::Draft,
+                PostState::PendingReview,
+                PostState::Published
+            ],
+            states
+        );
+        assert_eq!("I ate a salad", store.revision("salad", 1).unwrap().content);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn reloads_into_the_typed_state() {
+        let (store, dir) = temp_store("typed");
+
+        let mut draft = Post::new();
+        draft.add_text("first");
+        store.save("typed", &draft).unwrap();
+
+        let mut draft: DraftPost = store.load_as("typed").unwrap();
+        draft.add_text(" and more");
+        let review = draft.request_review();
+        store.save("typed", &review).unwrap();
+
+        match store.load_as::<DraftPost>("typed") {
+            Err(StoreError::WrongState { expected, found }) => {
+                assert_eq!(PostState::Draft, expected);
+                assert_eq!(PostState::PendingReview, found);
+            }
+            _ => panic!("a pending post should not load as a draft"),
+        }
+
+        match store.load("typed").unwrap() {
+            LoadedPost::PendingReview(post) => {
+                assert_eq!("first and more", post.approve().content())
+            }
+            _ => panic!("expected the pending review post back"),
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn never_moves_a_post_back() {
+        let (store, dir) = temp_store("back");
+
+        let mut draft = Post::new();
+        draft.add_text("done");
+        store
+            .save("done", &draft.request_review().approve())
+            .unwrap();
+
+        let mut draft = Post::new();
+        draft.add_text("redone");
+        match store.save("done", &draft) {
+            Err(StoreError::MovedBack { latest, saving }) => {
+                assert_eq!(PostState::Published, latest);
+                assert_eq!(PostState::Draft, saving);
+            }
+            _ => panic!("a draft should not be saved over a published post"),
+        }
+        assert!(matches!(
+            store.save("done", &draft.request_review()),
+            Err(StoreError::MovedBack { .. })
+        ));
+        assert_eq!(1, store.history("done").unwrap().len());
+        assert_eq!("done", store.load_as::<Post>("done").unwrap().content());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn diffs_between_revisions() {
+        let (store, dir) = temp_store("diff");
+
+        let mut post = Post::new();
+        post.add_text("title\nfirst line\nsecond line");
+        store.save("diffed", &post).unwrap();
+        let post = DraftPost::from_stored(String::from("title\nsecond line\nthird line"));
+        store.save("diffed", &post).unwrap();
+
+        let diff = store.diff("diffed", 1, 2).unwrap();
+        assert_eq!(
+            vec![
+                DiffLine::Same(String::from("title")),
+                DiffLine::Removed(String::from("first line")),
+                DiffLine::Same(String::from("second line")),
+                DiffLine::Added(String::from("third line")),
+            ],
+            diff
+        );
+
+        assert!(matches!(
+            store.diff("diffed", 1, 3),
+            Err(StoreError::NoSuchRevision { number: 3, .. })
+        ));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_ids_that_are_not_file_safe() {
+        let (store, dir) = temp_store("ids");
+
+        assert!(matches!(
+            store.save("../escape", &Post::new()),
+            Err(StoreError::InvalidId(_))
+        ));
+        assert!(matches!(
+            store.load("missing"),
+            Err(StoreError::NotFound(_))
+        ));
+
+        store.save("b", &Post::new()).unwrap();
+        store.save("a", &Post::new()).unwrap();
+        assert_eq!(vec!["a", "b"], store.ids().unwrap());
+
+        store.delete("a").unwrap();
+        assert_eq!(vec!["b"], store.ids().unwrap());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,537 @@
+// The Box<dyn State> version of the blog post from 17.3
+//
+// The book's State objects hard code their transitions (Draft::request_review returns a
+// PendingReview and so on). Here the transitions live in a Workflow table instead so a workflow
+// can be loaded from a file at runtime. The compiler can't check those, which is the trade off
+// against the type-state Post/DraftPost/PendingReviewPost in the parent module.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+use super::post_store::sealed::Restore;
+use super::post_store::{LoadedPost, PostState};
+use super::{DraftPost, PendingReviewPost, Post};
+
+/// The book's draft -> review -> published flow, with a reject back to draft added.
+pub const STANDARD_WORKFLOW: &str = "\
+# state <name> [editable] [visible]
+state draft editable
+state pending_review
+state published visible
+initial draft
+
+# <from> <event> -> <to>
+draft request_review -> pending_review
+pending_review approve -> published
+pending_review reject -> draft
+";
+
+pub trait State {
+    fn name(&self) -> &str;
+
+    // same shape as the book's request_review/approve, but the next state comes from the table
+    fn on_event(self: Box<Self>, event: &str, workflow: &Workflow) -> Box<dyn State>;
+
+    fn accepts_text(&self) -> bool {
+        false
+    }
+
+    // default implementation hides the content just like the book's Draft and PendingReview
+    fn content<'a>(&self, _post: &'a DynPost) -> &'a str {
+        ""
+    }
+}
+
+/// How a state behaves, as read from a workflow file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateSpec {
+    pub name: String,
+    pub editable: bool,
+    pub visible: bool,
+}
+
+// the only State impl we need since the behavior is all data now
+struct ConfiguredState {
+    spec: StateSpec,
+}
+
+impl State for ConfiguredState {
+    fn name(&self) -> &str {
+        &self.spec.name
+    }
+
+    fn on_event(self: Box<Self>, event: &str, workflow: &Workflow) -> Box<dyn State> {
+        match workflow.next_state(&self.spec.name, event) {
+            Some(to) => workflow
+                .state(to)
+                .expect("workflow transitions are validated on build"),
+            // unknown transitions leave the post where it is, like approve on a Draft
+            None => self,
+        }
+    }
+
+    fn accepts_text(&self) -> bool {
+        self.spec.editable
+    }
+
+    fn content<'a>(&self, post: &'a DynPost) -> &'a str {
+        if self.spec.visible {
+            &post.content
+        } else {
+            ""
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorkflowError {
+    Syntax { line: usize, message: String },
+    DuplicateState(String),
+    UnknownState(String),
+    MissingInitial,
+}
+
+impl fmt::Display for WorkflowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WorkflowError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+            WorkflowError::DuplicateState(name) => write!(f, "state {:?} declared twice", name),
+            WorkflowError::UnknownState(name) => write!(f, "unknown state {:?}", name),
+            WorkflowError::MissingInitial => write!(f, "workflow has no initial state"),
+        }
+    }
+}
+
+impl Error for WorkflowError {}
+
+/// States plus a (state, event) -> state transition table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Workflow {
+    initial: String,
+    states: HashMap<String, StateSpec>,
+    transitions: HashMap<(String, String), String>,
+}
+
+impl Workflow {
+    pub fn standard() -> Rc<Workflow> {
+        Rc::new(Workflow::parse(STANDARD_WORKFLOW).expect("the standard workflow is valid"))
+    }
+
+    /// Builds a workflow from its pieces, checking every name refers to a declared state.
+    pub fn new(
+        initial: &str,
+        states: Vec<StateSpec>,
+        transitions: Vec<(&str, &str, &str)>,
+    ) -> Result<Workflow, WorkflowError> {
+        let mut by_name = HashMap::new();
+        for spec in states {
+            if by_name.contains_key(&spec.name) {
+                return Err(WorkflowError::DuplicateState(spec.name));
+            }
+            by_name.insert(spec.name.clone(), spec);
+        }
+
+        let known = |name: &str| {
+            if by_name.contains_key(name) {
+                Ok(name.to_string())
+            } else {
+                Err(WorkflowError::UnknownState(name.to_string()))
+            }
+        };
+
+        let initial = known(initial)?;
+        let mut table = HashMap::new();
+        for (from, event, to) in transitions {
+            table.insert((known(from)?, event.to_string()), known(to)?);
+        }
+
+        Ok(Workflow {
+            initial,
+            states: by_name,
+            transitions: table,
+        })
+    }
+
+    /// Parses the line based format shown in `STANDARD_WORKFLOW`.
+    pub fn parse(text: &str) -> Result<Workflow, WorkflowError> {
+        let mut initial = None;
+        let mut states = vec![];
+        let mut transitions = vec![];
+
+        for (index, line) in text.lines().enumerate() {
+            let line_no = index + 1;
+            let syntax = |message: &str| WorkflowError::Syntax {
+                line: line_no,
+                message: message.to_string(),
+            };
+
+            let line = line.split('#').next().unwrap_or("").trim();
+            let words: Vec<&str> = line.split_whitespace().collect();
+
+            match words.as_slice() {
+                [] => {}
+                ["state", name, flags @ ..] => {
+                    let mut spec = StateSpec {
+                        name: name.to_string(),
+                        editable: false,
+                        visible: false,
+                    };
+                    for flag in flags {
+                        match *flag {
+                            "editable" => spec.editable = true,
+                            "visible" => spec.visible = true,
+                            _ => return Err(syntax(&format!("unknown state flag {:?}", flag))),
+                        }
+                    }
+                    states.push(spec);
+                }
+                ["initial", name] => initial = Some(*name),
+                [from, event, "->", to] => transitions.push((*from, *event, *to)),
+                _ => return Err(syntax("expected a state, initial or transition line")),
+            }
+        }
+
+        let initial = initial.ok_or(WorkflowError::MissingInitial)?;
+        Workflow::new(initial, states, transitions)
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Workflow, Box<dyn Error>> {
+        let text = fs::read_to_string(path)?;
+        Ok(Workflow::parse(&text)?)
+    }
+
+    pub fn initial(&self) -> &str {
+        &self.initial
+    }
+
+    pub fn next_state(&self, from: &str, event: &str) -> Option<&str> {
+        self.transitions
+            .get(&(from.to_string(), event.to_string()))
+            .map(|to| to.as_str())
+    }
+
+    /// A fresh state object for `name`.
+    pub fn state(&self, name: &str) -> Option<Box<dyn State>> {
+        self.states
+            .get(name)
+            .map(|spec| Box::new(ConfiguredState { spec: spec.clone() }) as Box<dyn State>)
+    }
+}
+
+/// One entry per event fired at a post, including the ones that didn't move it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    pub event: String,
+    pub from: String,
+    pub to: String,
+}
+
+impl LogEntry {
+    pub fn changed_state(&self) -> bool {
+        self.from != self.to
+    }
+}
+
+pub struct DynPost {
+    state: Option<Box<dyn State>>,
+    content: String,
+    workflow: Rc<Workflow>,
+    log: Vec<LogEntry>,
+}
+
+impl DynPost {
+    pub fn new(workflow: Rc<Workflow>) -> DynPost {
+        DynPost {
+            state: workflow.state(workflow.initial()),
+            content: String::new(),
+            workflow,
+            log: vec![],
+        }
+    }
+
+    /// Returns false and leaves the content alone if the current state isn't editable.
+    pub fn add_text(&mut self, text: &str) -> bool {
+        if self.state.as_ref().unwrap().accepts_text() {
+            self.content.push_str(text);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn content(&self) -> &str {
+        self.state.as_ref().unwrap().content(self)
+    }
+
+    pub fn state_name(&self) -> &str {
+        self.state.as_ref().unwrap().name()
+    }
+
+    /// Runs `event` through the workflow. Returns whether the state changed.
+    pub fn fire(&mut self, event: &str) -> bool {
+        if let Some(s) = self.state.take() {
+            let from = s.name().to_string();
+            let next = s.on_event(event, &self.workflow);
+            self.log.push(LogEntry {
+                event: event.to_string(),
+                from,
+                to: next.name().to_string(),
+            });
+            self.state = Some(next);
+        }
+        self.log.last().is_some_and(|entry| entry.changed_state())
+    }
+
+    pub fn request_review(&mut self) -> bool {
+        self.fire("request_review")
+    }
+
+    pub fn approve(&mut self) -> bool {
+        self.fire("approve")
+    }
+
+    pub fn reject(&mut self) -> bool {
+        self.fire("reject")
+    }
+
+    pub fn log(&self) -> &[LogEntry] {
+        &self.log
+    }
+
+    // used when coming from the type-state side where we know the state already
+    fn in_state(state: PostState, content: String) -> DynPost {
+        let workflow = Workflow::standard();
+        DynPost {
+            state: workflow.state(standard_state_name(state)),
+            content,
+            workflow,
+            log: vec![],
+        }
+    }
+}
+
+fn standard_state_name(state: PostState) -> &'static str {
+    match state {
+        PostState::Draft => "draft",
+        PostState::PendingReview => "pending_review",
+        PostState::Published => "published",
+    }
+}
+
+// *** Converting between the two representations
+// Going to DynPost always works and uses the standard workflow. Coming back only works for a
+// post on the standard workflow in one of its three states. A custom workflow may name a state
+// "published" without it meaning what Post means, so those posts stay dynamic.
+
+impl From<DraftPost> for DynPost {
+    fn from(post: DraftPost) -> DynPost {
+        DynPost::in_state(PostState::Draft, post.content)
+    }
+}
+
+impl From<PendingReviewPost> for DynPost {
+    fn from(post: PendingReviewPost) -> DynPost {
+        DynPost::in_state(PostState::PendingReview, post.content)
+    }
+}
+
+impl From<Post> for DynPost {
+    fn from(post: Post) -> DynPost {
+        DynPost::in_state(PostState::Published, post.content)
+    }
+}
+
+impl From<LoadedPost> for DynPost {
+    fn from(post: LoadedPost) -> DynPost {
+        match post {
+            LoadedPost::Draft(p) => p.into(),
+            LoadedPost::PendingReview(p) => p.into(),
+            LoadedPost::Published(p) => p.into(),
+        }
+    }
+}
+
+impl TryFrom<DynPost> for LoadedPost {
+    // hands the post back untouched when its state has no typed equivalent
+    type Error = DynPost;
+
+    fn try_from(post: DynPost) -> Result<LoadedPost, DynPost> {
+        if *post.workflow != *Workflow::standard() {
+            return Err(post);
+        }
+        let loaded = match post.state_name() {
+            "draft" => LoadedPost::Draft(DraftPost::from_stored(post.content)),
+            "pending_review" => {
+                LoadedPost::PendingReview(PendingReviewPost::from_stored(post.content))
+            }
+            "published" => LoadedPost::Published(Post::from_stored(post.content)),
+            _ => return Err(post),
+        };
+        Ok(loaded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_the_book_example() {
+        let mut post = DynPost::new(Workflow::standard());
+
+        assert!(post.add_text("I ate a salad for lunch today"));
+        assert_eq!("", post.content());
+
+        assert!(post.request_review());
+        assert_eq!("", post.content());
+        assert!(!post.add_text(" and dessert"));
+
+        assert!(post.approve());
+        assert_eq!("I ate a salad for lunch today", post.content());
+        assert_eq!("published", post.state_name());
+    }
+
+    #[test]
+    fn logs_every_event_even_ignored_ones() {
+        let mut post = DynPost::new(Workflow::standard());
+
+        assert!(!post.approve());
+        post.request_review();
+        post.reject();
+
+        let log: Vec<(&str, &str, &str)> = post
+            .log()
+            .iter()
+            .map(|e| (e.event.as_str(), e.from.as_str(), e.to.as_str()))
+            .collect();
+        assert_eq!(
+            vec![
+                ("approve", "draft", "draft"),
+                ("request_review", "draft", "pending_review"),
+                ("reject", "pending_review", "draft"),
+            ],
+            log
+        );
+    }
+
+    #[test]
+    fn loads_a_custom_workflow() {
+        let workflow = Workflow::parse(
+            "state draft editable
+             state scheduled
+             state live visible
+             initial draft
+             draft schedule -> scheduled
+             scheduled publish -> live # cron fires this",
+        )
+        .unwrap();
+        let mut post = DynPost::new(Rc::new(workflow));
+
+        post.add_text("tomorrow's news");
+        assert!(!post.approve());
+        assert!(post.fire("schedule"));
+        assert!(post.fire("publish"));
+        assert_eq!("tomorrow's news", post.content());
+
+        // no typed equivalent for "live" so we get the post back
+        let post = LoadedPost::try_from(post).err().unwrap();
+        assert_eq!("live", post.state_name());
+    }
+
+    #[test]
+    fn only_the_standard_workflow_converts_to_typed_posts() {
+        // "published" straight from an uneditable "draft", skipping review altogether
+        let workflow = Rc::new(
+            Workflow::parse(
+                "state draft
+                 state published visible
+                 initial draft
+                 draft publish -> published",
+            )
+            .unwrap(),
+        );
+
+        let post = DynPost::new(Rc::clone(&workflow));
+        let post = LoadedPost::try_from(post).err().unwrap();
+        assert_eq!("draft", post.state_name());
+
+        let mut post = post;
+        assert!(post.fire("publish"));
+        let post = LoadedPost::try_from(post).err().unwrap();
+        assert_eq!("published", post.state_name());
+
+        // the same flow parsed from the standard text is still the standard workflow
+        let standard = Rc::new(Workflow::parse(STANDARD_WORKFLOW).unwrap());
+        let mut post = DynPost::new(standard);
+        post.add_text("fine");
+        post.request_review();
+        post.approve();
+        match LoadedPost::try_from(post) {
+            Ok(LoadedPost::Published(post)) => assert_eq!("fine", post.content()),
+            _ => panic!("expected a published post"),
+        }
+    }
+
+    #[test]
+    fn rejects_bad_workflows() {
+        assert_eq!(
+            WorkflowError::UnknownState(String::from("nowhere")),
+            Workflow::parse("state a\ninitial a\na go -> nowhere").unwrap_err()
+        );
+        assert_eq!(
+            WorkflowError::MissingInitial,
+            Workflow::parse("state a").unwrap_err()
+        );
+        assert!(matches!(
+            Workflow::parse("state a shiny\ninitial a"),
+            Err(WorkflowError::Syntax { line: 1, .. })
+        ));
+        assert_eq!(
+            WorkflowError::DuplicateState(String::from("a")),
+            Workflow::parse("state a\nstate a\ninitial a").unwrap_err()
+        );
+    }
+
+    // drives both versions through the same steps and checks they agree after each one,
+    // converting across at every step so both directions get exercised
+    #[test]
+    fn type_state_and_dyn_state_are_equivalent() {
+        fn assert_same(typed: LoadedPost, dynamic: &DynPost) -> LoadedPost {
+            let round_trip = DynPost::from(typed);
+            assert_eq!(dynamic.state_name(), round_trip.state_name());
+            assert_eq!(dynamic.content(), round_trip.content());
+            LoadedPost::try_from(round_trip).ok().unwrap()
+        }
+
+        let mut dynamic = DynPost::new(Workflow::standard());
+        let mut draft = Post::new();
+        let typed = assert_same(LoadedPost::Draft(Post::new()), &dynamic);
+        assert_eq!(PostState::Draft, typed.state());
+
+        draft.add_text("I ate a salad");
+        dynamic.add_text("I ate a salad");
+        let draft = match assert_same(LoadedPost::Draft(draft), &dynamic) {
+            LoadedPost::Draft(d) => d,
+            _ => panic!("expected a draft"),
+        };
+
+        let pending = draft.request_review();
+        dynamic.request_review();
+        let pending = match assert_same(LoadedPost::PendingReview(pending), &dynamic) {
+            LoadedPost::PendingReview(p) => p,
+            _ => panic!("expected a pending review post"),
+        };
+
+        let published = pending.approve();
+        dynamic.approve();
+        match assert_same(LoadedPost::Published(published), &dynamic) {
+            LoadedPost::Published(p) => assert_eq!("I ate a salad", p.content()),
+            _ => panic!("expected a published post"),
+        }
+    }
+}
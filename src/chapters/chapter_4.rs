@@ -314,6 +314,7 @@ pub fn slice_type() {
     }
 
     // extremely brittle and error prone thus slices are introduced to avoid logic that trys to maintain sync
+    // (both only know about b' ', chapter_8::text::first_word splits on any Unicode whitespace)
     fn first_word(s: &String) -> usize {
         let bytes = s.as_bytes();
 
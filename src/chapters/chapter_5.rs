@@ -1,11 +1,28 @@
 pub fn defining_and_init_structs() {
+    use builder_derive::Builder;
+
+    // Builder adds User::builder(), see build_default_user below for what it saves
+    #[derive(Builder)]
+    #[builder(validate = User::check)]
     struct User {
         username: String,
         email: String,
+        #[builder(default = 1)]
         sign_in_count: u64,
+        #[builder(default = true)]
         active: bool,
     }
 
+    impl User {
+        fn check(&self) -> Result<(), &'static str> {
+            if self.email.contains('@') {
+                Ok(())
+            } else {
+                Err("email needs an @")
+            }
+        }
+    }
+
     // 5.1
     // will create a user with defaults and takes email and username
     fn build_default_user(email: String, username: String) -> User {
@@ -19,6 +36,15 @@ pub fn defining_and_init_structs() {
         }
     }
 
+    // the same defaults through the builder, email and username have to be given
+    let built = User::builder()
+        .email("someone@example.com")
+        .username("someusernamehere")
+        .build()
+        .unwrap();
+    assert!(built.active && built.sign_in_count == 1);
+    assert!(User::builder().email("nope").username("x").build().is_err());
+
     struct Color(i32, i32, i32);
     struct Point(i32, i32, i32);
     // CHAPTER 5 Defining and Instantiating Structs
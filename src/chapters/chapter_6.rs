@@ -1,3 +1,10 @@
+// 6.2 follow up: the Coin and UsState enums below as a money module with exact cents
+pub mod money;
+// 6.1 follow up: the IpAddr enums below parsed for real, with CIDR blocks and ranges
+pub mod ip;
+// 6.1 follow up: Message below as a protocol, binary frames and a line of text each
+pub mod message;
+
 pub fn enums() {
     // STRUCTS **************************************************
     struct IpAddr {
@@ -23,6 +30,7 @@ pub fn enums() {
         ChangeColor(i32, i32, i32),
     }
 
+    // message::Message is this one with call dispatching to a Handler
     impl Message {
         fn call(&self) {}
     }
@@ -133,6 +141,7 @@ pub fn enum_and_flow_control() {
     }
 
     // unlike an if that requires a bool we can take any type
+    // (u8 is fine for one coin but not for adding them up, money::Money uses i64 cents)
     fn value_in_cents(coin: Coin) -> u8 {
         match coin {
             Coin::Penny => 1,
@@ -0,0 +1,457 @@
+// IpAddrKind and IpAddrEnum from enums(), doing something now. Parsing leans on std::net, which
+// already gets every IPv6 shorthand right, and converts into our enum. On top of that: CIDR
+// blocks like 10.0.0.0/8, checking whether an address or a smaller block is inside one, and
+// walking a range an address at a time.
+//
+// Internally both kinds are treated as a number, u32 widened to u128 for V4, so masks and ranges
+// are plain integer arithmetic.
+
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::net;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IpAddrKind {
+    V4,
+    V6,
+}
+
+impl IpAddrKind {
+    /// Bits in an address of this kind.
+    pub fn width(self) -> u8 {
+        match self {
+            IpAddrKind::V4 => 32,
+            IpAddrKind::V6 => 128,
+        }
+    }
+}
+
+/// The chapter's IpAddrEnum, with V6 as its eight 16 bit groups instead of a String.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum IpAddr {
+    V4(u8, u8, u8, u8),
+    V6([u16; 8]),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IpError {
+    InvalidAddress(String),
+    InvalidPrefix(String),
+    /// A range or block mixing V4 and V6.
+    MixedKinds,
+    /// A range whose end comes before its start.
+    Backwards,
+}
+
+impl fmt::Display for IpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IpError::InvalidAddress(text) => write!(f, "{:?} isn't an IP address", text),
+            IpError::InvalidPrefix(text) => write!(f, "{:?} isn't a valid prefix length", text),
+            IpError::MixedKinds => write!(f, "can't mix IPv4 and IPv6 addresses"),
+            IpError::Backwards => write!(f, "the range ends before it starts"),
+        }
+    }
+}
+
+impl Error for IpError {}
+
+impl IpAddr {
+    pub fn kind(&self) -> IpAddrKind {
+        match self {
+            IpAddr::V4(..) => IpAddrKind::V4,
+            IpAddr::V6(_) => IpAddrKind::V6,
+        }
+    }
+
+    /// The address as a number, V4 in the low 32 bits.
+    pub fn to_bits(&self) -> u128 {
+        match *self {
+            IpAddr::V4(a, b, c, d) => u32::from_be_bytes([a, b, c, d]) as u128,
+            IpAddr::V6(segments) => u128::from(net::Ipv6Addr::from(segments)),
+        }
+    }
+
+    /// Panics if `bits` is too big for `kind`.
+    pub fn from_bits(kind: IpAddrKind, bits: u128) -> IpAddr {
+        match kind {
+            IpAddrKind::V4 => {
+                let [a, b, c, d] = u32::try_from(bits)
+                    .expect("more than 32 bits for an IPv4 address")
+                    .to_be_bytes();
+                IpAddr::V4(a, b, c, d)
+            }
+            IpAddrKind::V6 => IpAddr::V6(net::Ipv6Addr::from(bits).segments()),
+        }
+    }
+
+    pub fn is_loopback(&self) -> bool {
+        net::IpAddr::from(*self).is_loopback()
+    }
+}
+
+impl From<net::Ipv4Addr> for IpAddr {
+    fn from(addr: net::Ipv4Addr) -> IpAddr {
+        let [a, b, c, d] = addr.octets();
+        IpAddr::V4(a, b, c, d)
+    }
+}
+
+impl From<net::Ipv6Addr> for IpAddr {
+    fn from(addr: net::Ipv6Addr) -> IpAddr {
+        IpAddr::V6(addr.segments())
+    }
+}
+
+impl From<net::IpAddr> for IpAddr {
+    fn from(addr: net::IpAddr) -> IpAddr {
+        match addr {
+            net::IpAddr::V4(addr) => addr.into(),
+            net::IpAddr::V6(addr) => addr.into(),
+        }
+    }
+}
+
+impl From<IpAddr> for net::IpAddr {
+    fn from(addr: IpAddr) -> net::IpAddr {
+        match addr {
+            IpAddr::V4(a, b, c, d) => net::IpAddr::V4(net::Ipv4Addr::new(a, b, c, d)),
+            IpAddr::V6(segments) => net::IpAddr::V6(segments.into()),
+        }
+    }
+}
+
+impl FromStr for IpAddr {
+    type Err = IpError;
+
+    /// Dotted quad for V4, any of the usual IPv6 forms (`::1`, `fe80::1:2`, `::ffff:1.2.3.4`).
+    fn from_str(text: &str) -> Result<IpAddr, IpError> {
+        text.trim()
+            .parse::<net::IpAddr>()
+            .map(IpAddr::from)
+            .map_err(|_| IpError::InvalidAddress(text.to_string()))
+    }
+}
+
+impl fmt::Display for IpAddr {
+    /// The shortest standard form, `::1` rather than `0:0:0:0:0:0:0:1`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&net::IpAddr::from(*self), f)
+    }
+}
+
+/// A network like `192.168.0.0/16`: the addresses sharing their first `prefix` bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cidr {
+    network: IpAddr,
+    prefix: u8,
+}
+
+fn mask(kind: IpAddrKind, prefix: u8) -> u128 {
+    let host_bits = kind.width() - prefix;
+    let all = u128::MAX >> (128 - kind.width() as u32);
+    // shifting by the full width would overflow, a /0 has no network bits at all
+    all.checked_shl(host_bits as u32).unwrap_or(0) & all
+}
+
+impl Cidr {
+    /// Any host bits in `addr` are cleared, so 10.1.2.3/8 is 10.0.0.0/8.
+    pub fn new(addr: IpAddr, prefix: u8) -> Result<Cidr, IpError> {
+        let kind = addr.kind();
+        if prefix > kind.width() {
+            return Err(IpError::InvalidPrefix(prefix.to_string()));
+        }
+        let network = IpAddr::from_bits(kind, addr.to_bits() & mask(kind, prefix));
+        Ok(Cidr { network, prefix })
+    }
+
+    pub fn network(&self) -> IpAddr {
+        self.network
+    }
+
+    pub fn prefix(&self) -> u8 {
+        self.prefix
+    }
+
+    pub fn kind(&self) -> IpAddrKind {
+        self.network.kind()
+    }
+
+    /// 255.255.255.0 for a /24.
+    pub fn netmask(&self) -> IpAddr {
+        IpAddr::from_bits(self.kind(), mask(self.kind(), self.prefix))
+    }
+
+    pub fn first(&self) -> IpAddr {
+        self.network
+    }
+
+    /// The broadcast address for V4.
+    pub fn last(&self) -> IpAddr {
+        let kind = self.kind();
+        let host = !mask(kind, self.prefix) & (u128::MAX >> (128 - kind.width() as u32));
+        IpAddr::from_bits(kind, self.network.to_bits() | host)
+    }
+
+    /// How many addresses, `None` for an IPv6 /0 since 2^128 doesn't fit.
+    pub fn size(&self) -> Option<u128> {
+        1u128.checked_shl((self.kind().width() - self.prefix) as u32)
+    }
+
+    /// False for an address of the other kind.
+    pub fn contains(&self, addr: &IpAddr) -> bool {
+        addr.kind() == self.kind()
+            && addr.to_bits() & mask(self.kind(), self.prefix) == self.network.to_bits()
+    }
+
+    /// `other` is this block or a subnet of it.
+    pub fn contains_cidr(&self, other: &Cidr) -> bool {
+        other.prefix >= self.prefix && self.contains(&other.network)
+    }
+
+    /// Every address in the block, network and broadcast included.
+    pub fn iter(&self) -> AddrRange {
+        AddrRange {
+            kind: self.kind(),
+            next: Some(self.first().to_bits()),
+            last: self.last().to_bits(),
+        }
+    }
+}
+
+impl FromStr for Cidr {
+    type Err = IpError;
+
+    /// `10.0.0.0/8`, `fe80::/10`. A bare address is a block of one.
+    fn from_str(text: &str) -> Result<Cidr, IpError> {
+        let text = text.trim();
+        let (addr, prefix) = match text.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (text, None),
+        };
+        let addr: IpAddr = addr.parse()?;
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .parse::<u8>()
+                .map_err(|_| IpError::InvalidPrefix(prefix.to_string()))?,
+            None => addr.kind().width(),
+        };
+        Cidr::new(addr, prefix)
+    }
+}
+
+impl fmt::Display for Cidr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix)
+    }
+}
+
+/// The addresses from one to another, both included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddrRange {
+    kind: IpAddrKind,
+    // None once the range is used up, `last` can be the very top address so it can't go past it
+    next: Option<u128>,
+    last: u128,
+}
+
+impl AddrRange {
+    pub fn new(first: IpAddr, last: IpAddr) -> Result<AddrRange, IpError> {
+        if first.kind() != last.kind() {
+            return Err(IpError::MixedKinds);
+        }
+        if first > last {
+            return Err(IpError::Backwards);
+        }
+        Ok(AddrRange {
+            kind: first.kind(),
+            next: Some(first.to_bits()),
+            last: last.to_bits(),
+        })
+    }
+
+    /// Whether `addr` is one still to come.
+    pub fn contains(&self, addr: &IpAddr) -> bool {
+        addr.kind() == self.kind
+            && self
+                .next
+                .is_some_and(|next| (next..=self.last).contains(&addr.to_bits()))
+    }
+}
+
+impl FromStr for AddrRange {
+    type Err = IpError;
+
+    /// `10.0.0.1-10.0.0.20`.
+    fn from_str(text: &str) -> Result<AddrRange, IpError> {
+        match text.split_once('-') {
+            Some((first, last)) => AddrRange::new(first.parse()?, last.parse()?),
+            None => Err(IpError::InvalidAddress(text.to_string())),
+        }
+    }
+}
+
+impl Iterator for AddrRange {
+    type Item = IpAddr;
+
+    fn next(&mut self) -> Option<IpAddr> {
+        let next = self.next?;
+        self.next = if next == self.last {
+            None
+        } else {
+            Some(next + 1)
+        };
+        Some(IpAddr::from_bits(self.kind, next))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.next {
+            None => (0, Some(0)),
+            // a /64 has more addresses than usize can count
+            Some(next) => match usize::try_from(self.last - next)
+                .ok()
+                .and_then(|n| n.checked_add(1))
+            {
+                Some(n) => (n, Some(n)),
+                None => (usize::MAX, None),
+            },
+        }
+    }
+}
+
+impl DoubleEndedIterator for AddrRange {
+    fn next_back(&mut self) -> Option<IpAddr> {
+        let next = self.next?;
+        let last = self.last;
+        if last == next {
+            self.next = None;
+        } else {
+            self.last -= 1;
+        }
+        Some(IpAddr::from_bits(self.kind, last))
+    }
+}
+
+/// Every address written in `line`, in order. Catches `10.0.0.1:8080` and `[::1]:80` as
+/// addresses with a port.
+pub fn find_addresses(line: &str) -> Vec<IpAddr> {
+    line.split(|ch: char| !(ch.is_ascii_hexdigit() || ch == '.' || ch == ':'))
+        .filter(|token| token.len() >= 2)
+        .filter_map(|token| {
+            let token = token.trim_end_matches('.');
+            token.parse().ok().or_else(|| {
+                // a V4 address with a port stuck on the end
+                let (addr, _port) = token.rsplit_once(':')?;
+                addr.parse()
+                    .ok()
+                    .filter(|addr: &IpAddr| addr.kind() == IpAddrKind::V4)
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(text: &str) -> IpAddr {
+        text.parse().unwrap()
+    }
+
+    fn cidr(text: &str) -> Cidr {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_and_prints_both_kinds() {
+        assert_eq!(IpAddr::V4(127, 0, 0, 1), ip("127.0.0.1"));
+        assert_eq!(IpAddr::V6([0, 0, 0, 0, 0, 0, 0, 1]), ip("::1"));
+        assert_eq!("fe80::1:2", ip("FE80:0:0:0:0:0:1:2").to_string());
+        assert_eq!(IpAddrKind::V6, ip("::ffff:1.2.3.4").kind());
+        assert!(ip("::1").is_loopback());
+        for bad in &["256.0.0.1", "1.2.3", "1.2.3.4.5", "::1::2", "abc", ""] {
+            assert!(bad.parse::<IpAddr>().is_err(), "{}", bad);
+        }
+
+        let std: net::IpAddr = ip("10.1.2.3").into();
+        assert_eq!(net::IpAddr::V4(net::Ipv4Addr::new(10, 1, 2, 3)), std);
+        assert_eq!(
+            ip("2001:db8::8"),
+            IpAddr::from(net::Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 8))
+        );
+        assert_eq!(0x0a01_0203, ip("10.1.2.3").to_bits());
+    }
+
+    #[test]
+    fn cidr_blocks() {
+        let net = cidr("192.168.1.77/24");
+        assert_eq!("192.168.1.0/24", net.to_string());
+        assert_eq!(ip("255.255.255.0"), net.netmask());
+        assert_eq!(ip("192.168.1.255"), net.last());
+        assert_eq!(Some(256), net.size());
+        assert!(net.contains(&ip("192.168.1.200")));
+        assert!(!net.contains(&ip("192.168.2.1")));
+        assert!(!net.contains(&ip("::1")));
+
+        assert!(cidr("10.0.0.0/8").contains_cidr(&cidr("10.20.0.0/16")));
+        assert!(!cidr("10.20.0.0/16").contains_cidr(&cidr("10.0.0.0/8")));
+        assert!(cidr("0.0.0.0/0").contains(&ip("8.8.8.8")));
+        assert_eq!(Some(1), cidr("8.8.8.8").size());
+        assert_eq!(None, cidr("::/0").size());
+        assert!(cidr("fe80::/10").contains(&ip("fe80::1")));
+        assert_eq!(
+            ip("febf:ffff:ffff:ffff:ffff:ffff:ffff:ffff"),
+            cidr("fe80::/10").last()
+        );
+
+        assert_eq!(
+            Err(IpError::InvalidPrefix(String::from("33"))),
+            "1.2.3.4/33".parse::<Cidr>()
+        );
+        assert!("1.2.3.4/x".parse::<Cidr>().is_err());
+    }
+
+    #[test]
+    fn ranges_iterate_both_ways() {
+        let addrs: Vec<String> = cidr("10.0.0.252/30")
+            .iter()
+            .map(|a| a.to_string())
+            .collect();
+        assert_eq!(
+            vec!["10.0.0.252", "10.0.0.253", "10.0.0.254", "10.0.0.255"],
+            addrs
+        );
+
+        let mut range: AddrRange = "10.0.0.255-10.0.1.1".parse().unwrap();
+        assert_eq!((3, Some(3)), range.size_hint());
+        assert_eq!(Some(ip("10.0.1.1")), range.next_back());
+        assert_eq!(Some(ip("10.0.0.255")), range.next());
+        assert!(range.contains(&ip("10.0.1.0")));
+        assert_eq!(vec![ip("10.0.1.0")], range.collect::<Vec<_>>());
+
+        // the very top address ends the range rather than overflowing
+        let top = AddrRange::new(ip("255.255.255.254"), ip("255.255.255.255")).unwrap();
+        assert_eq!(2, top.count());
+        assert_eq!((usize::MAX, None), cidr("::/0").iter().size_hint());
+
+        assert_eq!(
+            Err(IpError::Backwards),
+            "10.0.0.2-10.0.0.1".parse::<AddrRange>()
+        );
+        assert_eq!(
+            Err(IpError::MixedKinds),
+            "10.0.0.1-::1".parse::<AddrRange>()
+        );
+    }
+
+    #[test]
+    fn finds_addresses_in_log_lines() {
+        assert_eq!(
+            vec![ip("10.0.0.1"), ip("::1"), ip("192.168.0.9")],
+            find_addresses("GET / from 10.0.0.1:8080 via [::1]:80 for 192.168.0.9.")
+        );
+        assert!(find_addresses("12:30:01 deadbeef cafe 1.2").is_empty());
+    }
+}
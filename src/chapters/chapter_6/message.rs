@@ -0,0 +1,560 @@
+// The Message enum from enums(), and again in chapters 15 and 18, as a protocol that can
+// actually go over a wire. Two ways to write one down:
+//
+// binary, a frame per message: a u32 big endian length, then that many bytes of payload. The
+// payload starts with a tag byte, 0 Quit, 1 Move, 2 Write, 3 ChangeColor, then the fields as
+// i32 big endian, or for Write the text as UTF-8 filling the rest of the payload.
+//
+// text, a line per message: `QUIT`, `MOVE 3 -4`, `WRITE hello there`, `COLOR 0 160 255`, with
+// any backslash, \n or \r in the text escaped so it stays on its line.
+//
+// Bytes off a socket turn up in whatever chunks they like, so Decoder buffers them and only hands
+// out a message once its whole frame is in.
+
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Frames claiming to be longer than this are refused rather than buffered.
+pub const MAX_FRAME: usize = 1024 * 1024;
+
+const QUIT: u8 = 0;
+const MOVE: u8 = 1;
+const WRITE: u8 = 2;
+const CHANGE_COLOR: u8 = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Message {
+    Quit,
+    Move { x: i32, y: i32 },
+    Write(String),
+    ChangeColor(i32, i32, i32),
+}
+
+/// What `Message::call` dispatches to, a method per variant. They all do nothing unless
+/// overridden, so a handler only writes the ones it cares about.
+pub trait Handler {
+    fn quit(&mut self) {}
+
+    fn move_to(&mut self, _x: i32, _y: i32) {}
+
+    fn write(&mut self, _text: &str) {}
+
+    fn change_color(&mut self, _r: i32, _g: i32, _b: i32) {}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// A frame with no payload, not even a tag.
+    Empty,
+    UnknownTag(u8),
+    /// The payload is the wrong size for its tag.
+    WrongLength {
+        tag: u8,
+        len: usize,
+    },
+    InvalidUtf8,
+    TooLong(usize),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::Empty => write!(f, "empty frame"),
+            DecodeError::UnknownTag(tag) => write!(f, "unknown message tag {}", tag),
+            DecodeError::WrongLength { tag, len } => {
+                write!(f, "{} bytes is the wrong length for tag {}", len, tag)
+            }
+            DecodeError::InvalidUtf8 => write!(f, "Write text isn't valid UTF-8"),
+            DecodeError::TooLong(len) => {
+                write!(f, "{} byte frame is over the {} limit", len, MAX_FRAME)
+            }
+        }
+    }
+}
+
+impl Error for DecodeError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodeError {
+    /// The payload would be over `MAX_FRAME`, which the other end refuses.
+    TooLong(usize),
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EncodeError::TooLong(len) => {
+                write!(f, "{} byte message is over the {} limit", len, MAX_FRAME)
+            }
+        }
+    }
+}
+
+impl Error for EncodeError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseMessageError {
+    UnknownCommand(String),
+    /// The command was fine, its arguments weren't.
+    BadArguments(String),
+    BadEscape(String),
+}
+
+impl fmt::Display for ParseMessageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseMessageError::UnknownCommand(line) => write!(f, "unknown command in {:?}", line),
+            ParseMessageError::BadArguments(line) => write!(f, "bad arguments in {:?}", line),
+            ParseMessageError::BadEscape(line) => write!(f, "bad escape in {:?}", line),
+        }
+    }
+}
+
+impl Error for ParseMessageError {}
+
+impl Message {
+    /// Hands the message's fields to the matching `handler` method.
+    pub fn call<H: Handler + ?Sized>(&self, handler: &mut H) {
+        match self {
+            Message::Quit => handler.quit(),
+            Message::Move { x, y } => handler.move_to(*x, *y),
+            Message::Write(text) => handler.write(text),
+            Message::ChangeColor(r, g, b) => handler.change_color(*r, *g, *b),
+        }
+    }
+
+    // tag byte included
+    fn payload_len(&self) -> usize {
+        1 + match self {
+            Message::Quit => 0,
+            Message::Move { .. } => 8,
+            Message::Write(text) => text.len(),
+            Message::ChangeColor(..) => 12,
+        }
+    }
+
+    /// The whole frame, length prefix included.
+    pub fn encode(&self) -> Result<Vec<u8>, EncodeError> {
+        let mut out = vec![];
+        self.encode_into(&mut out)?;
+        Ok(out)
+    }
+
+    /// Appends the frame to `out`, for writing several into one buffer. Only a Write can be too
+    /// long, and then `out` is left as it was.
+    pub fn encode_into(&self, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+        let len = self.payload_len();
+        if len > MAX_FRAME {
+            return Err(EncodeError::TooLong(len));
+        }
+        out.reserve(4 + len);
+        out.extend_from_slice(&(len as u32).to_be_bytes());
+        match self {
+            Message::Quit => out.push(QUIT),
+            Message::Move { x, y } => {
+                out.push(MOVE);
+                out.extend_from_slice(&x.to_be_bytes());
+                out.extend_from_slice(&y.to_be_bytes());
+            }
+            Message::Write(text) => {
+                out.push(WRITE);
+                out.extend_from_slice(text.as_bytes());
+            }
+            Message::ChangeColor(r, g, b) => {
+                out.push(CHANGE_COLOR);
+                for part in &[r, g, b] {
+                    out.extend_from_slice(&part.to_be_bytes());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// One payload, the bytes after the length prefix.
+    pub fn decode_payload(payload: &[u8]) -> Result<Message, DecodeError> {
+        let (&tag, fields) = payload.split_first().ok_or(DecodeError::Empty)?;
+        let wrong_length = DecodeError::WrongLength {
+            tag,
+            len: payload.len(),
+        };
+        let ints = |count: usize| -> Result<Vec<i32>, DecodeError> {
+            if fields.len() != count * 4 {
+                return Err(wrong_length.clone());
+            }
+            Ok(fields
+                .chunks_exact(4)
+                .map(|chunk| i32::from_be_bytes(<[u8; 4]>::try_from(chunk).unwrap()))
+                .collect())
+        };
+        match tag {
+            QUIT => ints(0).map(|_| Message::Quit),
+            MOVE => ints(2).map(|xy| Message::Move { x: xy[0], y: xy[1] }),
+            WRITE => String::from_utf8(fields.to_vec())
+                .map(Message::Write)
+                .map_err(|_| DecodeError::InvalidUtf8),
+            CHANGE_COLOR => ints(3).map(|rgb| Message::ChangeColor(rgb[0], rgb[1], rgb[2])),
+            tag => Err(DecodeError::UnknownTag(tag)),
+        }
+    }
+}
+
+impl fmt::Display for Message {
+    /// The text form, one line without the newline.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Message::Quit => write!(f, "QUIT"),
+            Message::Move { x, y } => write!(f, "MOVE {} {}", x, y),
+            Message::Write(text) => {
+                write!(f, "WRITE ")?;
+                for ch in text.chars() {
+                    match ch {
+                        '\\' => write!(f, "\\\\")?,
+                        '\n' => write!(f, "\\n")?,
+                        '\r' => write!(f, "\\r")?,
+                        ch => write!(f, "{}", ch)?,
+                    }
+                }
+                Ok(())
+            }
+            Message::ChangeColor(r, g, b) => write!(f, "COLOR {} {} {}", r, g, b),
+        }
+    }
+}
+
+fn unescape(text: &str, line: &str) -> Result<String, ParseMessageError> {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            out.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => out.push('\\'),
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            _ => return Err(ParseMessageError::BadEscape(line.to_string())),
+        }
+    }
+    Ok(out)
+}
+
+impl FromStr for Message {
+    type Err = ParseMessageError;
+
+    /// The command is case insensitive. Everything after `WRITE ` is the text, spaces and all.
+    fn from_str(line: &str) -> Result<Message, ParseMessageError> {
+        let line = line.trim_end_matches(['\r', '\n']);
+        let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let bad_arguments = || ParseMessageError::BadArguments(line.to_string());
+        let ints = |count: usize| -> Result<Vec<i32>, ParseMessageError> {
+            let ints = rest
+                .split_whitespace()
+                .map(|part| part.parse().map_err(|_| bad_arguments()))
+                .collect::<Result<Vec<i32>, _>>()?;
+            if ints.len() == count {
+                Ok(ints)
+            } else {
+                Err(bad_arguments())
+            }
+        };
+        match command.to_ascii_uppercase().as_str() {
+            "QUIT" => ints(0).map(|_| Message::Quit),
+            "MOVE" => ints(2).map(|xy| Message::Move { x: xy[0], y: xy[1] }),
+            "WRITE" => unescape(rest, line).map(Message::Write),
+            "COLOR" => ints(3).map(|rgb| Message::ChangeColor(rgb[0], rgb[1], rgb[2])),
+            _ => Err(ParseMessageError::UnknownCommand(line.to_string())),
+        }
+    }
+}
+
+/// Collects bytes from a stream and cuts them into messages.
+///
+/// ```ignore
+/// decoder.feed(&buf[..n]);
+/// while let Some(message) = decoder.decode()? {
+///     message.call(&mut handler);
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Decoder {
+    buffer: Vec<u8>,
+    // where the next frame starts. Decoded frames are only cut off the front on the next feed,
+    // so a buffer of many small frames isn't shifted down once per frame
+    read: usize,
+}
+
+impl Decoder {
+    pub fn new() -> Decoder {
+        Decoder::default()
+    }
+
+    pub fn feed(&mut self, bytes: &[u8]) {
+        self.buffer.drain(..self.read);
+        self.read = 0;
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// The next message, or `None` until the rest of its frame has been fed in.
+    ///
+    /// A bad frame is dropped before the error comes back, so decoding can carry on after it.
+    /// An over long one can't be skipped without reading it all, so after `TooLong` the stream
+    /// is best given up on.
+    pub fn decode(&mut self) -> Result<Option<Message>, DecodeError> {
+        let unread = &self.buffer[self.read..];
+        if unread.len() < 4 {
+            return Ok(None);
+        }
+        let len = u32::from_be_bytes(<[u8; 4]>::try_from(&unread[..4]).unwrap()) as usize;
+        if len > MAX_FRAME {
+            return Err(DecodeError::TooLong(len));
+        }
+        if unread.len() < 4 + len {
+            return Ok(None);
+        }
+        let message = Message::decode_payload(&unread[4..4 + len]);
+        self.read += 4 + len;
+        message.map(Some)
+    }
+
+    /// Bytes fed in that aren't part of a message yet.
+    pub fn buffered(&self) -> usize {
+        self.buffer.len() - self.read
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Screen {
+        position: (i32, i32),
+        lines: Vec<String>,
+        quit: bool,
+    }
+
+    impl Handler for Screen {
+        fn quit(&mut self) {
+            self.quit = true;
+        }
+
+        fn move_to(&mut self, x: i32, y: i32) {
+            self.position = (x, y);
+        }
+
+        fn write(&mut self, text: &str) {
+            self.lines.push(text.to_string());
+        }
+    }
+
+    // xorshift, enough randomness to throw lots of different messages at the codecs without
+    // pulling in a crate for it
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> u64 {
+            self.next() % n
+        }
+
+        fn int(&mut self) -> i32 {
+            // the edges are where bugs are, so they come up more than chance would give
+            match self.below(4) {
+                0 => [0, -1, i32::MIN, i32::MAX][self.below(4) as usize],
+                _ => self.next() as i32,
+            }
+        }
+
+        fn text(&mut self) -> String {
+            let pool = ['a', 'Z', ' ', '\\', '\n', '\r', 'é', '€', '🦀', 'n', '0'];
+            (0..self.below(12))
+                .map(|_| pool[self.below(pool.len() as u64) as usize])
+                .collect()
+        }
+
+        fn message(&mut self) -> Message {
+            match self.below(4) {
+                0 => Message::Quit,
+                1 => Message::Move {
+                    x: self.int(),
+                    y: self.int(),
+                },
+                2 => Message::Write(self.text()),
+                _ => Message::ChangeColor(self.int(), self.int(), self.int()),
+            }
+        }
+    }
+
+    #[test]
+    fn binary_layout() {
+        assert_eq!(vec![0, 0, 0, 1, 0], Message::Quit.encode().unwrap());
+        assert_eq!(
+            vec![0, 0, 0, 9, 1, 0, 0, 0, 3, 0xff, 0xff, 0xff, 0xfc],
+            Message::Move { x: 3, y: -4 }.encode().unwrap()
+        );
+        assert_eq!(
+            vec![0, 0, 0, 3, 2, b'h', b'i'],
+            Message::Write(String::from("hi")).encode().unwrap()
+        );
+
+        // the tag byte counts, so the longest text is one short of MAX_FRAME
+        let longest = Message::Write("x".repeat(MAX_FRAME - 1));
+        assert_eq!(4 + MAX_FRAME, longest.encode().unwrap().len());
+        let mut out = vec![7];
+        assert_eq!(
+            Err(EncodeError::TooLong(MAX_FRAME + 1)),
+            Message::Write("x".repeat(MAX_FRAME)).encode_into(&mut out)
+        );
+        assert_eq!(vec![7], out);
+
+        assert_eq!(Err(DecodeError::Empty), Message::decode_payload(&[]));
+        assert_eq!(
+            Err(DecodeError::UnknownTag(9)),
+            Message::decode_payload(&[9])
+        );
+        assert_eq!(
+            Err(DecodeError::WrongLength { tag: 1, len: 5 }),
+            Message::decode_payload(&[1, 0, 0, 0, 3])
+        );
+        assert_eq!(
+            Err(DecodeError::InvalidUtf8),
+            Message::decode_payload(&[2, 0xff])
+        );
+    }
+
+    #[test]
+    fn text_form() {
+        let cases = vec![
+            (Message::Quit, "QUIT"),
+            (Message::Move { x: 3, y: -4 }, "MOVE 3 -4"),
+            (Message::ChangeColor(0, 160, 255), "COLOR 0 160 255"),
+            (
+                Message::Write(String::from(" two\nlines \\ ")),
+                "WRITE  two\\nlines \\\\ ",
+            ),
+            (Message::Write(String::new()), "WRITE "),
+        ];
+        for (message, text) in cases {
+            assert_eq!(text, message.to_string());
+            assert_eq!(Ok(message), text.parse());
+        }
+
+        assert_eq!(Ok(Message::Quit), "quit\r\n".parse());
+        assert_eq!(Ok(Message::Write(String::new())), "WRITE".parse());
+        assert!(matches!(
+            "JUMP 1".parse::<Message>(),
+            Err(ParseMessageError::UnknownCommand(_))
+        ));
+        for bad in &["MOVE 1", "MOVE 1 2 3", "COLOR 1 2 x", "QUIT now"] {
+            assert!(
+                matches!(
+                    bad.parse::<Message>(),
+                    Err(ParseMessageError::BadArguments(_))
+                ),
+                "{}",
+                bad
+            );
+        }
+        assert!(matches!(
+            "WRITE tab\\t".parse::<Message>(),
+            Err(ParseMessageError::BadEscape(_))
+        ));
+    }
+
+    #[test]
+    fn decoder_waits_for_whole_frames() {
+        let mut stream = Message::Move { x: 1, y: 2 }.encode().unwrap();
+        Message::Write(String::from("hello"))
+            .encode_into(&mut stream)
+            .unwrap();
+        Message::Quit.encode_into(&mut stream).unwrap();
+
+        let mut decoder = Decoder::new();
+        let mut screen = Screen::default();
+        // a byte at a time, the worst a stream can do
+        for byte in stream {
+            decoder.feed(&[byte]);
+            while let Some(message) = decoder.decode().unwrap() {
+                message.call(&mut screen);
+            }
+        }
+        assert_eq!(0, decoder.buffered());
+        assert_eq!((1, 2), screen.position);
+        assert_eq!(vec!["hello"], screen.lines);
+        assert!(screen.quit);
+
+        // a bad frame is skipped over, the next one still decodes
+        decoder.feed(&[0, 0, 0, 1, 7]);
+        decoder.feed(&Message::Quit.encode().unwrap());
+        assert_eq!(Err(DecodeError::UnknownTag(7)), decoder.decode());
+        assert_eq!(Ok(Some(Message::Quit)), decoder.decode());
+
+        decoder.feed(&[0xff, 0xff, 0xff, 0xff]);
+        assert_eq!(
+            Err(DecodeError::TooLong(u32::MAX as usize)),
+            decoder.decode()
+        );
+
+        // lots of small frames in one feed come out one at a time without shifting the rest
+        let mut decoder = Decoder::new();
+        let mut stream = vec![];
+        for _ in 0..100_000 {
+            Message::Quit.encode_into(&mut stream).unwrap();
+        }
+        decoder.feed(&stream);
+        let mut count = 0;
+        while let Some(message) = decoder.decode().unwrap() {
+            assert_eq!(Message::Quit, message);
+            count += 1;
+        }
+        assert_eq!(100_000, count);
+        assert_eq!(0, decoder.buffered());
+        // and the space they took is reclaimed on the next feed
+        decoder.feed(&[0, 0]);
+        assert_eq!(2, decoder.buffer.len());
+    }
+
+    #[test]
+    fn random_messages_round_trip() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        let messages: Vec<Message> = (0..2000).map(|_| rng.message()).collect();
+
+        let mut stream = vec![];
+        for message in &messages {
+            assert_eq!(Ok(message.clone()), message.to_string().parse());
+            assert!(!message.to_string().contains(['\n', '\r']));
+            message.encode_into(&mut stream).unwrap();
+        }
+
+        // fed in random sized chunks, frames split wherever they land
+        let mut decoder = Decoder::new();
+        let mut decoded = vec![];
+        let mut rest = &stream[..];
+        while !rest.is_empty() {
+            let (chunk, after) = rest.split_at((rng.below(20) as usize).min(rest.len()));
+            decoder.feed(chunk);
+            rest = after;
+            while let Some(message) = decoder.decode().unwrap() {
+                decoded.push(message);
+            }
+        }
+        assert_eq!(messages, decoded);
+
+        // and random garbage never panics, it only errors
+        for _ in 0..2000 {
+            let payload: Vec<u8> = (0..rng.below(16)).map(|_| rng.next() as u8).collect();
+            let _ = Message::decode_payload(&payload);
+            let text: String = rng.text();
+            let _ = text.parse::<Message>();
+        }
+    }
+}
@@ -0,0 +1,665 @@
+// Coin, CoinBindValExample and UsState from enum_and_flow_control grown up. Amounts are whole
+// cents in an i64, so there is no rounding, and every operation checks for overflow instead of
+// wrapping the way value_in_cents' u8 would past $2.55.
+
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, Mul, Neg, Sub};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Money {
+    cents: i64,
+}
+
+impl Money {
+    pub const ZERO: Money = Money { cents: 0 };
+
+    pub const fn from_cents(cents: i64) -> Money {
+        Money { cents }
+    }
+
+    /// `None` if it doesn't fit.
+    pub fn from_dollars(dollars: i64) -> Option<Money> {
+        dollars.checked_mul(100).map(Money::from_cents)
+    }
+
+    pub const fn cents(self) -> i64 {
+        self.cents
+    }
+
+    /// Whole dollars, rounded toward zero like integer division.
+    pub const fn dollars(self) -> i64 {
+        self.cents / 100
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.cents < 0
+    }
+
+    pub fn checked_add(self, other: Money) -> Option<Money> {
+        self.cents.checked_add(other.cents).map(Money::from_cents)
+    }
+
+    pub fn checked_sub(self, other: Money) -> Option<Money> {
+        self.cents.checked_sub(other.cents).map(Money::from_cents)
+    }
+
+    pub fn checked_mul(self, times: i64) -> Option<Money> {
+        self.cents.checked_mul(times).map(Money::from_cents)
+    }
+
+    pub fn checked_neg(self) -> Option<Money> {
+        self.cents.checked_neg().map(Money::from_cents)
+    }
+
+    /// Splits into `parts` amounts as even as possible that still add back up exactly, the
+    /// leftover cents going to the first few. $10 in 3 is $3.34, $3.33, $3.33.
+    pub fn split(self, parts: usize) -> Vec<Money> {
+        if parts == 0 {
+            return vec![];
+        }
+        let parts_i64 = parts as i64;
+        let (share, leftover) = (self.cents / parts_i64, self.cents % parts_i64);
+        (0..parts_i64)
+            .map(|i| {
+                let extra = if i < leftover.abs() {
+                    leftover.signum()
+                } else {
+                    0
+                };
+                Money::from_cents(share + extra)
+            })
+            .collect()
+    }
+}
+
+// the operators panic on overflow in release builds too, money shouldn't wrap
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, other: Money) -> Money {
+        self.checked_add(other).expect("money overflowed")
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, other: Money) -> Money {
+        self.checked_sub(other).expect("money overflowed")
+    }
+}
+
+impl Mul<i64> for Money {
+    type Output = Money;
+
+    fn mul(self, times: i64) -> Money {
+        self.checked_mul(times).expect("money overflowed")
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+
+    fn neg(self) -> Money {
+        self.checked_neg().expect("money overflowed")
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Money> for Money {
+    fn sum<I: Iterator<Item = &'a Money>>(iter: I) -> Money {
+        iter.copied().sum()
+    }
+}
+
+impl fmt::Display for Money {
+    /// `$3.25`, `-$0.05`, `$1,234.00`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.cents < 0 { "-" } else { "" };
+        // unsigned so i64::MIN still has a magnitude
+        let cents = self.cents.unsigned_abs();
+        let digits = (cents / 100).to_string();
+        let mut dollars = String::new();
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                dollars.push(',');
+            }
+            dollars.push(digit);
+        }
+        write!(f, "{}${}.{:02}", sign, dollars, cents % 100)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseMoneyError {
+    Empty,
+    /// Not a number, with the text that wasn't understood.
+    Invalid(String),
+    /// More than two digits after the point, money doesn't have fractions of a cent.
+    TooPrecise,
+    Overflow,
+}
+
+impl fmt::Display for ParseMoneyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseMoneyError::Empty => write!(f, "no amount given"),
+            ParseMoneyError::Invalid(text) => write!(f, "{:?} isn't an amount of money", text),
+            ParseMoneyError::TooPrecise => write!(f, "amounts can't go below a cent"),
+            ParseMoneyError::Overflow => write!(f, "amount is too large"),
+        }
+    }
+}
+
+impl Error for ParseMoneyError {}
+
+impl FromStr for Money {
+    type Err = ParseMoneyError;
+
+    /// `$3.25`, `3.25`, `-$1,000`, `$.50`, `3.5` (three fifty) or `75¢`/`75c` for cents.
+    fn from_str(text: &str) -> Result<Money, ParseMoneyError> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(ParseMoneyError::Empty);
+        }
+        let invalid = || ParseMoneyError::Invalid(text.to_string());
+        let (negative, rest) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text),
+        };
+
+        let cents = if let Some(amount) = rest.strip_suffix(['¢', 'c']) {
+            if amount.is_empty() || !amount.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(invalid());
+            }
+            amount
+                .parse::<i64>()
+                .map_err(|_| ParseMoneyError::Overflow)?
+        } else {
+            let amount = rest.strip_prefix('$').unwrap_or(rest);
+            let (whole, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+            let digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+            // commas only in the dollars, between groups of three: "1,000" but not "1,00"
+            if whole.contains(',') {
+                let mut groups = whole.split(',');
+                let first = groups.next().unwrap_or("");
+                let grouped = (1..=3).contains(&first.len())
+                    && groups.all(|group| group.len() == 3 && digits(group));
+                if !grouped {
+                    return Err(invalid());
+                }
+            }
+            let whole = whole.replace(',', "");
+            let whole = whole.as_str();
+            if (whole.is_empty() && fraction.is_empty()) || !digits(whole) || !digits(fraction) {
+                return Err(invalid());
+            }
+            if fraction.len() > 2 {
+                return Err(ParseMoneyError::TooPrecise);
+            }
+            let dollars = if whole.is_empty() {
+                0
+            } else {
+                whole
+                    .parse::<i64>()
+                    .map_err(|_| ParseMoneyError::Overflow)?
+            };
+            // "5" after the point is 50 cents
+            let fraction = format!("{:0<2}", fraction).parse::<i64>().unwrap();
+            dollars
+                .checked_mul(100)
+                .and_then(|cents| cents.checked_add(fraction))
+                .ok_or(ParseMoneyError::Overflow)?
+        };
+        Ok(Money::from_cents(if negative { -cents } else { cents }))
+    }
+}
+
+macro_rules! us_states {
+    ($($state:ident => $name:expr, $abbreviation:expr;)*) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub enum UsState {
+            $($state,)*
+        }
+
+        impl UsState {
+            /// All 50, alphabetically.
+            pub const ALL: [UsState; 50] = [$(UsState::$state,)*];
+
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(UsState::$state => $name,)*
+                }
+            }
+
+            /// The postal code, "AK" for Alaska.
+            pub fn abbreviation(self) -> &'static str {
+                match self {
+                    $(UsState::$state => $abbreviation,)*
+                }
+            }
+        }
+    };
+}
+
+us_states! {
+    Alabama => "Alabama", "AL";
+    Alaska => "Alaska", "AK";
+    Arizona => "Arizona", "AZ";
+    Arkansas => "Arkansas", "AR";
+    California => "California", "CA";
+    Colorado => "Colorado", "CO";
+    Connecticut => "Connecticut", "CT";
+    Delaware => "Delaware", "DE";
+    Florida => "Florida", "FL";
+    Georgia => "Georgia", "GA";
+    Hawaii => "Hawaii", "HI";
+    Idaho => "Idaho", "ID";
+    Illinois => "Illinois", "IL";
+    Indiana => "Indiana", "IN";
+    Iowa => "Iowa", "IA";
+    Kansas => "Kansas", "KS";
+    Kentucky => "Kentucky", "KY";
+    Louisiana => "Louisiana", "LA";
+    Maine => "Maine", "ME";
+    Maryland => "Maryland", "MD";
+    Massachusetts => "Massachusetts", "MA";
+    Michigan => "Michigan", "MI";
+    Minnesota => "Minnesota", "MN";
+    Mississippi => "Mississippi", "MS";
+    Missouri => "Missouri", "MO";
+    Montana => "Montana", "MT";
+    Nebraska => "Nebraska", "NE";
+    Nevada => "Nevada", "NV";
+    NewHampshire => "New Hampshire", "NH";
+    NewJersey => "New Jersey", "NJ";
+    NewMexico => "New Mexico", "NM";
+    NewYork => "New York", "NY";
+    NorthCarolina => "North Carolina", "NC";
+    NorthDakota => "North Dakota", "ND";
+    Ohio => "Ohio", "OH";
+    Oklahoma => "Oklahoma", "OK";
+    Oregon => "Oregon", "OR";
+    Pennsylvania => "Pennsylvania", "PA";
+    RhodeIsland => "Rhode Island", "RI";
+    SouthCarolina => "South Carolina", "SC";
+    SouthDakota => "South Dakota", "SD";
+    Tennessee => "Tennessee", "TN";
+    Texas => "Texas", "TX";
+    Utah => "Utah", "UT";
+    Vermont => "Vermont", "VT";
+    Virginia => "Virginia", "VA";
+    Washington => "Washington", "WA";
+    WestVirginia => "West Virginia", "WV";
+    Wisconsin => "Wisconsin", "WI";
+    Wyoming => "Wyoming", "WY";
+}
+
+impl fmt::Display for UsState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for UsState {
+    type Err = String;
+
+    /// The name or the postal code, any case.
+    fn from_str(text: &str) -> Result<UsState, String> {
+        let text = text.trim();
+        UsState::ALL
+            .iter()
+            .copied()
+            .find(|state| {
+                state.name().eq_ignore_ascii_case(text)
+                    || state.abbreviation().eq_ignore_ascii_case(text)
+            })
+            .ok_or_else(|| format!("{:?} isn't a US state", text))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Coin {
+    Penny,
+    Nickel,
+    Dime,
+    /// A state quarter, the book's Quarter(UsState).
+    Quarter(UsState),
+    HalfDollar,
+    Dollar,
+}
+
+impl Coin {
+    pub fn value(self) -> Money {
+        Money::from_cents(match self {
+            Coin::Penny => 1,
+            Coin::Nickel => 5,
+            Coin::Dime => 10,
+            Coin::Quarter(_) => 25,
+            Coin::HalfDollar => 50,
+            Coin::Dollar => 100,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Bill {
+    One,
+    Two,
+    Five,
+    Ten,
+    Twenty,
+    Fifty,
+    Hundred,
+}
+
+impl Bill {
+    pub const ALL: [Bill; 7] = [
+        Bill::One,
+        Bill::Two,
+        Bill::Five,
+        Bill::Ten,
+        Bill::Twenty,
+        Bill::Fifty,
+        Bill::Hundred,
+    ];
+
+    pub fn value(self) -> Money {
+        let dollars = match self {
+            Bill::One => 1,
+            Bill::Two => 2,
+            Bill::Five => 5,
+            Bill::Ten => 10,
+            Bill::Twenty => 20,
+            Bill::Fifty => 50,
+            Bill::Hundred => 100,
+        };
+        Money::from_cents(dollars * 100)
+    }
+}
+
+/// What a till usually holds: every bill but the $2, and coins up to the quarter.
+pub fn us_denominations() -> Vec<Money> {
+    let mut denominations: Vec<Money> = Bill::ALL
+        .iter()
+        .filter(|&&bill| bill != Bill::Two)
+        .map(|bill| bill.value())
+        .collect();
+    denominations.extend(
+        [
+            Coin::Quarter(UsState::Alabama),
+            Coin::Dime,
+            Coin::Nickel,
+            Coin::Penny,
+        ]
+        .iter()
+        .map(|coin| coin.value()),
+    );
+    denominations
+}
+
+/// How many of each denomination, largest first, leaving out the ones not used.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Change {
+    pub pieces: Vec<(Money, u64)>,
+}
+
+impl Change {
+    /// Coins and bills handed over.
+    pub fn count(&self) -> u64 {
+        self.pieces.iter().map(|(_, n)| n).sum()
+    }
+
+    pub fn total(&self) -> Money {
+        self.pieces.iter().map(|&(value, n)| value * n as i64).sum()
+    }
+}
+
+fn usable(denominations: &[Money]) -> Vec<i64> {
+    let mut cents: Vec<i64> = denominations
+        .iter()
+        .map(|d| d.cents())
+        .filter(|&c| c > 0)
+        .collect();
+    cents.sort_unstable_by(|a, b| b.cmp(a));
+    cents.dedup();
+    cents
+}
+
+/// Takes as many of the largest denomination as fit, then the next, like a cashier does. Best
+/// possible for US money but not for every set: with 4, 3 and 1 six comes out 4+1+1 rather than
+/// 3+3, and with only 5 and 3 it gets stuck on nine (5+3 leaves 1) when 3+3+3 works. `None` when
+/// it gets stuck or the amount is negative.
+pub fn greedy_change(amount: Money, denominations: &[Money]) -> Option<Change> {
+    if amount.is_negative() {
+        return None;
+    }
+    let mut left = amount.cents();
+    let mut pieces = vec![];
+    for value in usable(denominations) {
+        let n = left / value;
+        if n > 0 {
+            pieces.push((Money::from_cents(value), n as u64));
+            left -= n * value;
+        }
+    }
+    if left == 0 {
+        Some(Change { pieces })
+    } else {
+        None
+    }
+}
+
+/// The most steps `optimal_change` will take, its table has one entry per step.
+pub const OPTIMAL_CHANGE_LIMIT: usize = 10_000_000;
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// The fewest pieces that make `amount` exactly, for any set of denominations. Dynamic
+/// programming over every amount up to this one, in steps of the largest unit every
+/// denomination is a multiple of (a cent with pennies, five cents without). That's a table
+/// entry per step, so past `OPTIMAL_CHANGE_LIMIT` steps, about $100,000 in cents, it gives up
+/// with `None` rather than run out of memory. Also `None` if it can't be done.
+pub fn optimal_change(amount: Money, denominations: &[Money]) -> Option<Change> {
+    if amount.is_negative() {
+        return None;
+    }
+    let cents = usable(denominations);
+    let unit = cents.iter().fold(0, |unit, &value| gcd(value, unit));
+    if amount.cents() == 0 {
+        return Some(Change::default());
+    }
+    if unit == 0 || amount.cents() % unit != 0 {
+        return None;
+    }
+    // everything counted in units from here
+    let target = usize::try_from(amount.cents() / unit).ok()?;
+    if target > OPTIMAL_CHANGE_LIMIT {
+        return None;
+    }
+    let values: Vec<usize> = cents.iter().map(|&value| (value / unit) as usize).collect();
+    // fewest[a] is the fewest pieces that make a units, last[a] the value used to get there
+    let mut fewest = vec![u64::MAX; target + 1];
+    let mut last = vec![0usize; target + 1];
+    fewest[0] = 0;
+    for a in 1..=target {
+        for &value in &values {
+            if value <= a && fewest[a - value] != u64::MAX && fewest[a - value] + 1 < fewest[a] {
+                fewest[a] = fewest[a - value] + 1;
+                last[a] = value;
+            }
+        }
+    }
+    if fewest[target] == u64::MAX {
+        return None;
+    }
+
+    let mut counts = vec![0u64; values.len()];
+    let mut a = target;
+    while a > 0 {
+        let i = values.iter().position(|&v| v == last[a]).unwrap();
+        counts[i] += 1;
+        a -= last[a];
+    }
+    let pieces = cents
+        .into_iter()
+        .zip(counts)
+        .filter(|&(_, n)| n > 0)
+        .map(|(value, n)| (Money::from_cents(value), n))
+        .collect();
+    Some(Change { pieces })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn money(text: &str) -> Money {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_and_prints() {
+        assert_eq!(325, money("$3.25").cents());
+        assert_eq!(350, money("3.5").cents());
+        assert_eq!(50, money("$.50").cents());
+        assert_eq!(75, money("75¢").cents());
+        assert_eq!(-100_000, money("-$1,000").cents());
+        assert_eq!("$1,234,567.08", money(" 1234567.08 ").to_string());
+        assert_eq!("-$0.05", Money::from_cents(-5).to_string());
+        assert_eq!(
+            "-$92,233,720,368,547,758.08",
+            Money::from_cents(i64::MIN).to_string()
+        );
+
+        assert_eq!(Err(ParseMoneyError::Empty), "  ".parse::<Money>());
+        assert_eq!(Err(ParseMoneyError::TooPrecise), "$1.005".parse::<Money>());
+        assert_eq!(
+            Err(ParseMoneyError::Overflow),
+            "$99999999999999999999".parse::<Money>()
+        );
+        assert_eq!(123_456_700, money("$1,234,567").cents());
+        for bad in &[
+            "$",
+            "abc",
+            "$1.2.3",
+            "1e5",
+            "$-3",
+            "c",
+            "1,2.3,4",
+            "$1.2,5",
+            "$1.25,",
+            "$1,00",
+            "$1000,000",
+            ",100",
+            "$1,,000",
+            "$1,000,",
+        ] {
+            assert!(
+                matches!(bad.parse::<Money>(), Err(ParseMoneyError::Invalid(_))),
+                "{}",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn arithmetic_checks_overflow() {
+        let a = money("$2.55");
+        let b = Coin::Quarter(UsState::Ohio).value();
+        assert_eq!(money("$2.80"), a + b);
+        assert_eq!(money("$2.30"), a - b);
+        assert_eq!(money("$25.50"), a * 10);
+        assert_eq!(money("-$2.55"), -a);
+        assert_eq!(money("$3.05"), [a, b, b].iter().sum());
+
+        let max = Money::from_cents(i64::MAX);
+        assert_eq!(None, max.checked_add(Money::from_cents(1)));
+        assert_eq!(None, max.checked_mul(2));
+        assert_eq!(None, Money::from_cents(i64::MIN).checked_neg());
+        assert_eq!(None, Money::from_dollars(i64::MAX / 10));
+        assert!(std::panic::catch_unwind(|| max + max).is_err());
+    }
+
+    #[test]
+    fn splits_exactly() {
+        let parts = money("$10").split(3);
+        assert_eq!(vec![money("3.34"), money("3.33"), money("3.33")], parts);
+        assert_eq!(money("$10"), parts.iter().sum());
+        let parts = money("-$0.05").split(2);
+        assert_eq!(vec![money("-0.03"), money("-0.02")], parts);
+        assert!(money("$1").split(0).is_empty());
+    }
+
+    #[test]
+    fn makes_change() {
+        let us = us_denominations();
+        let change = greedy_change(money("$68.41"), &us).unwrap();
+        assert_eq!(
+            vec![
+                (money("$50"), 1),
+                (money("$10"), 1),
+                (money("$5"), 1),
+                (money("$1"), 3),
+                (money("$.25"), 1),
+                (money("$.10"), 1),
+                (money("$.05"), 1),
+                (money("$.01"), 1),
+            ],
+            change.pieces
+        );
+        assert_eq!(money("$68.41"), change.total());
+        assert_eq!(optimal_change(money("$68.41"), &us), Some(change));
+
+        // where being greedy isn't best
+        let odd = [money("4c"), money("3c"), money("1c")];
+        assert_eq!(3, greedy_change(money("6c"), &odd).unwrap().count());
+        let best = optimal_change(money("6c"), &odd).unwrap();
+        assert_eq!(vec![(money("3c"), 2)], best.pieces);
+
+        // and where it gets stuck
+        let no_pennies = [money("5c"), money("3c")];
+        assert_eq!(None, greedy_change(money("9c"), &no_pennies));
+        assert_eq!(3, optimal_change(money("9c"), &no_pennies).unwrap().count());
+        assert_eq!(None, optimal_change(money("7c"), &no_pennies));
+        assert_eq!(Some(Change::default()), optimal_change(Money::ZERO, &odd));
+
+        // in $20 steps ten million dollars is half a million steps, though it's a billion cents
+        let big_notes = [money("$100"), money("$20")];
+        let millions = optimal_change(money("$10,000,000"), &big_notes).unwrap();
+        assert_eq!(vec![(money("$100"), 100_000)], millions.pieces);
+        assert_eq!(None, optimal_change(money("$10,000,010"), &big_notes));
+        // a billion dollars is over the limit, None rather than a table that can't be allocated
+        assert_eq!(None, optimal_change(money("$1,000,000,000"), &us));
+        let just_over = Money::from_cents(OPTIMAL_CHANGE_LIMIT as i64 + 1);
+        assert_eq!(None, optimal_change(just_over, &us));
+        assert_eq!(None, optimal_change(money("$1.00"), &[]));
+    }
+
+    #[test]
+    fn all_fifty_states() {
+        let mut abbreviations: Vec<&str> = UsState::ALL.iter().map(|s| s.abbreviation()).collect();
+        abbreviations.sort_unstable();
+        abbreviations.dedup();
+        assert_eq!(50, abbreviations.len());
+        assert_eq!(Ok(UsState::NewYork), "new york".parse());
+        assert_eq!(Ok(UsState::Alaska), "AK".parse());
+        assert!("Puerto Rico".parse::<UsState>().is_err());
+        assert_eq!("West Virginia", UsState::WestVirginia.to_string());
+    }
+}
@@ -1,3 +1,11 @@
+// 8.3 follow ups on the HashMap examples below, a generic Counter and a league table
+pub mod counter;
+pub mod league;
+// the 8.3 exercise, "Add Sally to Engineering", as a REPL
+pub mod directory;
+// 8.2 follow up: string handling that survives non ASCII text, Pig Latin from the exercises too
+pub mod text;
+
 use std::collections::HashMap;
 
 pub fn working_with_vectors() {
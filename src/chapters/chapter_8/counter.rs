@@ -0,0 +1,179 @@
+// The entry().or_insert(0) word count from the book, `*map.entry(word).or_insert(0) += 1`, as a
+// type that works for any key, with the questions usually asked of a count answered: how many
+// of this, which are the most common, how many altogether.
+
+use std::borrow::Borrow;
+use std::collections::hash_map::{self, HashMap};
+use std::hash::Hash;
+use std::iter::FromIterator;
+use std::ops::Index;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Counter<K: Eq + Hash> {
+    counts: HashMap<K, usize>,
+}
+
+impl<K: Eq + Hash> Default for Counter<K> {
+    fn default() -> Self {
+        Counter {
+            counts: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Eq + Hash> Counter<K> {
+    pub fn new() -> Counter<K> {
+        Counter::default()
+    }
+
+    /// Counts `key` once more, returning its new count.
+    pub fn insert(&mut self, key: K) -> usize {
+        self.insert_n(key, 1)
+    }
+
+    pub fn insert_n(&mut self, key: K, n: usize) -> usize {
+        let count = self.counts.entry(key).or_insert(0);
+        *count += n;
+        *count
+    }
+
+    /// 0 for a key never counted.
+    pub fn get<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.counts.get(key).copied().unwrap_or(0)
+    }
+
+    /// Takes the key out altogether, returning what its count was.
+    pub fn remove<Q>(&mut self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.counts.remove(key).unwrap_or(0)
+    }
+
+    /// Distinct keys.
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Every count added up.
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    /// Keys and counts in no particular order.
+    pub fn iter(&self) -> hash_map::Iter<'_, K, usize> {
+        self.counts.iter()
+    }
+
+    /// Adds `other`'s counts into this one.
+    pub fn merge(&mut self, other: Counter<K>) {
+        for (key, n) in other.counts {
+            self.insert_n(key, n);
+        }
+    }
+
+    /// The `n` biggest counts, biggest first. Equal counts come out in key order so the result is
+    /// the same every run, whatever order the HashMap keeps them in.
+    pub fn most_common(&self, n: usize) -> Vec<(&K, usize)>
+    where
+        K: Ord,
+    {
+        let mut counts: Vec<(&K, usize)> = self.counts.iter().map(|(k, n)| (k, *n)).collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        counts.truncate(n);
+        counts
+    }
+}
+
+impl<K, Q> Index<&Q> for Counter<K>
+where
+    K: Eq + Hash + Borrow<Q>,
+    Q: Eq + Hash + ?Sized,
+{
+    type Output = usize;
+
+    /// `counter["word"]`, 0 rather than a panic for a missing key.
+    fn index(&self, key: &Q) -> &usize {
+        self.counts.get(key).unwrap_or(&0)
+    }
+}
+
+impl<K: Eq + Hash> FromIterator<K> for Counter<K> {
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        let mut counter = Counter::new();
+        counter.extend(iter);
+        counter
+    }
+}
+
+impl<K: Eq + Hash> Extend<K> for Counter<K> {
+    fn extend<I: IntoIterator<Item = K>>(&mut self, iter: I) {
+        for key in iter {
+            self.insert(key);
+        }
+    }
+}
+
+impl<K: Eq + Hash> IntoIterator for Counter<K> {
+    type Item = (K, usize);
+    type IntoIter = hash_map::IntoIter<K, usize>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.counts.into_iter()
+    }
+}
+
+/// The book's word count, except "Hello," and "hello" are the same word. Words are split on
+/// whitespace, lowercased and lose any punctuation either side, so "don't" keeps its '.
+pub fn word_frequencies(text: &str) -> Counter<String> {
+    text.split_whitespace()
+        .map(|word| word.trim_matches(|ch: char| !ch.is_alphanumeric()))
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_anything_hashable() {
+        let mut counter: Counter<char> = "mississippi".chars().collect();
+        assert_eq!(4, counter.get(&'s'));
+        assert_eq!(0, counter[&'z']);
+        assert_eq!(4, counter.len());
+        assert_eq!(11, counter.total());
+        assert_eq!(vec![(&'i', 4), (&'s', 4)], counter.most_common(2));
+
+        assert_eq!(2, counter.insert('m'));
+        assert_eq!(4, counter.remove(&'i'));
+        assert_eq!(0, counter.remove(&'i'));
+        counter.merge("mom".chars().collect());
+        assert_eq!(4, counter[&'m']);
+        assert_eq!(1, counter[&'o']);
+    }
+
+    #[test]
+    fn words() {
+        let counts = word_frequencies("Hello world, wonderful world! HELLO? don't -- 'world'");
+        assert_eq!(3, counts["world"]);
+        assert_eq!(2, counts["hello"]);
+        assert_eq!(1, counts["don't"]);
+        assert_eq!(4, counts.len());
+        assert_eq!(
+            vec![(&String::from("world"), 3), (&String::from("hello"), 2)],
+            counts.most_common(2)
+        );
+        assert!(word_frequencies(" -- ").is_empty());
+    }
+}
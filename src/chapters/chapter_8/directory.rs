@@ -0,0 +1,685 @@
+// The chapter 8 exercise: a text interface for adding employees to departments, "Add Sally to
+// Engineering", then listing everyone in a department or the whole company by department, sorted
+// alphabetically. On top of the book's version people can be removed and renamed, departments
+// renamed, and the directory is saved to a file after every change.
+//
+// The REPL reads from any BufRead and writes to any Write, so a test can script a session with a
+// string and check what came back. employee_directory() hooks it up to stdin and stdout.
+//
+//     > Add Sally to Engineering
+//     Added Sally to Engineering
+//     > List
+//     Engineering
+//       Sally
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+pub const HELP: &str = "\
+Add <name> to <department>
+Remove <name> from <department>
+Remove <name>                       from every department
+Rename <name> to <new name>
+Rename department <name> to <new name>
+List                                everyone, by department
+List <department>
+Help
+Quit";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Add {
+        employee: String,
+        department: String,
+    },
+    /// No department means every department they're in.
+    Remove {
+        employee: String,
+        department: Option<String>,
+    },
+    RenameEmployee {
+        from: String,
+        to: String,
+    },
+    RenameDepartment {
+        from: String,
+        to: String,
+    },
+    /// No department means the whole company.
+    List(Option<String>),
+    Help,
+    Quit,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseCommandError {
+    Empty,
+    Unknown(String),
+    /// Right command, wrong shape, with how it should look.
+    Usage(&'static str),
+}
+
+impl fmt::Display for ParseCommandError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseCommandError::Empty => write!(f, "no command given"),
+            ParseCommandError::Unknown(command) => {
+                write!(f, "unknown command {:?}, try Help", command)
+            }
+            ParseCommandError::Usage(usage) => write!(f, "usage: {}", usage),
+        }
+    }
+}
+
+impl Error for ParseCommandError {}
+
+// "Sally Smith to Human Resources" split around the first `keyword`, both sides non empty
+fn split_on(words: &[&str], keyword: &str) -> Option<(String, String)> {
+    let at = words
+        .iter()
+        .position(|word| word.eq_ignore_ascii_case(keyword))?;
+    let (before, after) = (&words[..at], &words[at + 1..]);
+    if before.is_empty() || after.is_empty() {
+        return None;
+    }
+    Some((before.join(" "), after.join(" ")))
+}
+
+impl FromStr for Command {
+    type Err = ParseCommandError;
+
+    /// Keywords are case insensitive, names keep their case. Names can be several words, extra
+    /// spaces between them are dropped.
+    fn from_str(line: &str) -> Result<Command, ParseCommandError> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let (command, rest) = words.split_first().ok_or(ParseCommandError::Empty)?;
+        match command.to_lowercase().as_str() {
+            "add" => split_on(rest, "to")
+                .map(|(employee, department)| Command::Add {
+                    employee,
+                    department,
+                })
+                .ok_or(ParseCommandError::Usage("Add <name> to <department>")),
+            "remove" => match split_on(rest, "from") {
+                Some((employee, department)) => Ok(Command::Remove {
+                    employee,
+                    department: Some(department),
+                }),
+                None if !rest.is_empty()
+                    && !rest.iter().any(|w| w.eq_ignore_ascii_case("from")) =>
+                {
+                    Ok(Command::Remove {
+                        employee: rest.join(" "),
+                        department: None,
+                    })
+                }
+                None => Err(ParseCommandError::Usage(
+                    "Remove <name> [from <department>]",
+                )),
+            },
+            "rename" => match rest.split_first() {
+                Some((first, names)) if first.eq_ignore_ascii_case("department") => {
+                    split_on(names, "to")
+                        .map(|(from, to)| Command::RenameDepartment { from, to })
+                        .ok_or(ParseCommandError::Usage(
+                            "Rename department <name> to <new name>",
+                        ))
+                }
+                _ => split_on(rest, "to")
+                    .map(|(from, to)| Command::RenameEmployee { from, to })
+                    .ok_or(ParseCommandError::Usage("Rename <name> to <new name>")),
+            },
+            "list" if rest.is_empty() => Ok(Command::List(None)),
+            "list" => Ok(Command::List(Some(rest.join(" ")))),
+            "help" => Ok(Command::Help),
+            "quit" | "exit" => Ok(Command::Quit),
+            _ => Err(ParseCommandError::Unknown(command.to_string())),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum DirectoryError {
+    NoSuchDepartment(String),
+    NotInDepartment {
+        employee: String,
+        department: String,
+    },
+    /// Not in any department.
+    NoSuchEmployee(String),
+    AlreadyInDepartment {
+        employee: String,
+        department: String,
+    },
+    DepartmentExists(String),
+    Io(io::Error),
+    Format(serde_json::Error),
+}
+
+impl fmt::Display for DirectoryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DirectoryError::NoSuchDepartment(department) => {
+                write!(f, "there's no {} department", department)
+            }
+            DirectoryError::NotInDepartment {
+                employee,
+                department,
+            } => write!(f, "{} isn't in {}", employee, department),
+            DirectoryError::NoSuchEmployee(employee) => {
+                write!(f, "{} isn't in any department", employee)
+            }
+            DirectoryError::AlreadyInDepartment {
+                employee,
+                department,
+            } => write!(f, "{} is already in {}", employee, department),
+            DirectoryError::DepartmentExists(department) => {
+                write!(f, "there's already a {} department", department)
+            }
+            DirectoryError::Io(e) => write!(f, "directory file error: {}", e),
+            DirectoryError::Format(e) => write!(f, "directory file is corrupt: {}", e),
+        }
+    }
+}
+
+impl Error for DirectoryError {}
+
+impl From<io::Error> for DirectoryError {
+    fn from(e: io::Error) -> Self {
+        DirectoryError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for DirectoryError {
+    fn from(e: serde_json::Error) -> Self {
+        DirectoryError::Format(e)
+    }
+}
+
+/// Department name to the people in it. Departments and each list of people are kept sorted,
+/// so the saved file only changes where the directory did, and a department goes when its last
+/// person does.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Directory {
+    departments: BTreeMap<String, Vec<String>>,
+}
+
+impl Directory {
+    pub fn new() -> Directory {
+        Directory::default()
+    }
+
+    /// An empty directory if there's no file yet.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Directory, DirectoryError> {
+        match fs::read_to_string(path) {
+            Ok(text) => {
+                let mut directory: Directory = serde_json::from_str(&text)?;
+                // the file may have been edited by hand, the lists have to be sorted to search
+                directory.departments.retain(|_, people| !people.is_empty());
+                for people in directory.departments.values_mut() {
+                    people.sort_unstable();
+                    people.dedup();
+                }
+                Ok(directory)
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Directory::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    // written next to the real file then renamed over it, like post_store, so a failed write
+    // leaves the last good copy alone
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), DirectoryError> {
+        let path = path.as_ref();
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    pub fn add_employee(&mut self, employee: &str, department: &str) -> Result<(), DirectoryError> {
+        let people = self.departments.entry(department.to_string()).or_default();
+        match people.binary_search_by(|name| name.as_str().cmp(employee)) {
+            Ok(_) => Err(DirectoryError::AlreadyInDepartment {
+                employee: employee.to_string(),
+                department: department.to_string(),
+            }),
+            Err(at) => {
+                people.insert(at, employee.to_string());
+                Ok(())
+            }
+        }
+    }
+
+    pub fn remove_employee(
+        &mut self,
+        employee: &str,
+        department: &str,
+    ) -> Result<(), DirectoryError> {
+        let people = self
+            .departments
+            .get_mut(department)
+            .ok_or_else(|| DirectoryError::NoSuchDepartment(department.to_string()))?;
+        let at = people
+            .binary_search_by(|name| name.as_str().cmp(employee))
+            .map_err(|_| DirectoryError::NotInDepartment {
+                employee: employee.to_string(),
+                department: department.to_string(),
+            })?;
+        people.remove(at);
+        if people.is_empty() {
+            self.departments.remove(department);
+        }
+        Ok(())
+    }
+
+    /// Every department `employee` is in.
+    pub fn departments_of(&self, employee: &str) -> Vec<&str> {
+        self.departments
+            .iter()
+            .filter(|(_, people)| people.iter().any(|name| name == employee))
+            .map(|(department, _)| department.as_str())
+            .collect()
+    }
+
+    /// Takes `employee` out of every department, returning which ones they were in.
+    pub fn remove_everywhere(&mut self, employee: &str) -> Result<Vec<String>, DirectoryError> {
+        let departments: Vec<String> = self
+            .departments_of(employee)
+            .into_iter()
+            .map(String::from)
+            .collect();
+        if departments.is_empty() {
+            return Err(DirectoryError::NoSuchEmployee(employee.to_string()));
+        }
+        for department in &departments {
+            self.remove_employee(employee, department)?;
+        }
+        Ok(departments)
+    }
+
+    /// Renames them in every department they're in.
+    pub fn rename_employee(&mut self, from: &str, to: &str) -> Result<(), DirectoryError> {
+        let departments = self.departments_of(from);
+        if departments.is_empty() {
+            return Err(DirectoryError::NoSuchEmployee(from.to_string()));
+        }
+        // checked up front so a clash in one department doesn't leave the rename half done
+        if let Some(department) = self
+            .departments_of(to)
+            .into_iter()
+            .find(|d| departments.contains(d))
+        {
+            return Err(DirectoryError::AlreadyInDepartment {
+                employee: to.to_string(),
+                department: department.to_string(),
+            });
+        }
+        let departments: Vec<String> = departments.into_iter().map(String::from).collect();
+        for department in &departments {
+            self.remove_employee(from, department)?;
+            self.add_employee(to, department)?;
+        }
+        Ok(())
+    }
+
+    pub fn rename_department(&mut self, from: &str, to: &str) -> Result<(), DirectoryError> {
+        if self.departments.contains_key(to) {
+            return Err(DirectoryError::DepartmentExists(to.to_string()));
+        }
+        let people = self
+            .departments
+            .remove(from)
+            .ok_or_else(|| DirectoryError::NoSuchDepartment(from.to_string()))?;
+        self.departments.insert(to.to_string(), people);
+        Ok(())
+    }
+
+    /// Sorted alphabetically, `None` for a department nobody's in.
+    pub fn department(&self, department: &str) -> Option<&[String]> {
+        self.departments.get(department).map(Vec::as_slice)
+    }
+
+    /// Every department and its people, departments in alphabetical order too.
+    pub fn by_department(&self) -> Vec<(&str, &[String])> {
+        self.departments
+            .iter()
+            .map(|(department, people)| (department.as_str(), people.as_slice()))
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.departments.is_empty()
+    }
+
+    /// Carries out one command and says how it went. `Help` and `Quit` are left to the REPL.
+    pub fn apply(&mut self, command: &Command) -> Result<String, DirectoryError> {
+        match command {
+            Command::Add {
+                employee,
+                department,
+            } => {
+                self.add_employee(employee, department)?;
+                Ok(format!("Added {} to {}", employee, department))
+            }
+            Command::Remove {
+                employee,
+                department: Some(department),
+            } => {
+                self.remove_employee(employee, department)?;
+                Ok(format!("Removed {} from {}", employee, department))
+            }
+            Command::Remove {
+                employee,
+                department: None,
+            } => {
+                let departments = self.remove_everywhere(employee)?;
+                Ok(format!(
+                    "Removed {} from {}",
+                    employee,
+                    departments.join(", ")
+                ))
+            }
+            Command::RenameEmployee { from, to } => {
+                self.rename_employee(from, to)?;
+                Ok(format!("Renamed {} to {}", from, to))
+            }
+            Command::RenameDepartment { from, to } => {
+                self.rename_department(from, to)?;
+                Ok(format!("Renamed department {} to {}", from, to))
+            }
+            Command::List(Some(department)) => {
+                let people = self
+                    .department(department)
+                    .ok_or_else(|| DirectoryError::NoSuchDepartment(department.to_string()))?;
+                Ok(listing(&[(department, people)]))
+            }
+            Command::List(None) if self.is_empty() => Ok(String::from("Nobody yet")),
+            Command::List(None) => Ok(listing(&self.by_department())),
+            Command::Help => Ok(HELP.to_string()),
+            Command::Quit => Ok(String::new()),
+        }
+    }
+}
+
+fn listing(departments: &[(&str, &[String])]) -> String {
+    let mut lines = vec![];
+    for (department, people) in departments {
+        lines.push(department.to_string());
+        lines.extend(people.iter().map(|name| format!("  {}", name)));
+    }
+    lines.join("\n")
+}
+
+/// Reads commands from `input` until Quit or the end of it, answering each on `output`. Errors
+/// are reported and the session carries on. With a `path` the directory is saved after every
+/// change, and a change that can't be saved is reported and not made.
+pub fn run<R: BufRead, W: Write>(
+    directory: &mut Directory,
+    input: R,
+    mut output: W,
+    path: Option<&Path>,
+) -> io::Result<()> {
+    write!(output, "> ")?;
+    output.flush()?;
+    for line in input.lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            match line.parse::<Command>() {
+                Ok(Command::Quit) => break,
+                Ok(command) => {
+                    let changes = !matches!(command, Command::List(_) | Command::Help);
+                    let reply = match path {
+                        // a change is made on a copy, and only kept once it's safely on disk
+                        Some(path) if changes => {
+                            let mut changed = directory.clone();
+                            changed.apply(&command).and_then(|reply| {
+                                changed.save(path)?;
+                                *directory = changed;
+                                Ok(reply)
+                            })
+                        }
+                        _ => directory.apply(&command),
+                    };
+                    match reply {
+                        Ok(reply) => writeln!(output, "{}", reply)?,
+                        Err(e) => writeln!(output, "error: {}", e)?,
+                    }
+                }
+                Err(e) => writeln!(output, "error: {}", e)?,
+            }
+        }
+        write!(output, "> ")?;
+        output.flush()?;
+    }
+    writeln!(output)
+}
+
+/// The REPL on stdin and stdout, kept in `directory.json` in the current directory.
+pub fn employee_directory() -> Result<(), Box<dyn Error>> {
+    let path = Path::new("directory.json");
+    let mut directory = Directory::load(path)?;
+    println!("Employee directory, Help for the commands");
+    let stdin = io::stdin();
+    run(&mut directory, stdin.lock(), io::stdout(), Some(path))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process;
+
+    fn command(line: &str) -> Command {
+        line.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!(
+            Command::Add {
+                employee: String::from("Sally Smith"),
+                department: String::from("Human Resources"),
+            },
+            command("add  Sally Smith TO Human Resources")
+        );
+        assert_eq!(
+            Command::Remove {
+                employee: String::from("Amir"),
+                department: None,
+            },
+            command("Remove Amir")
+        );
+        assert_eq!(
+            Command::RenameDepartment {
+                from: String::from("Eng"),
+                to: String::from("Engineering"),
+            },
+            command("Rename department Eng to Engineering")
+        );
+        assert_eq!(
+            Command::RenameEmployee {
+                from: String::from("Sal"),
+                to: String::from("Sally"),
+            },
+            command("rename Sal to Sally")
+        );
+        assert_eq!(Command::List(None), command("LIST"));
+        assert_eq!(Command::Quit, command("exit"));
+
+        assert_eq!(Err(ParseCommandError::Empty), "   ".parse::<Command>());
+        assert_eq!(
+            Err(ParseCommandError::Unknown(String::from("Fire"))),
+            "Fire Sally".parse::<Command>()
+        );
+        for bad in &[
+            "Add Sally",
+            "Add to Sales",
+            "Remove Sally from",
+            "Rename Sally",
+        ] {
+            assert!(
+                matches!(bad.parse::<Command>(), Err(ParseCommandError::Usage(_))),
+                "{}",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn changes_to_the_directory() {
+        let mut directory = Directory::new();
+        for (name, department) in &[
+            ("Sally", "Engineering"),
+            ("Amir", "Engineering"),
+            ("Bob", "Sales"),
+            ("Amir", "Sales"),
+        ] {
+            directory.add_employee(name, department).unwrap();
+        }
+        assert!(matches!(
+            directory.add_employee("Sally", "Engineering"),
+            Err(DirectoryError::AlreadyInDepartment { .. })
+        ));
+        assert_eq!(
+            Some(&[String::from("Amir"), String::from("Sally")][..]),
+            directory.department("Engineering")
+        );
+        assert_eq!(
+            vec!["Engineering", "Sales"],
+            directory.departments_of("Amir")
+        );
+
+        directory.rename_employee("Amir", "Amira").unwrap();
+        assert_eq!(
+            vec!["Engineering", "Sales"],
+            directory.departments_of("Amira")
+        );
+        assert!(matches!(
+            directory.rename_employee("Amira", "Sally"),
+            Err(DirectoryError::AlreadyInDepartment { .. })
+        ));
+        // the failed rename left Amira everywhere they were
+        assert_eq!(2, directory.departments_of("Amira").len());
+
+        directory.rename_department("Sales", "Marketing").unwrap();
+        assert!(matches!(
+            directory.rename_department("Marketing", "Engineering"),
+            Err(DirectoryError::DepartmentExists(_))
+        ));
+        assert_eq!(
+            vec!["Engineering", "Marketing"],
+            directory.remove_everywhere("Amira").unwrap()
+        );
+        directory.remove_employee("Bob", "Marketing").unwrap();
+        // Marketing went with Bob
+        assert_eq!(None, directory.department("Marketing"));
+        assert!(matches!(
+            directory.remove_employee("Bob", "Marketing"),
+            Err(DirectoryError::NoSuchDepartment(_))
+        ));
+        assert!(matches!(
+            directory.remove_everywhere("Nobody"),
+            Err(DirectoryError::NoSuchEmployee(_))
+        ));
+    }
+
+    #[test]
+    fn scripted_session() {
+        let script = "\
+Add Sally to Engineering
+Add Amir to Sales
+add Bob to Engineering
+
+List
+Add Sally to Engineering
+Fire Bob
+List Sales
+Quit
+Add Never to Reached
+";
+        let mut directory = Directory::new();
+        let mut output = vec![];
+        run(&mut directory, script.as_bytes(), &mut output, None).unwrap();
+
+        let expected = "\
+> Added Sally to Engineering
+> Added Amir to Sales
+> Added Bob to Engineering
+> > Engineering
+  Bob
+  Sally
+Sales
+  Amir
+> error: Sally is already in Engineering
+> error: unknown command \"Fire\", try Help
+> Sales
+  Amir
+>\x20
+";
+        assert_eq!(expected, String::from_utf8(output).unwrap());
+        assert!(directory.department("Reached").is_none());
+    }
+
+    #[test]
+    fn saved_between_sessions() {
+        let path = std::env::temp_dir().join(format!("directory_{}.json", process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut directory = Directory::load(&path).unwrap();
+        assert!(directory.is_empty());
+        let script = "Add Sally to Engineering\nAdd Amir to Engineering\nRemove Amir\n";
+        run(&mut directory, script.as_bytes(), io::sink(), Some(&path)).unwrap();
+
+        let loaded = Directory::load(&path).unwrap();
+        assert_eq!(directory, loaded);
+
+        // saving the same directory again writes the same bytes, departments in order
+        for department in &["Sales", "Marketing", "Accounts", "Legal"] {
+            directory.add_employee("Bob", department).unwrap();
+        }
+        directory.save(&path).unwrap();
+        let first = fs::read_to_string(&path).unwrap();
+        Directory::load(&path).unwrap().save(&path).unwrap();
+        assert_eq!(first, fs::read_to_string(&path).unwrap());
+        assert!(first.find("Accounts").unwrap() < first.find("Sales").unwrap());
+        for department in &["Sales", "Marketing", "Accounts", "Legal"] {
+            directory.remove_employee("Bob", department).unwrap();
+        }
+        directory.save(&path).unwrap();
+        assert_eq!(
+            Some(&[String::from("Sally")][..]),
+            loaded.department("Engineering")
+        );
+
+        // a change that can't be saved isn't kept either, or the next good save would write it
+        let nowhere = path.with_extension("missing").join("directory.json");
+        let mut output = vec![];
+        run(
+            &mut directory,
+            "Add Bob to Sales\n".as_bytes(),
+            &mut output,
+            Some(&nowhere),
+        )
+        .unwrap();
+        assert!(String::from_utf8(output)
+            .unwrap()
+            .contains("error: directory file error"));
+        assert_eq!(None, directory.department("Sales"));
+
+        fs::write(&path, "not json").unwrap();
+        assert!(matches!(
+            Directory::load(&path),
+            Err(DirectoryError::Format(_))
+        ));
+        fs::remove_file(&path).unwrap();
+    }
+}
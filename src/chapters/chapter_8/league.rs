@@ -0,0 +1,476 @@
+// The team scores from working_with_hashmap grown into a league table. Results are kept as they
+// were recorded and the table is worked out from them when asked for, so nothing has to be kept
+// in step and a team's history is just the results it played in.
+//
+// Standings go by points, then goal difference, then goals scored, then the points teams still
+// level took off each other, and finally by name so the order is always the same.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchResult {
+    pub home: String,
+    pub away: String,
+    pub home_goals: u32,
+    pub away_goals: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Win,
+    Draw,
+    Loss,
+}
+
+impl Outcome {
+    fn from_goals(scored: u32, conceded: u32) -> Outcome {
+        match scored.cmp(&conceded) {
+            Ordering::Greater => Outcome::Win,
+            Ordering::Equal => Outcome::Draw,
+            Ordering::Less => Outcome::Loss,
+        }
+    }
+
+    /// W, D or L, as in a form guide.
+    pub fn letter(self) -> char {
+        match self {
+            Outcome::Win => 'W',
+            Outcome::Draw => 'D',
+            Outcome::Loss => 'L',
+        }
+    }
+}
+
+/// One match from one team's side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TeamResult<'a> {
+    pub opponent: &'a str,
+    pub home: bool,
+    pub scored: u32,
+    pub conceded: u32,
+    pub outcome: Outcome,
+}
+
+/// A row of the table.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Standing {
+    pub team: String,
+    pub played: u32,
+    pub won: u32,
+    pub drawn: u32,
+    pub lost: u32,
+    pub goals_for: u32,
+    pub goals_against: u32,
+    pub points: u32,
+}
+
+impl Standing {
+    pub fn goal_difference(&self) -> i64 {
+        self.goals_for as i64 - self.goals_against as i64
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LeagueError {
+    /// A team can't play itself.
+    SameTeam(String),
+    EmptyName,
+}
+
+impl fmt::Display for LeagueError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LeagueError::SameTeam(team) => write!(f, "{} can't play itself", team),
+            LeagueError::EmptyName => write!(f, "team names can't be empty"),
+        }
+    }
+}
+
+impl Error for LeagueError {}
+
+#[derive(Debug, Clone)]
+pub struct League {
+    // in the order they first appeared, the table before any match is played
+    teams: Vec<String>,
+    results: Vec<MatchResult>,
+    win_points: u32,
+    draw_points: u32,
+}
+
+impl Default for League {
+    fn default() -> Self {
+        League::with_points(3, 1)
+    }
+}
+
+impl League {
+    /// Three points for a win, one for a draw.
+    pub fn new() -> League {
+        League::default()
+    }
+
+    pub fn with_points(win_points: u32, draw_points: u32) -> League {
+        League {
+            teams: vec![],
+            results: vec![],
+            win_points,
+            draw_points,
+        }
+    }
+
+    /// Adds a team with no matches yet, so it shows in the table. Recording a match adds its
+    /// teams anyway. Spaces either side of a name are dropped, so "Red " is Red.
+    pub fn add_team(&mut self, team: &str) -> Result<(), LeagueError> {
+        let team = team.trim();
+        if team.is_empty() {
+            return Err(LeagueError::EmptyName);
+        }
+        if !self.teams.iter().any(|known| known == team) {
+            self.teams.push(team.to_string());
+        }
+        Ok(())
+    }
+
+    pub fn teams(&self) -> &[String] {
+        &self.teams
+    }
+
+    pub fn record(
+        &mut self,
+        home: &str,
+        home_goals: u32,
+        away: &str,
+        away_goals: u32,
+    ) -> Result<(), LeagueError> {
+        let (home, away) = (home.trim(), away.trim());
+        if home.is_empty() || away.is_empty() {
+            return Err(LeagueError::EmptyName);
+        }
+        if home == away {
+            return Err(LeagueError::SameTeam(home.to_string()));
+        }
+        self.add_team(home)?;
+        self.add_team(away)?;
+        self.results.push(MatchResult {
+            home: home.to_string(),
+            away: away.to_string(),
+            home_goals,
+            away_goals,
+        });
+        Ok(())
+    }
+
+    pub fn results(&self) -> &[MatchResult] {
+        &self.results
+    }
+
+    /// Every match `team` played, in the order they were recorded.
+    pub fn history(&self, team: &str) -> Vec<TeamResult<'_>> {
+        let team = team.trim();
+        self.results
+            .iter()
+            .filter_map(|result| {
+                let (opponent, home, scored, conceded) = if result.home == team {
+                    (&result.away, true, result.home_goals, result.away_goals)
+                } else if result.away == team {
+                    (&result.home, false, result.away_goals, result.home_goals)
+                } else {
+                    return None;
+                };
+                Some(TeamResult {
+                    opponent,
+                    home,
+                    scored,
+                    conceded,
+                    outcome: Outcome::from_goals(scored, conceded),
+                })
+            })
+            .collect()
+    }
+
+    /// The last `n` outcomes, oldest first, like "WWDLW".
+    pub fn form(&self, team: &str, n: usize) -> String {
+        let history = self.history(team);
+        let skip = history.len().saturating_sub(n);
+        history[skip..]
+            .iter()
+            .map(|result| result.outcome.letter())
+            .collect()
+    }
+
+    fn points_for(&self, outcome: Outcome) -> u32 {
+        match outcome {
+            Outcome::Win => self.win_points,
+            Outcome::Draw => self.draw_points,
+            Outcome::Loss => 0,
+        }
+    }
+
+    // the chapter's entry().or_insert, a row per team filled in a result at a time
+    fn tally<'a>(
+        &self,
+        results: impl Iterator<Item = &'a MatchResult>,
+    ) -> HashMap<String, Standing> {
+        let mut rows: HashMap<String, Standing> = HashMap::new();
+        for result in results {
+            let sides = [
+                (&result.home, result.home_goals, result.away_goals),
+                (&result.away, result.away_goals, result.home_goals),
+            ];
+            for (team, scored, conceded) in sides.iter() {
+                let row = rows.entry(team.to_string()).or_insert_with(|| Standing {
+                    team: team.to_string(),
+                    ..Standing::default()
+                });
+                // saturating, so absurd scores or points per win pin at u32::MAX, not panic
+                let outcome = Outcome::from_goals(*scored, *conceded);
+                row.played = row.played.saturating_add(1);
+                row.goals_for = row.goals_for.saturating_add(*scored);
+                row.goals_against = row.goals_against.saturating_add(*conceded);
+                row.points = row.points.saturating_add(self.points_for(outcome));
+                let count = match outcome {
+                    Outcome::Win => &mut row.won,
+                    Outcome::Draw => &mut row.drawn,
+                    Outcome::Loss => &mut row.lost,
+                };
+                *count = count.saturating_add(1);
+            }
+        }
+        rows
+    }
+
+    /// The table, top first.
+    pub fn standings(&self) -> Vec<Standing> {
+        let mut rows = self.tally(self.results.iter());
+        let mut table: Vec<Standing> = self
+            .teams
+            .iter()
+            .map(|team| {
+                rows.remove(team).unwrap_or_else(|| Standing {
+                    team: team.clone(),
+                    ..Standing::default()
+                })
+            })
+            .collect();
+
+        let overall = |row: &Standing| (row.points, row.goal_difference(), row.goals_for);
+        table.sort_by(|a, b| {
+            overall(b)
+                .cmp(&overall(a))
+                .then_with(|| a.team.cmp(&b.team))
+        });
+
+        // teams still level go again on the matches between just them
+        let mut start = 0;
+        while start < table.len() {
+            let end = start
+                + table[start..]
+                    .iter()
+                    .take_while(|row| overall(row) == overall(&table[start]))
+                    .count();
+            if end - start > 1 {
+                let level: Vec<&str> = table[start..end]
+                    .iter()
+                    .map(|row| row.team.as_str())
+                    .collect();
+                let head_to_head = self.tally(self.results.iter().filter(|r| {
+                    level.contains(&r.home.as_str()) && level.contains(&r.away.as_str())
+                }));
+                let points =
+                    |row: &Standing| head_to_head.get(&row.team).map_or(0, |row| row.points);
+                // stable, so teams level on this too stay in name order
+                table[start..end].sort_by_key(|row| std::cmp::Reverse(points(row)));
+            }
+            start = end;
+        }
+        table
+    }
+
+    /// The standings as CSV, with a header row and the position first.
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from(
+            "position,team,played,won,drawn,lost,goals_for,goals_against,goal_difference,points\n",
+        );
+        for (i, row) in self.standings().iter().enumerate() {
+            out.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{},{}\n",
+                i + 1,
+                csv_field(&row.team),
+                row.played,
+                row.won,
+                row.drawn,
+                row.lost,
+                row.goals_for,
+                row.goals_against,
+                row.goal_difference(),
+                row.points
+            ));
+        }
+        out
+    }
+}
+
+/// Quoted when it has to be, with any quote inside doubled.
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn league() -> League {
+        let mut league = League::new();
+        league.record("Blue", 2, "Yellow", 0).unwrap();
+        league.record("Yellow", 1, "Red", 1).unwrap();
+        league.record("Red", 3, "Blue", 1).unwrap();
+        league.record("Green", 0, "Blue", 0).unwrap();
+        league
+    }
+
+    fn order(league: &League) -> Vec<String> {
+        league.standings().into_iter().map(|row| row.team).collect()
+    }
+
+    #[test]
+    fn table_and_history() {
+        let mut league = league();
+        league.add_team("White").unwrap();
+        let table = league.standings();
+        // Red 4 points, Blue 4 but a worse goal difference
+        assert_eq!(
+            vec!["Red", "Blue", "Green", "Yellow", "White"],
+            order(&league)
+        );
+        assert_eq!(
+            Standing {
+                team: String::from("Blue"),
+                played: 3,
+                won: 1,
+                drawn: 1,
+                lost: 1,
+                goals_for: 3,
+                goals_against: 3,
+                points: 4,
+            },
+            table[1]
+        );
+        assert_eq!(0, table[4].played);
+
+        let history = league.history("Blue");
+        assert_eq!(3, history.len());
+        assert_eq!("Red", history[1].opponent);
+        assert!(!history[1].home);
+        assert_eq!(Outcome::Loss, history[1].outcome);
+        assert_eq!("WLD", league.form("Blue", 5));
+        assert_eq!("LD", league.form("Blue", 2));
+        assert!(league.history("Nobody").is_empty());
+
+        assert_eq!(
+            Err(LeagueError::SameTeam(String::from("Red"))),
+            league.record("Red", 1, "Red", 0)
+        );
+        assert_eq!(Err(LeagueError::EmptyName), league.record("Red", 1, " ", 0));
+        assert_eq!(
+            Err(LeagueError::SameTeam(String::from("Red"))),
+            league.record("Red ", 1, " Red", 0)
+        );
+    }
+
+    #[test]
+    fn names_are_trimmed() {
+        let mut league = League::new();
+        league.add_team("Red ").unwrap();
+        league.record(" Red", 2, "Blue\t", 0).unwrap();
+        league.add_team("Blue").unwrap();
+        assert_eq!(&["Red", "Blue"], league.teams());
+        assert_eq!("Blue", league.results()[0].away);
+        assert_eq!("W", league.form("Red ", 5));
+        assert_eq!(vec!["Red", "Blue"], order(&league));
+        assert_eq!(Err(LeagueError::EmptyName), league.add_team(" \n"));
+    }
+
+    #[test]
+    fn tie_breakers() {
+        // A and B level on points, goal difference and goals scored, C scored fewer. B beat A
+        // so B goes above, though A comes first by name
+        let mut league = League::new();
+        league.record("B", 2, "A", 1).unwrap();
+        league.record("A", 1, "C", 0).unwrap();
+        league.record("C", 1, "B", 0).unwrap();
+        let table = league.standings();
+        let points: Vec<(&str, u32, i64, u32)> = table
+            .iter()
+            .map(|row| {
+                (
+                    row.team.as_str(),
+                    row.points,
+                    row.goal_difference(),
+                    row.goals_for,
+                )
+            })
+            .collect();
+        assert_eq!(vec![("B", 3, 0, 2), ("A", 3, 0, 2), ("C", 3, 0, 1)], points);
+
+        // nothing separates them at all, so it's by name
+        let mut league = League::new();
+        league.record("Zed", 1, "Amy", 1).unwrap();
+        assert_eq!(vec!["Amy", "Zed"], order(&league));
+
+        // A's win is worth as much as C's three draws at three points a win and goes above on
+        // goal difference, at two points a win C is top
+        let play = |league: &mut League| {
+            league.record("A", 1, "B", 0).unwrap();
+            league.record("C", 0, "D", 0).unwrap();
+            league.record("C", 0, "E", 0).unwrap();
+            league.record("C", 0, "F", 0).unwrap();
+        };
+        let mut league = League::new();
+        play(&mut league);
+        assert_eq!(vec!["A", "C", "D", "E", "F", "B"], order(&league));
+        let mut league = League::with_points(2, 1);
+        play(&mut league);
+        assert_eq!(vec!["C", "A", "D", "E", "F", "B"], order(&league));
+        assert_eq!(
+            vec![3, 2, 1, 1, 1, 0],
+            league
+                .standings()
+                .iter()
+                .map(|row| row.points)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn huge_scores_saturate() {
+        let mut league = League::with_points(u32::MAX, 1);
+        league.record("A", u32::MAX, "B", 0).unwrap();
+        league.record("A", 1, "B", u32::MAX).unwrap();
+        league.record("A", 2, "C", 0).unwrap();
+        let table = league.standings();
+        assert_eq!("A", table[0].team);
+        assert_eq!(u32::MAX, table[0].points);
+        assert_eq!(u32::MAX, table[0].goals_for);
+        assert_eq!(0, table[0].goal_difference());
+        assert_eq!(3, table[0].played);
+    }
+
+    #[test]
+    fn csv() {
+        let mut league = league();
+        league.record("Tom, \"the\" Cat", 1, "Green", 2).unwrap();
+        let csv = league.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(6, lines.len());
+        assert!(lines[0].starts_with("position,team,"));
+        assert_eq!("2,Green,2,1,1,0,2,1,1,4", lines[2]);
+        assert_eq!("5,\"Tom, \"\"the\"\" Cat\",1,0,0,1,1,2,-1,0", lines[5]);
+    }
+}
@@ -0,0 +1,194 @@
+// Text transforms that hold up outside ASCII. first_word in chapter_4 walks bytes looking for
+// b' ', and slicing at byte offsets like that panics in the middle of a multi-byte char or, worse,
+// quietly splits "é" written as e + a combining accent. Everything here works on grapheme
+// clusters, what a reader sees as one character, and on Unicode word boundaries from
+// unicode-segmentation, so accents, emoji and flags come through whole.
+//
+// Each transform is a fn(&str) -> String, which makes it a chapter_10 strategy Transform as it is.
+
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The first word, up to any kind of whitespace. A slice of `text` so nothing is copied.
+pub fn first_word(text: &str) -> &str {
+    text.split_whitespace().next().unwrap_or("")
+}
+
+/// The words, punctuation and spacing left out. "can't" stays one word, as do numbers like
+/// "3.14".
+pub fn words(text: &str) -> Vec<&str> {
+    text.unicode_words().collect()
+}
+
+/// User perceived characters, so "e\u{301}" and "👨‍👩‍👧" each count as one.
+pub fn graphemes(text: &str) -> Vec<&str> {
+    text.graphemes(true).collect()
+}
+
+/// Backwards a grapheme at a time. Reversing chars would move a combining accent onto the
+/// wrong letter and pull emoji sequences apart.
+pub fn reverse(text: &str) -> String {
+    text.graphemes(true).rev().collect()
+}
+
+/// The words in the opposite order, with single spaces between them.
+pub fn reverse_words(text: &str) -> String {
+    text.split_whitespace().rev().collect::<Vec<_>>().join(" ")
+}
+
+// the first grapheme uppercased, the rest of the word lowercased. Lowercasing the rest as one str
+// rather than char by char lets std pick the final form of Greek sigma.
+fn capitalize(word: &str) -> String {
+    let mut graphemes = word.graphemes(true);
+    let first = match graphemes.next() {
+        Some(first) => first,
+        None => return String::new(),
+    };
+    let mut out = first.to_uppercase();
+    out.push_str(&graphemes.as_str().to_lowercase());
+    out
+}
+
+/// Every word capitalized, everything between words left as it was.
+pub fn title_case(text: &str) -> String {
+    text.split_word_bounds()
+        .map(|piece| {
+            if piece.chars().any(char::is_alphabetic) {
+                capitalize(piece)
+            } else {
+                piece.to_string()
+            }
+        })
+        .collect()
+}
+
+// a, e, i, o, u and their accented forms. An accent written as a separate combining char sits
+// after its letter, so looking at the first char of the grapheme is enough for those.
+fn is_vowel(grapheme: &str) -> bool {
+    grapheme.chars().next().is_some_and(|ch| {
+        ch.to_lowercase()
+            .all(|ch| "aeiouáàâäãåæéèêëíìîïóòôöõøœúùûü".contains(ch))
+    })
+}
+
+/// The chapter 8 exercise for one word: the first consonant moves to the end followed by "ay",
+/// so "first" is "irst-fay", and a word starting with a vowel gets "hay" on the end instead,
+/// "apple-hay". A capitalized word stays capitalized, "Zoë" is "Oë-zay". Anything without a
+/// letter in it comes back as it was.
+pub fn pig_latin_word(word: &str) -> String {
+    if !word.chars().any(char::is_alphabetic) {
+        return word.to_string();
+    }
+    let mut graphemes = word.graphemes(true);
+    let first = graphemes.next().unwrap_or("");
+    if is_vowel(first) {
+        return format!("{}-hay", word);
+    }
+    let rest = graphemes.as_str();
+    let capitalized = first.chars().next().is_some_and(char::is_uppercase);
+    if capitalized && rest.chars().any(char::is_alphabetic) {
+        format!("{}-{}ay", capitalize(rest), first.to_lowercase())
+    } else {
+        format!("{}-{}ay", rest, first)
+    }
+}
+
+/// Pig Latin for every word in `text`, with the spacing, punctuation and emoji around them kept
+/// where they were.
+pub fn pig_latin(text: &str) -> String {
+    text.split_word_bounds()
+        .map(|piece| {
+            if piece.chars().any(char::is_alphabetic) {
+                pig_latin_word(piece)
+            } else {
+                piece.to_string()
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // é as e plus a combining acute, two chars but one grapheme
+    const CAFE: &str = "cafe\u{301}";
+    const FAMILY: &str = "👨\u{200d}👩\u{200d}👧";
+    const FLAG: &str = "🇳🇿";
+
+    #[test]
+    fn splitting() {
+        let text = format!("  {} and {} {}, can't stop  ", CAFE, FAMILY, FLAG);
+        assert_eq!(CAFE, first_word(&text));
+        assert_eq!("", first_word(" \t\n"));
+        assert_eq!("東京", first_word("東京\u{3000}大阪"));
+        assert_eq!(vec![CAFE, "and", "can't", "stop"], words(&text));
+
+        assert_eq!(4, graphemes(CAFE).len());
+        assert_eq!(
+            vec![FAMILY, FLAG],
+            graphemes(&format!("{}{}", FAMILY, FLAG))
+        );
+        // where byte slicing gives up
+        assert!(!CAFE.is_char_boundary(CAFE.len() - 1));
+    }
+
+    #[test]
+    fn reversing() {
+        assert_eq!("e\u{301}fac", reverse(CAFE));
+        assert_eq!(
+            format!("{}!{}", FLAG, FAMILY),
+            reverse(&format!("{}!{}", FAMILY, FLAG))
+        );
+        // reversing chars instead puts the accent in front of nothing
+        assert_ne!(reverse(CAFE), CAFE.chars().rev().collect::<String>());
+        assert_eq!("", reverse(""));
+        assert_eq!(
+            format!("{} world hello", FAMILY),
+            reverse_words(&format!(" hello  world {} ", FAMILY))
+        );
+    }
+
+    #[test]
+    fn title_casing() {
+        assert_eq!(
+            "The Quick-Brown Fox's Den",
+            title_case("the QUICK-brown fox's den")
+        );
+        assert_eq!("Éclair Über Øre", title_case("éclair über øre"));
+        assert_eq!(
+            format!("{} Cafe\u{301}", FAMILY),
+            title_case(&format!("{} CAFE\u{301}", FAMILY))
+        );
+        // the last sigma of a word takes its final form
+        assert_eq!("Οδος", title_case("ΟΔΟΣ"));
+        // some letters grow when uppercased
+        assert_eq!("SStraße", title_case("ßtraße"));
+        assert_eq!("42 Is 6×7", title_case("42 is 6×7"));
+    }
+
+    #[test]
+    fn pig_latin_words() {
+        assert_eq!("irst-fay", pig_latin_word("first"));
+        assert_eq!("apple-hay", pig_latin_word("apple"));
+        assert_eq!("Ello-hay", pig_latin_word("Hello"));
+        assert_eq!("Oë-zay", pig_latin_word("Zoë"));
+        assert_eq!("Éclair-hay", pig_latin_word("Éclair"));
+        assert_eq!("andu-ñay", pig_latin_word("ñandu"));
+        // a vowel with its accent as a separate char is still a vowel
+        assert_eq!("e\u{301}tude-hay", pig_latin_word("e\u{301}tude"));
+        // the combining accent moves with its letter
+        assert_eq!("ava-c\u{327}ay", pig_latin_word("c\u{327}ava"));
+        assert_eq!("123", pig_latin_word("123"));
+        assert_eq!(FAMILY, pig_latin_word(FAMILY));
+    }
+
+    #[test]
+    fn pig_latin_text() {
+        assert_eq!(
+            format!("Ello-hay, orld-way! {} ove-lay afe\u{301}-cay.", FLAG),
+            pig_latin(&format!("Hello, world! {} love {}.", FLAG, CAFE))
+        );
+        assert_eq!("", pig_latin(""));
+        assert_eq!("  \n", pig_latin("  \n"));
+    }
+}
@@ -0,0 +1,471 @@
+// Items<'a, X> from cow() as something you can actually use. CowVec and CowStr start out
+// borrowing and only copy the data the first time a change really happens: retaining everything,
+// sorting what's already sorted or deduping without duplicates leaves them borrowed. Shrinking
+// from the end (truncate, pop, clear) never needs a copy either, it just borrows less.
+
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::FromIterator;
+use std::ops::Deref;
+
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct CowVec<'a, T: Clone> {
+    inner: Cow<'a, [T]>,
+}
+
+impl<'a, T: Clone> CowVec<'a, T> {
+    pub fn borrowed(items: &'a [T]) -> CowVec<'a, T> {
+        CowVec {
+            inner: Cow::Borrowed(items),
+        }
+    }
+
+    pub fn owned(items: Vec<T>) -> CowVec<'a, T> {
+        CowVec {
+            inner: Cow::Owned(items),
+        }
+    }
+
+    /// Still pointing at the data it was made from.
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self.inner, Cow::Borrowed(_))
+    }
+
+    /// Has its own copy, because it was made from a Vec or something changed it.
+    pub fn is_owned(&self) -> bool {
+        !self.is_borrowed()
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.inner
+    }
+
+    /// Copies the items if they are still borrowed.
+    pub fn to_mut(&mut self) -> &mut Vec<T> {
+        self.inner.to_mut()
+    }
+
+    pub fn into_owned(self) -> Vec<T> {
+        self.inner.into_owned()
+    }
+
+    pub fn into_cow(self) -> Cow<'a, [T]> {
+        self.inner
+    }
+
+    pub fn push(&mut self, item: T) {
+        self.to_mut().push(item);
+    }
+
+    pub fn insert(&mut self, index: usize, item: T) {
+        self.to_mut().insert(index, item);
+    }
+
+    pub fn remove(&mut self, index: usize) -> T {
+        let len = self.len();
+        assert!(
+            index < len,
+            "remove index {} out of bounds for length {}",
+            index,
+            len
+        );
+        if index == len - 1 {
+            return self.pop().unwrap();
+        }
+        self.to_mut().remove(index)
+    }
+
+    /// Borrowed stays borrowed, it gives back a clone of the last item.
+    pub fn pop(&mut self) -> Option<T> {
+        match &mut self.inner {
+            Cow::Borrowed(items) => {
+                let (last, rest) = items.split_last()?;
+                *items = rest;
+                Some(last.clone())
+            }
+            Cow::Owned(items) => items.pop(),
+        }
+    }
+
+    pub fn truncate(&mut self, len: usize) {
+        match &mut self.inner {
+            Cow::Borrowed(items) => *items = &items[..len.min(items.len())],
+            Cow::Owned(items) => items.truncate(len),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// Keeps the items `keep` says yes to. `keep` sees each item once, in order, and nothing
+    /// is copied unless it says no to one.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut keep: F) {
+        let items = match &self.inner {
+            Cow::Borrowed(items) => *items,
+            Cow::Owned(_) => return self.to_mut().retain(keep),
+        };
+        let first_dropped = match items.iter().position(|item| !keep(item)) {
+            Some(i) => i,
+            None => return,
+        };
+        let mut kept = items[..first_dropped].to_vec();
+        kept.extend(
+            items[first_dropped + 1..]
+                .iter()
+                .filter(|&item| keep(item))
+                .cloned(),
+        );
+        self.inner = Cow::Owned(kept);
+    }
+
+    /// Removes consecutive items `same` says are equal, keeping the first of each run.
+    pub fn dedup_by<F: FnMut(&T, &T) -> bool>(&mut self, mut same: F) {
+        let items = match &self.inner {
+            Cow::Borrowed(items) => *items,
+            Cow::Owned(_) => return self.to_mut().dedup_by(|a, b| same(a, b)),
+        };
+        // `same` gets (later, earlier) like Vec::dedup_by
+        if !items.windows(2).any(|pair| same(&pair[1], &pair[0])) {
+            return;
+        }
+        let mut kept: Vec<T> = Vec::with_capacity(items.len());
+        for item in items {
+            match kept.last() {
+                Some(last) if same(item, last) => {}
+                _ => kept.push(item.clone()),
+            }
+        }
+        self.inner = Cow::Owned(kept);
+    }
+
+    pub fn dedup_by_key<K: PartialEq, F: FnMut(&T) -> K>(&mut self, mut key: F) {
+        self.dedup_by(|a, b| key(a) == key(b));
+    }
+
+    /// Sorts, stable like Vec::sort_by, copying only if the items aren't in order already.
+    pub fn sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, mut compare: F) {
+        if self.is_borrowed()
+            && self
+                .windows(2)
+                .all(|pair| compare(&pair[0], &pair[1]) != Ordering::Greater)
+        {
+            return;
+        }
+        self.to_mut().sort_by(compare);
+    }
+
+    pub fn sort_by_key<K: Ord, F: FnMut(&T) -> K>(&mut self, mut key: F) {
+        self.sort_by(|a, b| key(a).cmp(&key(b)));
+    }
+}
+
+impl<'a, T: Clone + PartialEq> CowVec<'a, T> {
+    pub fn dedup(&mut self) {
+        self.dedup_by(|a, b| a == b);
+    }
+}
+
+impl<'a, T: Clone + Ord> CowVec<'a, T> {
+    pub fn sort(&mut self) {
+        self.sort_by(|a, b| a.cmp(b));
+    }
+}
+
+impl<'a, T: Clone> Deref for CowVec<'a, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.inner
+    }
+}
+
+impl<'a, T: Clone> From<&'a [T]> for CowVec<'a, T> {
+    fn from(items: &'a [T]) -> CowVec<'a, T> {
+        CowVec::borrowed(items)
+    }
+}
+
+impl<'a, T: Clone> From<Vec<T>> for CowVec<'a, T> {
+    fn from(items: Vec<T>) -> CowVec<'a, T> {
+        CowVec::owned(items)
+    }
+}
+
+impl<'a, T: Clone> From<Cow<'a, [T]>> for CowVec<'a, T> {
+    fn from(inner: Cow<'a, [T]>) -> CowVec<'a, T> {
+        CowVec { inner }
+    }
+}
+
+impl<'a, T: Clone> FromIterator<T> for CowVec<'a, T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> CowVec<'a, T> {
+        CowVec::owned(iter.into_iter().collect())
+    }
+}
+
+impl<'a, T: Clone> Extend<T> for CowVec<'a, T> {
+    /// Copies only if the iterator has something in it.
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let mut iter = iter.into_iter().peekable();
+        if iter.peek().is_some() {
+            self.to_mut().extend(iter);
+        }
+    }
+}
+
+impl<'a, T: Clone + fmt::Debug> fmt::Debug for CowVec<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct CowStr<'a> {
+    inner: Cow<'a, str>,
+}
+
+impl<'a> CowStr<'a> {
+    pub fn borrowed(text: &'a str) -> CowStr<'a> {
+        CowStr {
+            inner: Cow::Borrowed(text),
+        }
+    }
+
+    pub fn owned(text: String) -> CowStr<'a> {
+        CowStr {
+            inner: Cow::Owned(text),
+        }
+    }
+
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self.inner, Cow::Borrowed(_))
+    }
+
+    pub fn is_owned(&self) -> bool {
+        !self.is_borrowed()
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.inner
+    }
+
+    pub fn to_mut(&mut self) -> &mut String {
+        self.inner.to_mut()
+    }
+
+    pub fn into_owned(self) -> String {
+        self.inner.into_owned()
+    }
+
+    pub fn into_cow(self) -> Cow<'a, str> {
+        self.inner
+    }
+
+    pub fn push(&mut self, ch: char) {
+        self.to_mut().push(ch);
+    }
+
+    /// Appending "" changes nothing, so it doesn't copy.
+    pub fn push_str(&mut self, text: &str) {
+        if !text.is_empty() {
+            self.to_mut().push_str(text);
+        }
+    }
+
+    pub fn insert(&mut self, index: usize, ch: char) {
+        self.to_mut().insert(index, ch);
+    }
+
+    pub fn insert_str(&mut self, index: usize, text: &str) {
+        if !text.is_empty() {
+            self.to_mut().insert_str(index, text);
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<char> {
+        let last = self.chars().next_back()?;
+        self.truncate(self.len() - last.len_utf8());
+        Some(last)
+    }
+
+    /// Panics if `len` isn't on a char boundary, like String::truncate.
+    pub fn truncate(&mut self, len: usize) {
+        match &mut self.inner {
+            Cow::Borrowed(text) => {
+                if len < text.len() {
+                    assert!(text.is_char_boundary(len), "truncate off a char boundary");
+                    *text = &text[..len];
+                }
+            }
+            Cow::Owned(text) => text.truncate(len),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// Keeps the chars `keep` says yes to, each seen once, copying only if one goes.
+    pub fn retain<F: FnMut(char) -> bool>(&mut self, mut keep: F) {
+        let text = match &self.inner {
+            Cow::Borrowed(text) => *text,
+            Cow::Owned(_) => return self.to_mut().retain(keep),
+        };
+        let (first_dropped, ch) = match text.char_indices().find(|&(_, ch)| !keep(ch)) {
+            Some(found) => found,
+            None => return,
+        };
+        let mut kept = text[..first_dropped].to_string();
+        kept.extend(
+            text[first_dropped + ch.len_utf8()..]
+                .chars()
+                .filter(|&ch| keep(ch)),
+        );
+        self.inner = Cow::Owned(kept);
+    }
+
+    /// Replaces every `from` with `to`, copying only if `from` turns up.
+    pub fn replace(&mut self, from: &str, to: &str) {
+        if !from.is_empty() && self.contains(from) {
+            self.inner = Cow::Owned(self.inner.replace(from, to));
+        }
+    }
+}
+
+impl<'a> Deref for CowStr<'a> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.inner
+    }
+}
+
+impl<'a> From<&'a str> for CowStr<'a> {
+    fn from(text: &'a str) -> CowStr<'a> {
+        CowStr::borrowed(text)
+    }
+}
+
+impl<'a> From<String> for CowStr<'a> {
+    fn from(text: String) -> CowStr<'a> {
+        CowStr::owned(text)
+    }
+}
+
+impl<'a> From<Cow<'a, str>> for CowStr<'a> {
+    fn from(inner: Cow<'a, str>) -> CowStr<'a> {
+        CowStr { inner }
+    }
+}
+
+impl<'a> Extend<char> for CowStr<'a> {
+    fn extend<I: IntoIterator<Item = char>>(&mut self, iter: I) {
+        let mut iter = iter.into_iter().peekable();
+        if iter.peek().is_some() {
+            self.to_mut().extend(iter);
+        }
+    }
+}
+
+impl<'a> fmt::Debug for CowStr<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl<'a> fmt::Display for CowStr<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vec_borrows_until_something_changes() {
+        let data = [1, 2, 2, 3, 5];
+        let mut v = CowVec::from(&data[..]);
+
+        v.retain(|&n| n > 0);
+        v.sort();
+        v.extend(vec![]);
+        v.truncate(4);
+        assert_eq!(Some(3), v.pop());
+        assert_eq!(2, v.remove(2));
+        assert!(v.is_borrowed());
+        assert_eq!(&[1, 2], v.as_slice());
+
+        let mut v = CowVec::from(&data[..]);
+        v.dedup();
+        assert!(v.is_owned());
+        assert_eq!(&[1, 2, 3, 5], v.as_slice());
+        assert_eq!(&[1, 2, 2, 3, 5], &data);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn vec_remove_past_the_end_panics() {
+        let data = [1, 2, 3];
+        CowVec::from(&data[..]).remove(usize::MAX);
+    }
+
+    #[test]
+    fn vec_mutations_match_vec() {
+        let data = [4, -1, 3, 3, -2, 8];
+        let mut expected = data.to_vec();
+        let mut v = CowVec::from(&data[..]);
+
+        let mut seen = vec![];
+        v.retain(|&n| {
+            seen.push(n);
+            n != -1
+        });
+        expected.retain(|&n| n != -1);
+        // every item looked at exactly once even though the copy started part way through
+        assert_eq!(data.to_vec(), seen);
+
+        v.dedup();
+        expected.dedup();
+        v.sort_by_key(|n: &i32| n.abs());
+        expected.sort_by_key(|n: &i32| n.abs());
+        v.insert(1, 7);
+        expected.insert(1, 7);
+        v.push(0);
+        expected.push(0);
+        assert_eq!(expected, v.into_owned());
+
+        let words = ["Apple", "apple", "Bee", "bee", "bee"];
+        let mut v = CowVec::from(&words[..]);
+        v.dedup_by_key(|w| w.to_lowercase());
+        assert_eq!(&["Apple", "Bee"], v.as_slice());
+    }
+
+    #[test]
+    fn str_borrows_until_something_changes() {
+        let text = "héllo wörld";
+        let mut s = CowStr::from(text);
+        s.retain(|ch| ch != 'x');
+        s.replace("xyz", "abc");
+        s.push_str("");
+        assert_eq!(Some('d'), s.pop());
+        s.truncate(6);
+        assert!(s.is_borrowed());
+        assert_eq!("héllo", s.as_str());
+
+        s.retain(|ch| ch != 'l');
+        assert!(s.is_owned());
+        assert_eq!("héo", s.as_str());
+
+        let mut s = CowStr::from("a-b-c");
+        s.replace("-", ", ");
+        s.insert(0, '[');
+        s.push(']');
+        assert_eq!("[a, b, c]", s.to_string());
+        s.clear();
+        assert!(s.is_empty());
+    }
+}
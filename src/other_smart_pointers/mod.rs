@@ -1,17 +1,28 @@
 use futures::StreamExt;
+use std::borrow::Cow;
+
+// Items<'a, X> below as public collections that borrow until they're changed
+pub mod cow_collections;
+// text clean up steps that only allocate when they change something
+pub mod normalize;
+// the Thing used in trial_cow_obj_destruct_match, with name iterators that end
+pub mod thing;
+// counts allocations so tests can check the Cow paths below really don't clone
+pub mod tracking_alloc;
+
+// out here rather than inside cow() so tests/alloc_tracking.rs can measure it
+pub fn abs_all(input: &mut Cow<[i32]>) {
+    for i in 0..input.len() {
+        let v = input[i];
+        if v < 0 {
+            input.to_mut()[i] = -v;
+        }
+    }
+}
 
 pub fn cow() {
     use std::borrow::Cow;
 
-    fn abs_all(input: &mut Cow<[i32]>) {
-        for i in 0..input.len() {
-            let v = input[i];
-            if v < 0 {
-                input.to_mut()[i] = -v;
-            }
-        }
-    }
-
     // No clone occurs because input doesn't need to be mutated
     let slice = [0, 1, 2];
     let mut input = Cow::from(&slice[..]);
@@ -83,24 +94,8 @@ pub fn trial_cow_obj_destruct_match() {
 
 
     // destruct value from match
-    #[derive(Debug )]
-    struct Thing {
-        name: String,
-    }
-
-    impl Thing {
-        fn new(name: String) -> Self {
-            Thing { name }
-        }
-    }
-
-    impl Iterator for Thing {
-        type Item = String;
-
-        fn next(&mut self) -> Option<Self::Item> {
-            Some((&self.name).parse().unwrap())
-        }
-    }
+    // Thing lives in thing.rs now, it used to be an iterator that never ended
+    use thing::Thing;
 
     let x = Thing::new(String::from("David"));
 
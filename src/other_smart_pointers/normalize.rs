@@ -0,0 +1,206 @@
+// Text clean up built on Cow<str>. Every step hands back what it was given, untouched, when
+// there is nothing to do, and only builds a new String when the text really changes. Most input
+// is usually clean already, so a whole pipeline over it allocates nothing.
+
+use std::borrow::Cow;
+
+/// Runs of whitespace, newlines included, become a single space.
+pub fn collapse_whitespace(text: Cow<'_, str>) -> Cow<'_, str> {
+    // fine as it is if every whitespace char is a lone ' '
+    let mut previous_space = false;
+    let clean = text.chars().all(|ch| {
+        let ok = !ch.is_whitespace() || (ch == ' ' && !previous_space);
+        previous_space = ch.is_whitespace();
+        ok
+    });
+    if clean {
+        return text;
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut in_space = false;
+    for ch in text.chars() {
+        if ch.is_whitespace() {
+            if !in_space {
+                out.push(' ');
+            }
+            in_space = true;
+        } else {
+            out.push(ch);
+            in_space = false;
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// Leading and trailing whitespace removed. Never allocates: a borrowed text is borrowed
+/// shorter, an owned one is cut down in place.
+pub fn trim(text: Cow<'_, str>) -> Cow<'_, str> {
+    match text {
+        Cow::Borrowed(text) => Cow::Borrowed(text.trim()),
+        Cow::Owned(mut text) => {
+            let end = text.trim_end().len();
+            text.truncate(end);
+            let start = text.len() - text.trim_start().len();
+            text.drain(..start);
+            Cow::Owned(text)
+        }
+    }
+}
+
+/// Lowercased, for comparing text without caring about case the way
+/// search_case_insensitive in chapter12_lib does.
+pub fn fold_case(text: Cow<'_, str>) -> Cow<'_, str> {
+    // some chars lowercase to more than one, so compare the whole mapping not just is_uppercase
+    if text
+        .chars()
+        .all(|ch| ch.to_lowercase().eq(std::iter::once(ch)))
+    {
+        return text;
+    }
+    Cow::Owned(text.to_lowercase())
+}
+
+/// `& < > " '` as HTML entities, safe to drop into markup.
+pub fn escape_html(text: Cow<'_, str>) -> Cow<'_, str> {
+    if !text.contains(['&', '<', '>', '"', '\'']) {
+        return text;
+    }
+    let mut out = String::with_capacity(text.len() + 16);
+    for ch in text.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(ch),
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// Windows `\r\n` and old Mac `\r` line endings become `\n`.
+pub fn normalize_line_endings(text: Cow<'_, str>) -> Cow<'_, str> {
+    if !text.contains('\r') {
+        return text;
+    }
+    Cow::Owned(text.replace("\r\n", "\n").replace('\r', "\n"))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    CollapseWhitespace,
+    Trim,
+    FoldCase,
+    EscapeHtml,
+    LineEndings,
+}
+
+impl Step {
+    pub fn apply<'a>(&self, text: Cow<'a, str>) -> Cow<'a, str> {
+        match self {
+            Step::CollapseWhitespace => collapse_whitespace(text),
+            Step::Trim => trim(text),
+            Step::FoldCase => fold_case(text),
+            Step::EscapeHtml => escape_html(text),
+            Step::LineEndings => normalize_line_endings(text),
+        }
+    }
+}
+
+/// Steps run one after the other, each getting the last one's output.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Pipeline {
+    steps: Vec<Step>,
+}
+
+impl Pipeline {
+    pub fn new() -> Pipeline {
+        Pipeline::default()
+    }
+
+    pub fn then(mut self, step: Step) -> Pipeline {
+        self.steps.push(step);
+        self
+    }
+
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    /// Borrowed from `text` unless some step changed it.
+    pub fn apply<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        self.apply_cow(Cow::Borrowed(text))
+    }
+
+    pub fn apply_cow<'a>(&self, text: Cow<'a, str>) -> Cow<'a, str> {
+        self.steps.iter().fold(text, |text, step| step.apply(text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_step() {
+        let cases: Vec<(Step, &str, &str)> = vec![
+            (Step::CollapseWhitespace, "a  b\t\nc d", "a b c d"),
+            (Step::Trim, "  a b \n", "a b"),
+            (Step::FoldCase, "Straße İS", "straße i\u{307}s"),
+            (
+                Step::EscapeHtml,
+                "<a href=\"x\">Tom & Jerry's</a>",
+                "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;",
+            ),
+            (Step::LineEndings, "a\r\nb\rc\n", "a\nb\nc\n"),
+        ];
+        for (step, input, expected) in cases {
+            let out = step.apply(Cow::Borrowed(input));
+            assert_eq!(expected, out, "{:?}", step);
+            assert!(
+                matches!(out, Cow::Owned(_)) || step == Step::Trim,
+                "{:?}",
+                step
+            );
+            // and a second pass has nothing left to do, except escaping which escapes its own &
+            let again = step.apply(Cow::Borrowed(&out));
+            assert!(matches!(again, Cow::Borrowed(_)) || step == Step::EscapeHtml);
+        }
+    }
+
+    #[test]
+    fn clean_text_stays_borrowed() {
+        let pipeline = Pipeline::new()
+            .then(Step::LineEndings)
+            .then(Step::Trim)
+            .then(Step::CollapseWhitespace)
+            .then(Step::FoldCase)
+            .then(Step::EscapeHtml);
+
+        let clean = "already clean text\u{e9}";
+        assert!(matches!(pipeline.apply(clean), Cow::Borrowed(_)));
+        // trimming first means the padding never reaches collapse_whitespace
+        assert!(matches!(
+            pipeline.apply("  padded only  "),
+            Cow::Borrowed(_)
+        ));
+
+        let messy = "  Rust:\r\n  Safe,\tFast & Productive.  ";
+        assert_eq!("rust: safe, fast &amp; productive.", pipeline.apply(messy));
+    }
+
+    #[test]
+    fn trim_owned_in_place() {
+        let text = String::from("\t owned \n");
+        let capacity = text.capacity();
+        match trim(Cow::Owned(text)) {
+            Cow::Owned(trimmed) => {
+                assert_eq!("owned", trimmed);
+                assert_eq!(capacity, trimmed.capacity());
+            }
+            Cow::Borrowed(_) => panic!("owned text came back borrowed"),
+        }
+    }
+}
@@ -0,0 +1,184 @@
+// Thing from trial_cow_obj_destruct_match. Its Iterator impl handed back the name forever, so
+// anything short of take() never finished. Thing isn't an iterator any more, it gives out
+// bounded ones instead. They know their length and can be walked from either end.
+
+use std::iter::FusedIterator;
+use std::slice;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Thing {
+    pub name: String,
+}
+
+impl Thing {
+    pub fn new(name: String) -> Self {
+        Thing { name }
+    }
+
+    /// The name `n` times.
+    pub fn iter_names(&self, n: usize) -> Names<'_> {
+        Names::new(slice::from_ref(self), n)
+    }
+
+    /// `n` names going round `things` in order, back to the first after the last.
+    pub fn cycle_names(things: &[Thing], n: usize) -> Names<'_> {
+        // nothing to go round
+        let n = if things.is_empty() { 0 } else { n };
+        Names::new(things, n)
+    }
+}
+
+/// Yields name number `i` for every `i` in a range, worked out from `i` alone so both ends and
+/// the length are always known.
+#[derive(Debug, Clone)]
+pub struct Names<'a> {
+    things: &'a [Thing],
+    front: usize,
+    back: usize,
+    numbered: bool,
+}
+
+impl<'a> Names<'a> {
+    fn new(things: &'a [Thing], n: usize) -> Names<'a> {
+        Names {
+            things,
+            front: 0,
+            back: n,
+            numbered: false,
+        }
+    }
+
+    /// Adds `-1`, `-2`, ... counting how many times each name has come up, so "David-1" and
+    /// "David-2" for a single Thing, or "Ann-1", "Bob-1", "Ann-2" going round two.
+    pub fn numbered(mut self) -> Names<'a> {
+        self.numbered = true;
+        self
+    }
+
+    fn name(&self, i: usize) -> String {
+        let name = &self.things[i % self.things.len()].name;
+        if self.numbered {
+            format!("{}-{}", name, i / self.things.len() + 1)
+        } else {
+            name.clone()
+        }
+    }
+}
+
+impl<'a> Iterator for Names<'a> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        if self.front == self.back {
+            return None;
+        }
+        self.front += 1;
+        Some(self.name(self.front - 1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<String> {
+        // skipping doesn't need to build the names it skips
+        self.front = self.front.saturating_add(n).min(self.back);
+        self.next()
+    }
+}
+
+impl<'a> DoubleEndedIterator for Names<'a> {
+    fn next_back(&mut self) -> Option<String> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        Some(self.name(self.back))
+    }
+}
+
+impl<'a> ExactSizeIterator for Names<'a> {}
+
+impl<'a> FusedIterator for Names<'a> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn david() -> Thing {
+        Thing::new(String::from("David"))
+    }
+
+    #[test]
+    fn names_end() {
+        let david = david();
+        assert_eq!(vec!["David"; 3], david.iter_names(3).collect::<Vec<_>>());
+        assert_eq!(
+            vec!["David-1", "David-2", "David-3"],
+            david.iter_names(3).numbered().collect::<Vec<_>>()
+        );
+        assert_eq!(0, david.iter_names(0).count());
+        assert_eq!(
+            Some(String::from("David-500")),
+            david.iter_names(500).numbered().next_back()
+        );
+    }
+
+    #[test]
+    fn cycles_through_several() {
+        let things = vec![
+            Thing::new(String::from("Ann")),
+            Thing::new(String::from("Bob")),
+        ];
+        assert_eq!(
+            vec!["Ann-1", "Bob-1", "Ann-2", "Bob-2", "Ann-3"],
+            Thing::cycle_names(&things, 5)
+                .numbered()
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(0, Thing::cycle_names(&[], 5).len());
+        // std's cycle works too since Names is Clone, but then there's no end
+        assert_eq!(
+            vec!["Ann", "Ann", "Ann"],
+            things[0].iter_names(1).cycle().take(3).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn both_ends() {
+        let david = david();
+        let mut names = david.iter_names(4).numbered();
+        assert_eq!(Some(String::from("David-4")), names.next_back());
+        assert_eq!(Some(String::from("David-1")), names.next());
+        assert_eq!(vec!["David-3", "David-2"], names.rev().collect::<Vec<_>>());
+
+        let mut names = david.iter_names(10).numbered();
+        assert_eq!(Some(String::from("David-3")), names.nth(2));
+        assert_eq!(Some(String::from("David-10")), names.nth_back(0));
+        assert_eq!(None, names.nth(100));
+        assert_eq!(None, names.next());
+    }
+
+    #[test]
+    fn size_hint_is_exact() {
+        let david = david();
+        let mut names = david.iter_names(5);
+        for remaining in (0..=5).rev() {
+            assert_eq!((remaining, Some(remaining)), names.size_hint());
+            assert_eq!(remaining, names.len());
+            if remaining % 2 == 0 {
+                names.next();
+            } else {
+                names.next_back();
+            }
+        }
+        assert_eq!((0, Some(0)), names.size_hint());
+
+        // adapters can rely on it too
+        let names = david.iter_names(7).numbered();
+        assert_eq!((3, Some(3)), names.clone().skip(4).size_hint());
+        assert_eq!((7, Some(7)), names.clone().map(|n| n.len()).size_hint());
+        assert_eq!(7, names.count());
+    }
+}
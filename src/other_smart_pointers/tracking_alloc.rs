@@ -0,0 +1,179 @@
+// A GlobalAlloc that counts what goes through it, so a test can say "this allocated nothing"
+// instead of trusting that a Cow stayed borrowed.
+//
+// Nothing uses it unless a binary or test file installs it:
+//
+//     #[global_allocator]
+//     static ALLOC: Tracking = Tracking::system();
+//
+// and then wraps the code to check in `measure`. Counts are kept per thread, so other tests
+// running at the same time don't end up in the numbers.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub struct Tracking<A = System> {
+    inner: A,
+}
+
+impl Tracking<System> {
+    pub const fn system() -> Tracking<System> {
+        Tracking { inner: System }
+    }
+}
+
+impl<A> Tracking<A> {
+    /// Counts on top of some other allocator.
+    pub const fn new(inner: A) -> Tracking<A> {
+        Tracking { inner }
+    }
+}
+
+// set the first time anything goes through a Tracking allocator
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Copy)]
+struct Totals {
+    allocations: usize,
+    deallocations: usize,
+    reallocations: usize,
+    allocated_bytes: usize,
+    live_bytes: isize,
+    peak_bytes: isize,
+}
+
+const ZERO: Totals = Totals {
+    allocations: 0,
+    deallocations: 0,
+    reallocations: 0,
+    allocated_bytes: 0,
+    live_bytes: 0,
+    peak_bytes: 0,
+};
+
+thread_local! {
+    // const and Copy so touching it never allocates, which would recurse into the allocator
+    static TOTALS: Cell<Totals> = const { Cell::new(ZERO) };
+}
+
+fn record(change: impl FnOnce(&mut Totals)) {
+    // try_with because the allocator is still called while a thread's locals are torn down
+    let _ = TOTALS.try_with(|totals| {
+        let mut t = totals.get();
+        change(&mut t);
+        t.peak_bytes = t.peak_bytes.max(t.live_bytes);
+        totals.set(t);
+    });
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for Tracking<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        INSTALLED.store(true, Ordering::Relaxed);
+        let ptr = self.inner.alloc(layout);
+        if !ptr.is_null() {
+            record(|t| {
+                t.allocations += 1;
+                t.allocated_bytes += layout.size();
+                t.live_bytes += layout.size() as isize;
+            });
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        INSTALLED.store(true, Ordering::Relaxed);
+        let ptr = self.inner.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            record(|t| {
+                t.allocations += 1;
+                t.allocated_bytes += layout.size();
+                t.live_bytes += layout.size() as isize;
+            });
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.inner.dealloc(ptr, layout);
+        record(|t| {
+            t.deallocations += 1;
+            t.live_bytes -= layout.size() as isize;
+        });
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new = self.inner.realloc(ptr, layout, new_size);
+        if !new.is_null() {
+            record(|t| {
+                t.reallocations += 1;
+                if new_size > layout.size() {
+                    t.allocated_bytes += new_size - layout.size();
+                }
+                t.live_bytes += new_size as isize - layout.size() as isize;
+            });
+        }
+        new
+    }
+}
+
+/// What one `measure` call saw on its thread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Counts {
+    pub allocations: usize,
+    pub deallocations: usize,
+    /// Blocks grown or shrunk in place of a new allocation, a Vec pushing past its capacity say.
+    pub reallocations: usize,
+    /// Bytes asked for, counting only the growth of a reallocation.
+    pub allocated_bytes: usize,
+    /// The most that was allocated and not yet freed at any one time.
+    pub peak_bytes: usize,
+    /// Allocated minus freed by the time `measure` returned, 0 if everything was cleaned up.
+    pub net_bytes: isize,
+}
+
+impl Counts {
+    /// No allocations or reallocations at all.
+    pub fn is_zero(&self) -> bool {
+        self.allocations == 0 && self.reallocations == 0
+    }
+}
+
+/// True once a Tracking allocator has been installed and used. Without one `measure` always
+/// reports zeros.
+pub fn installed() -> bool {
+    INSTALLED.load(Ordering::Relaxed)
+}
+
+/// Runs `f` and counts the allocations it makes on this thread. Nests: an outer measure sees
+/// everything an inner one does.
+pub fn measure<R, F: FnOnce() -> R>(f: F) -> (R, Counts) {
+    let before = TOTALS.with(|totals| {
+        let before = totals.get();
+        // peak from here on, the outer peak is put back below
+        totals.set(Totals {
+            peak_bytes: before.live_bytes,
+            ..before
+        });
+        before
+    });
+    let result = f();
+    let after = TOTALS.with(|totals| {
+        let after = totals.get();
+        totals.set(Totals {
+            peak_bytes: after.peak_bytes.max(before.peak_bytes),
+            ..after
+        });
+        after
+    });
+
+    let counts = Counts {
+        allocations: after.allocations - before.allocations,
+        deallocations: after.deallocations - before.deallocations,
+        reallocations: after.reallocations - before.reallocations,
+        allocated_bytes: after.allocated_bytes - before.allocated_bytes,
+        peak_bytes: (after.peak_bytes - before.live_bytes).max(0) as usize,
+        net_bytes: after.live_bytes - before.live_bytes,
+    };
+    (result, counts)
+}
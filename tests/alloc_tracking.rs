@@ -0,0 +1,136 @@
+use rust_book::other_smart_pointers::abs_all;
+use rust_book::other_smart_pointers::cow_collections::{CowStr, CowVec};
+use rust_book::other_smart_pointers::normalize::{Pipeline, Step};
+use rust_book::other_smart_pointers::tracking_alloc::{installed, measure, Tracking};
+use std::borrow::Cow;
+
+#[global_allocator]
+static ALLOC: Tracking = Tracking::system();
+
+#[test]
+fn counts_a_vec() {
+    let (_, counts) = measure(|| {
+        let mut v: Vec<u8> = Vec::with_capacity(1000);
+        v.extend(0..10);
+        let w = vec![0u64; 100];
+        drop(w);
+        v.len()
+    });
+    assert!(installed());
+    assert_eq!(2, counts.allocations);
+    assert_eq!(2, counts.deallocations);
+    assert_eq!(1800, counts.allocated_bytes);
+    assert_eq!(1800, counts.peak_bytes);
+    assert_eq!(0, counts.net_bytes);
+}
+
+#[test]
+fn growth_and_leftovers() {
+    let (kept, counts) = measure(|| {
+        let mut v: Vec<u32> = Vec::with_capacity(4);
+        v.extend(0..5);
+        v
+    });
+    assert_eq!(1, counts.allocations);
+    assert_eq!(1, counts.reallocations);
+    assert_eq!(0, counts.deallocations);
+    assert_eq!(kept.capacity() as isize * 4, counts.net_bytes);
+    assert_eq!(counts.net_bytes as usize, counts.peak_bytes);
+}
+
+#[test]
+fn nested_measures_add_up() {
+    let ((_, inner), outer) = measure(|| {
+        let a = Box::new(1u64);
+        let inner = measure(|| Box::new([0u8; 64]));
+        drop(a);
+        inner
+    });
+    assert_eq!(1, inner.allocations);
+    assert_eq!(64, inner.peak_bytes);
+    assert_eq!(2, outer.allocations);
+    assert_eq!(72, outer.peak_bytes);
+    assert_eq!(1, outer.deallocations);
+}
+
+#[test]
+fn cow_only_clones_when_it_has_to() {
+    let positives = [0, 1, 2];
+    let (input, counts) = measure(|| {
+        let mut input = Cow::from(&positives[..]);
+        abs_all(&mut input);
+        input
+    });
+    assert!(counts.is_zero());
+    assert!(matches!(input, Cow::Borrowed(_)));
+
+    let mixed = [-1, 0, 1];
+    let (input, counts) = measure(|| {
+        let mut input = Cow::from(&mixed[..]);
+        abs_all(&mut input);
+        input
+    });
+    assert_eq!(1, counts.allocations);
+    assert_eq!(12, counts.allocated_bytes);
+    assert_eq!(&[1, 0, 1][..], &*input);
+
+    // already owned, the vec! was allocated before measuring and nothing gets cloned
+    let mut owned = Cow::from(vec![-1, 0, 1]);
+    let (_, counts) = measure(|| abs_all(&mut owned));
+    assert!(counts.is_zero());
+    assert!(matches!(owned, Cow::Owned(_)));
+}
+
+#[test]
+fn other_threads_are_not_counted() {
+    let handle = std::thread::spawn(|| {
+        let (_, counts) = measure(|| vec![0u8; 10]);
+        counts.allocations
+    });
+    let (_, counts) = measure(|| ());
+    assert_eq!(0, counts.allocations);
+    assert_eq!(1, handle.join().unwrap());
+}
+
+#[test]
+fn cow_collections_allocate_on_first_change_only() {
+    let data = [1, 2, 3, 4];
+    let (v, counts) = measure(|| {
+        let mut v = CowVec::from(&data[..]);
+        v.sort();
+        v.dedup();
+        v.retain(|&n| n < 10);
+        v.truncate(3);
+        v
+    });
+    assert!(counts.is_zero());
+    assert!(v.is_borrowed());
+
+    let (s, counts) = measure(|| {
+        let mut s = CowStr::from("one two");
+        s.replace("three", "3");
+        s.retain(|ch| ch != ' ');
+        s
+    });
+    assert_eq!(1, counts.allocations);
+    assert_eq!("onetwo", s.as_str());
+}
+
+#[test]
+fn normalizing_clean_text_allocates_nothing() {
+    let pipeline = Pipeline::new()
+        .then(Step::LineEndings)
+        .then(Step::Trim)
+        .then(Step::CollapseWhitespace)
+        .then(Step::FoldCase)
+        .then(Step::EscapeHtml);
+
+    let (out, counts) = measure(|| pipeline.apply("  nothing to do here\n"));
+    assert!(counts.is_zero());
+    assert_eq!("nothing to do here", out);
+
+    let (out, counts) = measure(|| pipeline.apply("Two  changes"));
+    assert_eq!(2, counts.allocations);
+    assert_eq!(1, counts.deallocations);
+    assert_eq!("two changes", out);
+}
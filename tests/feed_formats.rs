@@ -0,0 +1,101 @@
+use rust_book::chapters::chapter_10::feed::Feed;
+use rust_book::chapters::chapter_10::formats::{self, FeedItem, Format};
+use rust_book::chapters::chapter_10::Summary;
+use std::path::PathBuf;
+
+// the three fixtures hold the same four items, one file per format
+fn fixture(name: &str) -> PathBuf {
+    [
+        env!("CARGO_MANIFEST_DIR"),
+        "tests",
+        "fixtures",
+        "feeds",
+        name,
+    ]
+    .iter()
+    .collect()
+}
+
+fn expected() -> Vec<FeedItem> {
+    formats::load(fixture("penguins.json")).unwrap()
+}
+
+#[test]
+fn every_format_reads_the_same_items() {
+    let items = expected();
+    assert_eq!(4, items.len());
+    match &items[0] {
+        FeedItem::Article(article) => {
+            assert_eq!("Iceburgh", article.author);
+            assert_eq!("Pittsburgh, PA, USA", article.location);
+            assert_eq!(1_622_548_800, article.timestamp);
+        }
+        other => panic!("expected an article, got {:?}", other),
+    }
+
+    assert_eq!(items, formats::load(fixture("penguins.rss")).unwrap());
+    assert_eq!(items, formats::load(fixture("penguins.atom")).unwrap());
+}
+
+#[test]
+fn every_format_round_trips() {
+    let items = expected();
+    for &format in &[Format::Json, Format::Rss, Format::Atom] {
+        let written = formats::write(&items, format, "Penguins").unwrap();
+        assert_eq!(Some(format), Format::sniff(&written));
+        assert_eq!(
+            items,
+            formats::parse(&written, format).unwrap(),
+            "{:?}",
+            format
+        );
+    }
+}
+
+#[test]
+fn saves_by_extension() {
+    let dir = std::env::temp_dir().join(format!("feed_formats_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let items = expected();
+
+    for name in &["out.rss", "out.atom", "out.json"] {
+        let path = dir.join(name);
+        formats::save(&path, &items, "Penguins").unwrap();
+        assert_eq!(items, formats::load(&path).unwrap());
+    }
+    assert!(formats::save(dir.join("out.txt"), &items, "Penguins").is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn bad_input_is_an_error() {
+    assert!(formats::parse("{\"type\": \"poem\"}", Format::Json).is_err());
+    assert!(formats::parse("<rss><channel>", Format::Rss).is_err());
+    let bad_date = "<rss version=\"2.0\"><channel><title>t</title>\
+                    <item><pubDate>yesterday</pubDate></item></channel></rss>";
+    assert_eq!(
+        "can't read the date \"yesterday\"",
+        formats::parse(bad_date, Format::Rss)
+            .unwrap_err()
+            .to_string()
+    );
+}
+
+#[test]
+fn imported_items_feed_the_digest() {
+    let feed: Feed = formats::load(fixture("penguins.atom"))
+        .unwrap()
+        .into_iter()
+        .map(|item| Box::new(item) as Box<dyn Summary>)
+        .collect();
+
+    assert_eq!(
+        "Digest (3 items)\n\
+         1. [2021-06-01 13:00] horse_ebooks of course, as you probably already know, people \
+         (reposted by penguin_fan)\n\
+         2. [2021-06-01 12:00] Penguins win the Stanley Cup Championship! Iceburgh Pittsburgh, PA, USA\n\
+         3. [undated] critic Cup <b>again</b>? Sure & why not\n",
+        feed.digest().to_text()
+    );
+}